@@ -6,12 +6,15 @@ use bumpalo::Bump;
 
 use arrayvec::ArrayVec;
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct BSDF<'a> {
     pub p: Point3f,
     pub ns: Normal3f,
     pub ng: Normal3f,
     pub tan: Vec3f,
     pub bitan: Vec3f,
+    // Relative index of refraction across the surface, one for opaque materials
+    pub eta: Float,
     bxdfs: ArrayVec<[&'a mut dyn BxDF; 8]>,
 }
 
@@ -26,6 +29,7 @@ impl<'a> BSDF<'a> {
             ng: hit.int.normal,
             bitan: bitan.into(),
             tan: tan.into(),
+            eta: 1.0,
             bxdfs: ArrayVec::new(),
         }
     }
@@ -76,12 +80,17 @@ impl<'a> BSDF<'a> {
             return empty_rv;
         }
 
+        // The first sample picks the component and is then stretched back to [0, 1) for it
         let component =
-            ((samples.0.floor() * num_matching as Float) as usize).min(num_matching - 1);
+            ((samples.0 * num_matching as Float).floor() as usize).min(num_matching - 1);
         let bxdf = self.match_at(types, component);
+        let remapped = (
+            (samples.0 * num_matching as Float - component as Float).min(1.0 - Float::EPSILON),
+            samples.1,
+        );
 
         let wo_local = self.vec_to_shading(wo).normalized();
-        let (mut spectrum, wi_local, mut pdf) = bxdf.sample(wo_local, samples);
+        let (mut spectrum, wi_local, mut pdf) = bxdf.sample(wo_local, remapped);
         if wi_local.length_squared() == 0.0 {
             return empty_rv;
         }
@@ -93,9 +102,10 @@ impl<'a> BSDF<'a> {
                 pdf += self
                     .bxdfs
                     .iter()
+                    .filter(|bxdf| bxdf.matches(types))
                     .enumerate()
-                    .filter(|(i, bxdf)| *i != component && bxdf.matches(types))
-                    .map(|(_, bxdf)| bxdf.pdf(wo_local, wi_local))
+                    .filter(|(i, _)| *i != component)
+                    .map(|(_, bxdf)| bxdf.pdf(wi_local, wo_local))
                     .sum::<Float>();
             }
 
@@ -107,7 +117,7 @@ impl<'a> BSDF<'a> {
                 .bxdfs
                 .iter()
                 .filter(|bxdf| bxdf.matches(flags))
-                .map(|bxdf| bxdf.eval(wo_local, wi_local))
+                .map(|bxdf| bxdf.eval(wi_local, wo_local))
                 .sum()
         }
 
@@ -115,7 +125,9 @@ impl<'a> BSDF<'a> {
             pdf /= num_matching as Float;
         }
 
-        (spectrum, wi, pdf, bxdf.get_type())
+        // BxDFs with both reflection and transmission report the one that was sampled
+        let sampled = bxdf.get_type().for_hemisphere(wo_local, wi_local);
+        (spectrum, wi, pdf, sampled)
     }
 
     pub fn eval(&self, wo: Vec3f, wi: Vec3f, flags: BxDFType) -> Spectrum {
@@ -128,7 +140,7 @@ impl<'a> BSDF<'a> {
         self.bxdfs
             .iter()
             .filter(|bxdf| bxdf.matches(flags))
-            .map(|bxdf| bxdf.eval(wo_local, wi_local))
+            .map(|bxdf| bxdf.eval(wi_local, wo_local))
             .sum()
     }
 
@@ -140,9 +152,8 @@ impl<'a> BSDF<'a> {
             .bxdfs
             .iter()
             .filter(|bxdf| bxdf.matches(flags))
-            .map(|bxdf| bxdf.pdf(wo_local, wi_local))
-            .enumerate()
-            .fold((0, 0.0), |(_, pdf_acc), (i, pdf)| (i, pdf_acc + pdf));
+            .map(|bxdf| bxdf.pdf(wi_local, wo_local))
+            .fold((0, 0.0), |(n, pdf_acc), pdf| (n + 1, pdf_acc + pdf));
 
         if n_components > 0 {
             pdf / n_components as Float
@@ -7,6 +7,8 @@ fn dielectric(cos_i: Float, eta_i: Float, cos_t: Float, eta_t: Float) -> Float {
     0.5 * (r_par * r_par + r_perp * r_perp)
 }
 
+// Metals aren't modelled by any material yet
+#[allow(dead_code)]
 fn conductor(cos_i: Float, eta: &Spectrum, k: &Spectrum) -> Spectrum {
     let b = *eta * *eta + *k * *k;
     let a = b * cos_i * cos_i;
@@ -46,6 +48,7 @@ impl Fresnel for Dielectric {
     }
 }
 
+#[allow(dead_code)]
 #[derive(new, Copy, Clone)]
 pub struct Conductor {
     pub eta: Spectrum,
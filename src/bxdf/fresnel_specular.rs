@@ -0,0 +1,64 @@
+use crate::bxdf::fresnel::{Dielectric, Fresnel};
+use crate::bxdf::BxDF;
+use crate::bxdf::BxDFType;
+use crate::film::spectrum::Spectrum;
+use crate::math::*;
+
+// Perfectly smooth dielectric boundary. Sampling picks reflection or refraction in proportion
+// to the Fresnel reflectance, so light that can't get out is all reflected.
+#[derive(new)]
+pub struct FresnelSpecular {
+    r: Spectrum,
+    t: Spectrum,
+    fresnel: Dielectric,
+}
+
+impl BxDF for FresnelSpecular {
+    fn get_type(&self) -> BxDFType {
+        BxDFType::REFLECTION | BxDFType::TRANSMISSION | BxDFType::SPECULAR
+    }
+
+    fn eval(&self, _wi: ShadingVec3f, _wo: ShadingVec3f) -> Spectrum {
+        Spectrum::all(0.0)
+    }
+
+    fn sample(&self, wo: ShadingVec3f, samples: (Float, Float)) -> (Spectrum, ShadingVec3f, Float) {
+        let empty_rv = (Spectrum::all(0.0), ShadingVec3f::new(0.0, 0.0, 0.0), 0.0);
+        let f = self.fresnel.fresnel(wo.cos_theta()).y();
+
+        if samples.0 < f {
+            let wi = ShadingVec3f::new(-wo.x, -wo.y, wo.z);
+            if wi.cos_theta() == 0.0 {
+                return empty_rv;
+            }
+            (self.r * f / wi.cos_theta().abs(), wi, f)
+        } else {
+            let entering = wo.cos_theta() > 0.0;
+            let (eta_i, eta_t, n) = if entering {
+                (
+                    self.fresnel.eta_i,
+                    self.fresnel.eta_t,
+                    ShadingVec3f::new(0.0, 0.0, 1.0),
+                )
+            } else {
+                (
+                    self.fresnel.eta_t,
+                    self.fresnel.eta_i,
+                    ShadingVec3f::new(0.0, 0.0, -1.0),
+                )
+            };
+            let wi = match ShadingVec3f::refract(wo, n, eta_i / eta_t) {
+                Some(wi) if wi.cos_theta() != 0.0 => wi,
+                _ => return empty_rv,
+            };
+
+            // Radiance is squeezed into a smaller solid angle on the denser side
+            let t = self.t * (1.0 - f) * ((eta_i * eta_i) / (eta_t * eta_t));
+            (t / wi.cos_theta().abs(), wi, 1.0 - f)
+        }
+    }
+
+    fn pdf(&self, _wi: ShadingVec3f, _wo: ShadingVec3f) -> Float {
+        0.0
+    }
+}
@@ -41,11 +41,36 @@ impl MicrofacetDistribution for Beckmann {
         }
     }
 
-    fn sample(&self, _wo: ShadingVec3f, _sample: (Float, Float)) -> ShadingVec3f {
-        unimplemented!()
+    // Samples the whole distribution rather than just the normals visible from wo
+    fn sample(&self, wo: ShadingVec3f, sample: (Float, Float)) -> ShadingVec3f {
+        let (ax, ay) = (self.alpha_x, self.alpha_y);
+        let log_sample = (1.0 - sample.0).ln();
+
+        let (tan_2_theta, phi) = if ax == ay {
+            (-ax * ax * log_sample, 2.0 * Float::PI() * sample.1)
+        } else {
+            let mut phi =
+                (ay / ax * (2.0 * Float::PI() * sample.1 + 0.5 * Float::PI()).tan()).atan();
+            if sample.1 > 0.5 {
+                phi += Float::PI();
+            }
+            let (sin_phi, cos_phi) = phi.sin_cos();
+            let tan_2_theta =
+                -log_sample / (cos_phi * cos_phi / (ax * ax) + sin_phi * sin_phi / (ay * ay));
+            (tan_2_theta, phi)
+        };
+
+        let cos_theta = 1.0 / (1.0 + tan_2_theta).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let wh = ShadingVec3f::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        if wo.same_hemisphere(wh) {
+            wh
+        } else {
+            -wh
+        }
     }
 
-    fn pdf(&self, _wo: ShadingVec3f, _wh: ShadingVec3f) -> Float {
-        unimplemented!()
+    fn pdf(&self, _wo: ShadingVec3f, wh: ShadingVec3f) -> Float {
+        self.distribution(wh) * wh.cos_theta().abs()
     }
 }
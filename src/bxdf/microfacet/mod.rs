@@ -29,3 +29,17 @@ pub fn roughness_to_alpha(r: Float) -> Float {
         + 0.017_120_1 * x.powi(3)
         + 0.000_640_711 * x.powi(4)
 }
+
+// Inverse of roughness_to_alpha for roughness in [1e-3, 1], found by bisection
+pub fn alpha_to_roughness(alpha: Float) -> Float {
+    let (mut lo, mut hi) = ((1e-3 as Float).ln(), 0.0);
+    for _ in 0..32 {
+        let mid = 0.5 * (lo + hi);
+        if roughness_to_alpha(mid.exp()) < alpha {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (0.5 * (lo + hi)).exp()
+}
@@ -39,8 +39,11 @@ impl MicrofacetDistribution for TrowbridgeReitz {
 
     // Sampling method from http://jcgt.org/published/0007/04/01/paper.pdf#page=10
     // vec3 sampleGGXVNDF(vec3 Ve, float alpha_x, float alpha_y, float U1, float U2)
+    // The method expects wo above the surface, so one below is flipped along with the result
     fn sample(&self, wo: ShadingVec3f, samples: (Float, Float)) -> ShadingVec3f {
         let (ax, ay) = (self.alpha_x, self.alpha_y);
+        let flip = wo.z < 0.0;
+        let wo = if flip { -wo } else { wo };
 
         // Section 3.2: transforming the view direction to the hemisphere configuration
         // vec3 Vh = normalize(vec3(alpha_x * Ve.x, alpha_y * Ve.y, Ve.z));
@@ -49,12 +52,12 @@ impl MicrofacetDistribution for TrowbridgeReitz {
         // Section 4.1: orthonormal basis
         // vec3 T1 = (Vh.z < 0.9999) ? normalize(cross(vec3(0, 0, 1), Vh)) : vec3(1, 0, 0);
         let t1_v = if vh.z < 0.9999 {
-            ShadingVec3f::new(0.0, 0.0, 0.1).cross(vh).normalized()
+            ShadingVec3f::new(0.0, 0.0, 1.0).cross(vh).normalized()
         } else {
             ShadingVec3f::new(1.0, 0.0, 0.0)
         };
         // vec3 T2 = cross(Vh, T1);
-        let t2_v = wo.cross(t1_v);
+        let t2_v = vh.cross(t1_v);
 
         // Section 4.2: parameterization of the projected area
         // float r = sqrt(U1);
@@ -76,7 +79,12 @@ impl MicrofacetDistribution for TrowbridgeReitz {
 
         // Section 3.4: transforming the normal back to the ellipsoid configuration
         // return normalize(vec3(alpha_x * Nh.x, alpha_y * Nh.y, std::max<float>(0.0, Nh.z)));
-        ShadingVec3f::new(ax * nh.x, ay * nh.y, nh.z.max(0.0)).normalized()
+        let wh = ShadingVec3f::new(ax * nh.x, ay * nh.y, nh.z.max(0.0)).normalized();
+        if flip {
+            -wh
+        } else {
+            wh
+        }
     }
 
     fn pdf(&self, wo: ShadingVec3f, wh: ShadingVec3f) -> Float {
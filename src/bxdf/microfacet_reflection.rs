@@ -26,7 +26,10 @@ impl BxDF for MicrofacetReflection<'_> {
             return Spectrum::all(0.0);
         }
 
-        let wh = wh.normalized();
+        // Facing the normal up keeps dielectrics seen from inside the right way round
+        let wh = wh
+            .normalized()
+            .face_forward(ShadingVec3f::new(0.0, 0.0, 1.0));
         let f = self.fresnel.fresnel(wi.dot(wh));
 
         self.r * self.distribution.distribution(wh) * self.distribution.g(wo, wi) * f
@@ -34,14 +37,22 @@ impl BxDF for MicrofacetReflection<'_> {
     }
 
     fn sample(&self, wo: ShadingVec3f, samples: (Float, Float)) -> (Spectrum, ShadingVec3f, Float) {
+        let empty_rv = (Spectrum::default(), ShadingVec3f::default(), 0.0);
+        if wo.cos_theta() == 0.0 {
+            return empty_rv;
+        }
+
         let wh = self.distribution.sample(wo, samples);
-        let wi = ShadingVec3f::reflect(wo, wo);
+        if wo.dot(wh) <= 0.0 {
+            return empty_rv;
+        }
+        let wi = ShadingVec3f::reflect(wo, wh);
         if !wo.same_hemisphere(wi) {
-            return (Spectrum::default(), ShadingVec3f::default(), 0.0);
+            return empty_rv;
         }
 
         let pdf = self.distribution.pdf(wo, wh) / (4.0 * wo.dot(wh));
-        (self.eval(wo, wi), wi, pdf)
+        (self.eval(wi, wo), wi, pdf)
     }
 
     fn pdf(&self, wi: ShadingVec3f, wo: ShadingVec3f) -> Float {
@@ -0,0 +1,113 @@
+use crate::bxdf::fresnel::{Dielectric, Fresnel};
+use crate::bxdf::microfacet::MicrofacetDistribution;
+use crate::bxdf::BxDF;
+use crate::bxdf::BxDFType;
+use crate::film::spectrum::Spectrum;
+use crate::math::*;
+
+// Light refracted through a rough dielectric boundary, the transmitted part of Walter et al.'s
+// microfacet model. Directions on either side are tied together by the generalized half vector
+// wo + eta * wi, with eta the index on wi's side over the index on wo's side.
+#[derive(new)]
+pub struct MicrofacetTransmission<'a> {
+    t: Spectrum,
+    distribution: &'a dyn MicrofacetDistribution,
+    fresnel: Dielectric,
+}
+
+impl MicrofacetTransmission<'_> {
+    fn eta(&self, wo: ShadingVec3f) -> Float {
+        if wo.cos_theta() > 0.0 {
+            self.fresnel.eta_t / self.fresnel.eta_i
+        } else {
+            self.fresnel.eta_i / self.fresnel.eta_t
+        }
+    }
+
+    // The half vector facing up, or None if it faces away from wo or wi, since light can't
+    // refract between them through the back of a microfacet
+    fn half_vector(&self, wo: ShadingVec3f, wi: ShadingVec3f) -> Option<ShadingVec3f> {
+        let wh = wo + wi * self.eta(wo);
+        if wh.length_squared() == 0.0 {
+            return None;
+        }
+        let wh = wh
+            .normalized()
+            .face_forward(ShadingVec3f::new(0.0, 0.0, 1.0));
+        if wo.dot(wh) * wo.cos_theta() <= 0.0 || wi.dot(wh) * wi.cos_theta() <= 0.0 {
+            None
+        } else {
+            Some(wh)
+        }
+    }
+}
+
+impl BxDF for MicrofacetTransmission<'_> {
+    fn get_type(&self) -> BxDFType {
+        BxDFType::TRANSMISSION | BxDFType::GLOSSY
+    }
+
+    fn eval(&self, wi: ShadingVec3f, wo: ShadingVec3f) -> Spectrum {
+        if wo.same_hemisphere(wi) {
+            return Spectrum::all(0.0);
+        }
+
+        let cos_theta_o = wo.cos_theta();
+        let cos_theta_i = wi.cos_theta();
+        if cos_theta_i == 0.0 || cos_theta_o == 0.0 {
+            return Spectrum::all(0.0);
+        }
+
+        let wh = match self.half_vector(wo, wi) {
+            Some(wh) => wh,
+            None => return Spectrum::all(0.0),
+        };
+
+        let eta = self.eta(wo);
+        let f = self.fresnel.fresnel(wo.dot(wh));
+        let sqrt_denom = wo.dot(wh) + eta * wi.dot(wh);
+
+        // Radiance is squeezed into a smaller solid angle on the denser side, which cancels
+        // the eta squared of the Jacobian
+        let value = self.distribution.distribution(wh)
+            * self.distribution.g(wo, wi)
+            * wi.dot(wh).abs()
+            * wo.dot(wh).abs()
+            / (cos_theta_i * cos_theta_o * sqrt_denom * sqrt_denom);
+        (Spectrum::all(1.0) - f) * self.t * value.abs()
+    }
+
+    fn sample(&self, wo: ShadingVec3f, samples: (Float, Float)) -> (Spectrum, ShadingVec3f, Float) {
+        let empty_rv = (Spectrum::default(), ShadingVec3f::default(), 0.0);
+        if wo.cos_theta() == 0.0 {
+            return empty_rv;
+        }
+
+        let wh = self.distribution.sample(wo, samples);
+        if wo.dot(wh) <= 0.0 {
+            return empty_rv;
+        }
+
+        let wi = match ShadingVec3f::refract(wo, wh, 1.0 / self.eta(wo)) {
+            Some(wi) => wi,
+            None => return empty_rv,
+        };
+        (self.eval(wi, wo), wi, self.pdf(wi, wo))
+    }
+
+    fn pdf(&self, wi: ShadingVec3f, wo: ShadingVec3f) -> Float {
+        if wo.same_hemisphere(wi) {
+            return 0.0;
+        }
+        let wh = match self.half_vector(wo, wi) {
+            Some(wh) => wh,
+            None => return 0.0,
+        };
+
+        // Change of variables from the half vector to wi
+        let eta = self.eta(wo);
+        let sqrt_denom = wo.dot(wh) + eta * wi.dot(wh);
+        let dwh_dwi = (eta * eta * wi.dot(wh)).abs() / (sqrt_denom * sqrt_denom);
+        self.distribution.pdf(wo, wh) * dwh_dwi
+    }
+}
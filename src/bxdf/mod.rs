@@ -4,9 +4,11 @@ use num::traits::float::FloatConst;
 
 pub mod bsdf;
 pub mod fresnel;
+pub mod fresnel_specular;
 pub mod lambertian;
 pub mod microfacet;
 pub mod microfacet_reflection;
+pub mod microfacet_transmission;
 pub mod oren_nayar;
 pub mod specular_reflection;
 
@@ -102,3 +104,144 @@ pub trait BxDF {
             / (samples_a.len() as Float * Float::PI())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::fresnel::Dielectric;
+    use super::microfacet::{
+        beckmann::Beckmann, trowbridge_reitz::TrowbridgeReitz, MicrofacetDistribution,
+    };
+    use super::microfacet_reflection::MicrofacetReflection;
+    use super::microfacet_transmission::MicrofacetTransmission;
+    use super::*;
+
+    const GLASS: Dielectric = Dielectric {
+        eta_i: 1.0,
+        eta_t: 1.5,
+    };
+
+    fn distributions() -> Vec<(&'static str, Box<dyn MicrofacetDistribution>)> {
+        vec![
+            ("trowbridge_reitz", Box::new(TrowbridgeReitz::new(0.3, 0.5))),
+            ("beckmann", Box::new(Beckmann::new(0.3, 0.5))),
+        ]
+    }
+
+    fn direction(cos_theta: Float, phi: Float) -> ShadingVec3f {
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        ShadingVec3f::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+    }
+
+    // Outgoing directions from near the normal to grazing, above and below the surface
+    fn outgoing() -> Vec<ShadingVec3f> {
+        [0.9, 0.5, 0.2, -0.3, -0.7]
+            .iter()
+            .map(|&cos_theta| direction(cos_theta, 0.7))
+            .collect()
+    }
+
+    // Midpoint rule over the sphere, uniform in cos theta and phi
+    fn integrate(f: impl Fn(ShadingVec3f) -> Float) -> Float {
+        let (n_theta, n_phi) = (400, 400);
+        let mut sum = 0.0;
+        for i in 0..n_theta {
+            let cos_theta = -1.0 + 2.0 * (i as Float + 0.5) / n_theta as Float;
+            for j in 0..n_phi {
+                let phi = 2.0 * Float::PI() * (j as Float + 0.5) / n_phi as Float;
+                sum += f(direction(cos_theta, phi));
+            }
+        }
+        sum * 4.0 * Float::PI() / (n_theta * n_phi) as Float
+    }
+
+    fn samples() -> impl Iterator<Item = (Float, Float)> {
+        (0..32).flat_map(|i| {
+            (0..32).map(move |j| ((i as Float + 0.5) / 32.0, (j as Float + 0.5) / 32.0))
+        })
+    }
+
+    fn check_bxdf(name: &str, bxdf: &dyn BxDF, wo: ShadingVec3f) {
+        let total = integrate(|wi| bxdf.pdf(wi, wo));
+        assert!(
+            total <= 1.02,
+            "{} pdf integrates to {} for {:?}",
+            name,
+            total,
+            wo
+        );
+
+        // Sampling reports the same value and density as evaluating the sampled direction
+        for sample in samples() {
+            let (f, wi, pdf) = bxdf.sample(wo, sample);
+            if pdf == 0.0 {
+                continue;
+            }
+            let expected = bxdf.pdf(wi, wo);
+            assert!(
+                (pdf - expected).abs() <= 1e-3 * expected.max(1.0),
+                "{} sampled pdf {} but pdf() gives {}",
+                name,
+                pdf,
+                expected
+            );
+            assert!((f.y() - bxdf.eval(wi, wo).y()).abs() <= 1e-4 * f.y().max(1.0));
+        }
+    }
+
+    #[test]
+    fn microfacet_reflection() {
+        for (name, distribution) in distributions() {
+            let bxdf = MicrofacetReflection::new(Spectrum::all(1.0), distribution.as_ref(), &GLASS);
+            for wo in outgoing() {
+                check_bxdf(name, &bxdf, wo);
+
+                // Reflection is symmetric in its two directions
+                for wi in outgoing() {
+                    let (a, b) = (bxdf.eval(wi, wo).y(), bxdf.eval(wo, wi).y());
+                    assert!(
+                        (a - b).abs() <= 1e-4 * a.max(1.0),
+                        "{} {} vs {}",
+                        name,
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn microfacet_transmission() {
+        for (name, distribution) in distributions() {
+            let bxdf =
+                MicrofacetTransmission::new(Spectrum::all(1.0), distribution.as_ref(), GLASS);
+            for wo in outgoing() {
+                check_bxdf(name, &bxdf, wo);
+
+                // From outside the glass every visible microfacet refracts, so sampling the
+                // visible normals loses nothing
+                if name == "trowbridge_reitz" && wo.cos_theta() > 0.0 {
+                    let total = integrate(|wi| bxdf.pdf(wi, wo));
+                    assert!(total >= 0.98, "pdf integrates to {} for {:?}", total, wo);
+                }
+
+                // Radiance transport isn't symmetric across the boundary: swapping the
+                // directions scales the value by the squared ratio of the indices
+                for wi in outgoing() {
+                    if wo.same_hemisphere(wi) {
+                        continue;
+                    }
+                    let eta = if wo.cos_theta() > 0.0 { 1.5 } else { 1.0 / 1.5 };
+                    let (a, b) = (bxdf.eval(wi, wo).y(), bxdf.eval(wo, wi).y());
+                    assert!(
+                        (a * eta * eta - b).abs() <= 1e-3 * b.max(1.0),
+                        "{} {} vs {}",
+                        name,
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use rt::film::image::{ImageFormat, Tonemap};
+use rt::math::Float;
+use rt::settings::{FilterSettings, IntegratorSettings, RenderSettings};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub enum Command {
+    Render(RenderOptions),
+    Check { scene: PathBuf },
+}
+
+pub struct RenderOptions {
+    pub scene: PathBuf,
+    pub output: PathBuf,
+    pub format: ImageFormat,
+    pub tonemap: Tonemap,
+    pub exposure: Float,
+    pub threads: usize,
+    resolution: Option<(i32, i32)>,
+    spp: Option<u32>,
+    integrator: Option<String>,
+    min_depth: Option<i32>,
+    max_depth: Option<i32>,
+    filter: Option<String>,
+    filter_radius: Option<Float>,
+}
+
+fn app() -> App<'static, 'static> {
+    let scene_arg = Arg::with_name("SCENE")
+        .required(true)
+        .help("Scene description file");
+
+    App::new("rt")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Rust CPU ray tracer")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
+        .subcommand(
+            SubCommand::with_name("render")
+                .about("Renders a scene file to an image")
+                .arg(scene_arg.clone())
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("PATH")
+                        .default_value("out")
+                        .help("Output image path; the extension is set by the format"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["png", "exr"])
+                        .help("Output image format [default: png]"),
+                )
+                .arg(
+                    Arg::with_name("resolution")
+                        .short("r")
+                        .long("resolution")
+                        .value_name("WIDTHxHEIGHT")
+                        .help("Overrides the film resolution, e.g. 1280x720"),
+                )
+                .arg(
+                    Arg::with_name("spp")
+                        .short("s")
+                        .long("spp")
+                        .value_name("N")
+                        .help("Overrides the number of samples per pixel"),
+                )
+                .arg(
+                    Arg::with_name("integrator")
+                        .short("i")
+                        .long("integrator")
+                        .value_name("NAME")
+                        .possible_values(&["path", "whitted", "normals"])
+                        .help("Overrides the integrator"),
+                )
+                .arg(
+                    Arg::with_name("min-depth")
+                        .long("min-depth")
+                        .value_name("N")
+                        .help("Bounces before russian roulette starts (path integrator only)"),
+                )
+                .arg(
+                    Arg::with_name("max-depth")
+                        .long("max-depth")
+                        .value_name("N")
+                        .help("Maximum number of bounces (path and whitted integrators)"),
+                )
+                .arg(
+                    Arg::with_name("filter")
+                        .long("filter")
+                        .value_name("NAME")
+                        .possible_values(&["mitchell", "triangle"])
+                        .help("Overrides the reconstruction filter"),
+                )
+                .arg(
+                    Arg::with_name("filter-radius")
+                        .long("filter-radius")
+                        .value_name("PIXELS")
+                        .help("Overrides the reconstruction filter radius"),
+                )
+                .arg(
+                    Arg::with_name("tonemap")
+                        .short("t")
+                        .long("tonemap")
+                        .value_name("OPERATOR")
+                        .possible_values(&["reinhard", "reinhard-burn", "filmic"])
+                        .help("Tonemapping operator for LDR output [default: filmic]"),
+                )
+                .arg(
+                    Arg::with_name("max-luminance")
+                        .long("max-luminance")
+                        .value_name("L")
+                        .help("Luminance mapped to white (reinhard-burn only)"),
+                )
+                .arg(
+                    Arg::with_name("exposure")
+                        .short("e")
+                        .long("exposure")
+                        .value_name("STOPS")
+                        .allow_hyphen_values(true)
+                        .help("Exposure adjustment in stops for LDR output [default: 0]"),
+                )
+                .arg(
+                    Arg::with_name("threads")
+                        .short("j")
+                        .long("threads")
+                        .value_name("N")
+                        .help("Number of render threads [default: one per core]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Parses a scene file and reports errors without rendering")
+                .arg(scene_arg),
+        )
+}
+
+pub fn parse() -> Result<Command, String> {
+    let matches = app().get_matches();
+
+    match matches.subcommand() {
+        ("render", Some(m)) => Ok(Command::Render(render_options(m)?)),
+        ("check", Some(m)) => Ok(Command::Check {
+            scene: PathBuf::from(m.value_of("SCENE").unwrap()),
+        }),
+        _ => unreachable!(),
+    }
+}
+
+fn value<T: FromStr>(m: &ArgMatches, name: &str) -> Result<Option<T>, String> {
+    match m.value_of(name) {
+        Some(v) => v
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid value '{}' for --{}", v, name)),
+        None => Ok(None),
+    }
+}
+
+fn positive<T: FromStr + PartialOrd + Default>(
+    m: &ArgMatches,
+    name: &str,
+) -> Result<Option<T>, String> {
+    match value::<T>(m, name)? {
+        Some(v) if v <= T::default() => Err(format!("--{} must be greater than zero", name)),
+        v => Ok(v),
+    }
+}
+
+fn parse_resolution(s: &str) -> Result<(i32, i32), String> {
+    let err = || format!("invalid resolution '{}', expected WIDTHxHEIGHT", s);
+    let mut parts = s.splitn(2, &['x', 'X'][..]);
+    let width = parts.next().ok_or_else(err)?.parse::<i32>();
+    let height = parts.next().ok_or_else(err)?.parse::<i32>();
+    match (width, height) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(err()),
+    }
+}
+
+fn render_options(m: &ArgMatches) -> Result<RenderOptions, String> {
+    let format = match m.value_of("format") {
+        #[cfg(feature = "hdr")]
+        Some("exr") => ImageFormat::Exr,
+        #[cfg(not(feature = "hdr"))]
+        Some("exr") => {
+            return Err("EXR output requires building with the 'hdr' feature".to_string());
+        }
+        _ => ImageFormat::Png,
+    };
+
+    if format != ImageFormat::Png {
+        for arg in &["tonemap", "exposure"] {
+            if m.is_present(arg) {
+                return Err(format!("--{} only applies to PNG output", arg));
+            }
+        }
+    }
+
+    let max_luminance = positive::<Float>(m, "max-luminance")?;
+    let tonemap = match (m.value_of("tonemap"), max_luminance) {
+        (Some("reinhard-burn"), Some(l)) => Tonemap::ReinhardBurn(l),
+        (Some("reinhard-burn"), None) => {
+            return Err("--tonemap reinhard-burn requires --max-luminance".to_string());
+        }
+        (_, Some(_)) => {
+            return Err("--max-luminance only applies to --tonemap reinhard-burn".to_string());
+        }
+        (Some("reinhard"), None) => Tonemap::Reinhard,
+        _ => Tonemap::HableFilmic,
+    };
+
+    let integrator = m.value_of("integrator").map(str::to_string);
+    if matches!(integrator.as_deref(), Some(i) if i != "path") && m.is_present("min-depth") {
+        return Err("--min-depth only applies to the path integrator".to_string());
+    }
+    if integrator.as_deref() == Some("normals") && m.is_present("max-depth") {
+        return Err("--max-depth doesn't apply to the normals integrator".to_string());
+    }
+
+    // Catch a bad output path before spending time on the render
+    let output = PathBuf::from(m.value_of("output").unwrap());
+    if let Some(dir) = output.parent().filter(|d| !d.as_os_str().is_empty()) {
+        if !dir.is_dir() {
+            return Err(format!(
+                "output directory '{}' doesn't exist",
+                dir.display()
+            ));
+        }
+    }
+
+    Ok(RenderOptions {
+        scene: PathBuf::from(m.value_of("SCENE").unwrap()),
+        output,
+        format,
+        tonemap,
+        exposure: value(m, "exposure")?.unwrap_or(0.0),
+        threads: positive(m, "threads")?.unwrap_or(0),
+        resolution: match m.value_of("resolution") {
+            Some(r) => Some(parse_resolution(r)?),
+            None => None,
+        },
+        spp: positive(m, "spp")?,
+        integrator,
+        min_depth: value(m, "min-depth")?,
+        max_depth: positive(m, "max-depth")?,
+        filter: m.value_of("filter").map(str::to_string),
+        filter_radius: positive(m, "filter-radius")?,
+    })
+}
+
+impl RenderOptions {
+    // Command line flags take precedence over the scene file
+    pub fn apply(&self, settings: &mut RenderSettings) -> Result<(), String> {
+        if let Some((width, height)) = self.resolution {
+            settings.width = width;
+            settings.height = height;
+        }
+
+        if let Some(spp) = self.spp {
+            settings.spp = spp;
+        }
+
+        settings.filter = self.filter_settings(settings.filter);
+
+        let (default_min, default_max) = match settings.integrator {
+            IntegratorSettings::Path {
+                min_depth,
+                max_depth,
+            } => (min_depth, max_depth),
+            IntegratorSettings::Whitted { max_depth } => (4, max_depth),
+            IntegratorSettings::Normals => (4, 8),
+        };
+
+        let integrator = match self.integrator.as_deref() {
+            Some(name) => name,
+            None => match settings.integrator {
+                IntegratorSettings::Path { .. } => "path",
+                IntegratorSettings::Whitted { .. } => "whitted",
+                IntegratorSettings::Normals => "normals",
+            },
+        };
+
+        settings.integrator = match integrator {
+            "path" => IntegratorSettings::Path {
+                min_depth: self.min_depth.unwrap_or(default_min),
+                max_depth: self.max_depth.unwrap_or(default_max),
+            },
+            "whitted" if self.min_depth.is_some() => {
+                return Err("--min-depth only applies to the path integrator".to_string());
+            }
+            "whitted" => IntegratorSettings::Whitted {
+                max_depth: self.max_depth.unwrap_or(default_max),
+            },
+            _ if self.min_depth.is_some() || self.max_depth.is_some() => {
+                return Err("the normals integrator doesn't take a depth".to_string());
+            }
+            _ => IntegratorSettings::Normals,
+        };
+
+        settings.integrator.validate()
+    }
+
+    // Switching the filter type keeps the radius the scene file set
+    fn filter_settings(&self, scene: FilterSettings) -> FilterSettings {
+        let filter = match (self.filter.as_deref(), scene) {
+            (Some("mitchell"), FilterSettings::Triangle { radius }) => {
+                FilterSettings::default().with_radius(radius)
+            }
+            (Some("triangle"), _) => FilterSettings::Triangle {
+                radius: scene.radius(),
+            },
+            _ => scene,
+        };
+
+        match self.filter_radius {
+            Some(radius) => filter.with_radius(radius),
+            None => filter,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> RenderOptions {
+        let mut argv = vec!["rt", "render", "test.scene"];
+        argv.extend_from_slice(args);
+        let matches = app().get_matches_from(argv);
+        render_options(matches.subcommand_matches("render").unwrap()).unwrap()
+    }
+
+    const SCENE_MITCHELL: FilterSettings = FilterSettings::Mitchell {
+        radius: 3.0,
+        b: 0.5,
+        c: 0.25,
+    };
+
+    #[test]
+    fn filter_type_keeps_scene_radius() {
+        match options(&["--filter", "triangle"]).filter_settings(SCENE_MITCHELL) {
+            FilterSettings::Triangle { radius } => assert_eq!(radius, 3.0),
+            f => panic!("expected a triangle filter, got {:?}", f),
+        }
+
+        let scene_triangle = FilterSettings::Triangle { radius: 1.5 };
+        match options(&["--filter", "mitchell"]).filter_settings(scene_triangle) {
+            FilterSettings::Mitchell { radius, .. } => assert_eq!(radius, 1.5),
+            f => panic!("expected a mitchell filter, got {:?}", f),
+        }
+    }
+
+    #[test]
+    fn same_filter_type_is_left_alone() {
+        match options(&["--filter", "mitchell"]).filter_settings(SCENE_MITCHELL) {
+            FilterSettings::Mitchell { radius, b, c } => {
+                assert_eq!((radius, b, c), (3.0, 0.5, 0.25))
+            }
+            f => panic!("expected a mitchell filter, got {:?}", f),
+        }
+    }
+
+    #[test]
+    fn filter_radius_overrides_scene() {
+        let opts = options(&["--filter", "triangle", "--filter-radius", "0.5"]);
+        match opts.filter_settings(SCENE_MITCHELL) {
+            FilterSettings::Triangle { radius } => assert_eq!(radius, 0.5),
+            f => panic!("expected a triangle filter, got {:?}", f),
+        }
+
+        match options(&["--filter-radius", "0.5"]).filter_settings(SCENE_MITCHELL) {
+            FilterSettings::Mitchell { radius, b, c } => {
+                assert_eq!((radius, b, c), (0.5, 0.5, 0.25))
+            }
+            f => panic!("expected a mitchell filter, got {:?}", f),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::math::*;
+
+// Brown-Conrady radial and tangential distortion, with coefficients as OpenCV calibrates them.
+// Works on normalized image coordinates (x / z, y / z) with y pointing down.
+#[derive(Debug, Copy, Clone)]
+pub struct BrownConrady {
+    pub k1: Float,
+    pub k2: Float,
+    pub k3: Float,
+    pub p1: Float,
+    pub p2: Float,
+}
+
+impl BrownConrady {
+    pub fn distort(&self, p: Point2f) -> Point2f {
+        let (x, y) = (p.x, p.y);
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+        Point2f::new(
+            x * radial + 2.0 * self.p1 * x * y + self.p2 * (r2 + 2.0 * x * x),
+            y * radial + self.p1 * (r2 + 2.0 * y * y) + 2.0 * self.p2 * x * y,
+        )
+    }
+
+    // There's no closed form inverse, so iterate like OpenCV's undistortPoints. Strong
+    // distortion can fold the image over near its edges, where this gives None.
+    pub fn undistort(&self, distorted: Point2f) -> Option<Point2f> {
+        let mut p = distorted;
+        for _ in 0..20 {
+            let (x, y) = (p.x, p.y);
+            let r2 = x * x + y * y;
+            let radial = 1.0 + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+            if radial <= 0.0 {
+                return None;
+            }
+
+            let dx = 2.0 * self.p1 * x * y + self.p2 * (r2 + 2.0 * x * x);
+            let dy = self.p1 * (r2 + 2.0 * y * y) + 2.0 * self.p2 * x * y;
+            p = Point2f::new((distorted.x - dx) / radial, (distorted.y - dy) / radial);
+        }
+
+        let check = self.distort(p);
+        let err = (check.x - distorted.x)
+            .abs()
+            .max((check.y - distorted.y).abs());
+        if err.is_finite() && err < 1e-3 {
+            Some(p)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Typical of a wide angle lens calibrated with OpenCV
+    const BARREL: BrownConrady = BrownConrady {
+        k1: -0.28,
+        k2: 0.07,
+        k3: 0.0,
+        p1: 0.0012,
+        p2: -0.0008,
+    };
+
+    fn grid() -> impl Iterator<Item = Point2f> {
+        (-4..=4)
+            .flat_map(|i| (-4..=4).map(move |j| Point2f::new(i as Float * 0.15, j as Float * 0.15)))
+    }
+
+    #[test]
+    fn undistort_inverts_distort() {
+        for lens in &[
+            BARREL,
+            BrownConrady {
+                k1: 0.1,
+                k2: 0.01,
+                k3: 0.001,
+                p1: -0.002,
+                p2: 0.003,
+            },
+        ] {
+            for p in grid() {
+                let q = lens.undistort(lens.distort(p)).unwrap();
+                assert!(
+                    (q.x - p.x).abs() < 1e-3 && (q.y - p.y).abs() < 1e-3,
+                    "{:?} came back as {:?}",
+                    p,
+                    q
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn zero_coefficients_are_identity() {
+        let lens = BrownConrady {
+            k1: 0.0,
+            k2: 0.0,
+            k3: 0.0,
+            p1: 0.0,
+            p2: 0.0,
+        };
+        for p in grid() {
+            let (d, u) = (lens.distort(p), lens.undistort(p).unwrap());
+            assert_eq!((d.x, d.y), (p.x, p.y));
+            assert_eq!((u.x, u.y), (p.x, p.y));
+        }
+    }
+
+    #[test]
+    fn folded_regions_have_no_inverse() {
+        // r (1 - r^2 / 2) never gets past about 0.54, so nothing distorts to radius 1
+        let lens = BrownConrady {
+            k1: -0.5,
+            k2: 0.0,
+            k3: 0.0,
+            p1: 0.0,
+            p2: 0.0,
+        };
+        assert!(lens.undistort(Point2f::new(0.5, 0.0)).is_some());
+        assert!(lens.undistort(Point2f::new(1.0, 0.0)).is_none());
+    }
+}
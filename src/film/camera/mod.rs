@@ -0,0 +1,86 @@
+use crate::math::*;
+
+pub mod projective;
+pub use projective::*;
+
+pub mod panoramic;
+pub use panoramic::*;
+
+pub mod distortion;
+pub use distortion::*;
+
+pub mod realistic;
+pub use realistic::*;
+
+#[derive(Copy, Clone)]
+pub struct CameraSample {
+    pub film: Point2f,
+    pub lens: Point2f,
+    pub time: Float,
+}
+
+pub trait Camera {
+    // A ray through the film sample with its weight, or None if the camera sees nothing there.
+    // A zero weight still counts towards the pixel, as black.
+    fn generate_ray(&self, sample: &CameraSample) -> Option<(Ray, Float)>;
+
+    // Also traces the rays one pixel over in x and y with the same lens and time samples, and
+    // leaves out the differentials if either of those misses the camera
+    fn generate_ray_differential(&self, sample: &CameraSample) -> Option<(RayDifferential, Float)> {
+        let (ray, weight) = self.generate_ray(sample)?;
+
+        let shifted = |dx: Float, dy: Float| {
+            let sample = CameraSample {
+                film: Point2f::new(sample.film.x + dx, sample.film.y + dy),
+                ..*sample
+            };
+            match self.generate_ray(&sample) {
+                Some((ray, weight)) if weight > 0.0 => Some(ray),
+                _ => None,
+            }
+        };
+
+        let info = match (shifted(1.0, 0.0), shifted(0.0, 1.0)) {
+            (Some(rx), Some(ry)) => Some(RayDifferentialInfo {
+                rx_origin: rx.o,
+                ry_origin: ry.o,
+                rx_direction: rx.d,
+                ry_direction: ry.d,
+            }),
+            _ => None,
+        };
+
+        Some((RayDifferential { ray, info }, weight))
+    }
+}
+
+// Where the camera is while the shutter is open, in the same units as keyframe times
+#[derive(Debug, Clone)]
+pub struct CameraMotion {
+    pub camera_to_world: AnimatedTransform,
+    pub shutter_open: Float,
+    pub shutter_close: Float,
+}
+
+impl CameraMotion {
+    pub fn time(&self, u: Float) -> Float {
+        lerp(u, self.shutter_open, self.shutter_close)
+    }
+
+    // Moves a camera space ray into the world at the ray's time
+    pub fn to_world(&self, ray: &mut Ray) {
+        let camera_to_world = self.camera_to_world.interpolate(ray.time);
+        ray.o = camera_to_world.apply_point(ray.o);
+        ray.d = camera_to_world.apply(ray.d);
+    }
+}
+
+impl From<Transform> for CameraMotion {
+    fn from(camera_to_world: Transform) -> Self {
+        Self {
+            camera_to_world: AnimatedTransform::from(camera_to_world),
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use super::{Camera, CameraMotion, CameraSample};
+use crate::film::Film;
+use crate::math::*;
+use num::traits::FloatConst;
+
+fn camera_ray(
+    motion: &CameraMotion,
+    o: Point3f,
+    dir: Vec3f,
+    sample: &CameraSample,
+) -> (Ray, Float) {
+    let mut ray = Ray::new(o, dir);
+    ray.time = motion.time(sample.time);
+    motion.to_world(&mut ray);
+    (ray, 1.0)
+}
+
+// Direction for a point in a latitude-longitude image, given as fractions of its width and
+// height, along with its longitude phi and angle from the up axis theta
+fn lat_long_direction(u: Float, v: Float) -> (Vec3f, Float, Float) {
+    let phi = (u - 0.5) * 2.0 * Float::PI();
+    let theta = v * Float::PI();
+    let dir = Vec3f::new(
+        theta.sin() * phi.sin(),
+        theta.cos(),
+        theta.sin() * phi.cos(),
+    );
+    (dir, phi, theta)
+}
+
+// Latitude-longitude panorama covering every direction. The middle of the image looks along
+// the camera's view direction, with the poles along the top and bottom edges.
+pub struct EquirectangularCamera {
+    motion: CameraMotion,
+    resolution: Point2f,
+}
+
+impl EquirectangularCamera {
+    pub fn new(motion: CameraMotion, film: &Film) -> Self {
+        Self {
+            motion,
+            resolution: Point2f::new(
+                film.full_resolution.x as Float,
+                film.full_resolution.y as Float,
+            ),
+        }
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn generate_ray(&self, sample: &CameraSample) -> Option<(Ray, Float)> {
+        let (dir, _, _) = lat_long_direction(
+            sample.film.x / self.resolution.x,
+            sample.film.y / self.resolution.y,
+        );
+        Some(camera_ray(&self.motion, Point3f::default(), dir, sample))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum FisheyeMapping {
+    // Distance from the center is proportional to the angle from the view direction
+    Equidistant,
+    // Area in the image is proportional to solid angle
+    Equisolid,
+}
+
+// Circular fisheye image inscribed in the film. The field of view is the full angle across
+// the circle and can be up to 360 degrees.
+pub struct FisheyeCamera {
+    motion: CameraMotion,
+    mapping: FisheyeMapping,
+    center: Point2f,
+    radius: Float,
+    max_theta: Float,
+}
+
+impl FisheyeCamera {
+    pub fn new(motion: CameraMotion, mapping: FisheyeMapping, fov_deg: Float, film: &Film) -> Self {
+        let (width, height) = (
+            film.full_resolution.x as Float,
+            film.full_resolution.y as Float,
+        );
+
+        Self {
+            motion,
+            mapping,
+            center: Point2f::new(width / 2.0, height / 2.0),
+            radius: width.min(height) / 2.0,
+            max_theta: fov_deg.to_radians() / 2.0,
+        }
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn generate_ray(&self, sample: &CameraSample) -> Option<(Ray, Float)> {
+        // Raster y points down and camera space y points up
+        let x = (sample.film.x - self.center.x) / self.radius;
+        let y = (self.center.y - sample.film.y) / self.radius;
+        let r = (x * x + y * y).sqrt();
+        if r > 1.0 {
+            return None;
+        }
+
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => r * self.max_theta,
+            FisheyeMapping::Equisolid => 2.0 * (r * (self.max_theta / 2.0).sin()).asin(),
+        };
+        let phi = y.atan2(x);
+
+        let dir = Vec3f::new(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        );
+        Some(camera_ray(&self.motion, Point3f::default(), dir, sample))
+    }
+}
+
+// Omni-directional stereo: a latitude-longitude panorama for each eye, stacked with the left
+// eye on top. Each ray starts on a circle the width of the interpupillary distance, offset
+// sideways from the direction it looks in, so every view direction gets its own stereo pair.
+pub struct OdsCamera {
+    motion: CameraMotion,
+    resolution: Point2f,
+    half_ipd: Float,
+    // Rays toe in to meet at this distance, or stay parallel if there isn't one
+    convergence_distance: Option<Float>,
+    // The eyes merge between these elevations, in radians, so that the poles have no parallax
+    pole_merge_from: Float,
+    pole_merge_to: Float,
+}
+
+impl OdsCamera {
+    pub fn new(
+        motion: CameraMotion,
+        ipd: Float,
+        convergence_distance: Option<Float>,
+        pole_merge_from_deg: Float,
+        pole_merge_to_deg: Float,
+        film: &Film,
+    ) -> Self {
+        Self {
+            motion,
+            resolution: Point2f::new(
+                film.full_resolution.x as Float,
+                film.full_resolution.y as Float,
+            ),
+            half_ipd: ipd / 2.0,
+            convergence_distance,
+            pole_merge_from: pole_merge_from_deg.to_radians(),
+            pole_merge_to: pole_merge_to_deg.to_radians(),
+        }
+    }
+
+    fn pole_merge(&self, elevation: Float) -> Float {
+        if elevation <= self.pole_merge_from {
+            1.0
+        } else if elevation >= self.pole_merge_to {
+            0.0
+        } else {
+            let t =
+                (elevation - self.pole_merge_from) / (self.pole_merge_to - self.pole_merge_from);
+            1.0 - t * t * (3.0 - 2.0 * t)
+        }
+    }
+}
+
+impl Camera for OdsCamera {
+    fn generate_ray(&self, sample: &CameraSample) -> Option<(Ray, Float)> {
+        let eye_height = self.resolution.y / 2.0;
+        let (v, side) = if sample.film.y < eye_height {
+            (sample.film.y / eye_height, -1.0)
+        } else {
+            ((sample.film.y - eye_height) / eye_height, 1.0)
+        };
+
+        let (dir, phi, theta) = lat_long_direction(sample.film.x / self.resolution.x, v);
+
+        // Right of the horizontal view direction, so the left eye sits at -1 and the right at 1
+        let right = Vec3f::new(phi.cos(), 0.0, -phi.sin());
+        let elevation = (Float::FRAC_PI_2() - theta).abs();
+        let o = Point3f::default() + right * (side * self.half_ipd * self.pole_merge(elevation));
+
+        let dir = match self.convergence_distance {
+            Some(distance) => (Point3f::default() + dir * distance - o).normalized(),
+            None => dir,
+        };
+        Some(camera_ray(&self.motion, o, dir, sample))
+    }
+}
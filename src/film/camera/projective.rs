@@ -0,0 +1,218 @@
+use super::{BrownConrady, Camera, CameraMotion, CameraSample};
+use crate::film::Film;
+use crate::math::*;
+use num::traits::FloatConst;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ThinLens {
+    pub radius: Float,
+    pub focal_distance: Float,
+    // Zero for a round aperture, otherwise the number of sides of the polygonal one
+    pub blades: u32,
+    pub blade_rotation_deg: Float,
+}
+
+impl ThinLens {
+    // Moves the origin of a camera space ray across the lens, keeping the point where it
+    // crosses the plane of focus
+    pub fn refocus(&self, ray: &mut Ray, u: Point2f) {
+        let p_lens = if self.blades == 0 {
+            sample::concentric_disk((u.x, u.y))
+        } else {
+            // Blade rotation 0 puts a flat edge at the bottom, like most real apertures
+            let rotation = self.blade_rotation_deg.to_radians() - Float::FRAC_PI_2()
+                + Float::PI() / self.blades as Float;
+            sample::regular_polygon((u.x, u.y), self.blades, rotation)
+        } * self.radius;
+
+        let ft = self.focal_distance / ray.d.z;
+        let p_focus = ray.o + ray.d * ft;
+        ray.o = Point3f::new(ray.o.x + p_lens.x, ray.o.y + p_lens.y, ray.o.z);
+        ray.d = (p_focus - ray.o).normalized();
+    }
+}
+
+// The screen window of the film's aspect ratio that spans [-1, 1] along its shorter side
+pub fn default_screen_window(film: &Film) -> Bounds2f {
+    let aspect = film.full_resolution.x as Float / film.full_resolution.y as Float;
+    if aspect >= 1.0 {
+        Bounds2f::new(Point2f::new(-aspect, -1.0), Point2f::new(aspect, 1.0))
+    } else {
+        Bounds2f::new(
+            Point2f::new(-1.0, -1.0 / aspect),
+            Point2f::new(1.0, 1.0 / aspect),
+        )
+    }
+}
+
+// Shared by cameras that project the scene onto a screen window which is stretched over the film
+struct ProjectiveCamera {
+    raster_to_camera: Transform,
+    motion: CameraMotion,
+    lens: Option<ThinLens>,
+}
+
+impl ProjectiveCamera {
+    fn new(
+        camera_to_screen: Transform,
+        screen: Bounds2f,
+        motion: CameraMotion,
+        lens: Option<ThinLens>,
+        film: &Film,
+    ) -> Self {
+        let screen_to_raster =
+            Transform::scale(
+                film.full_resolution.x as Float,
+                film.full_resolution.y as Float,
+                1.0,
+            ) * Transform::scale(
+                1.0 / (screen.max.x - screen.min.x),
+                1.0 / (screen.min.y - screen.max.y),
+                1.0,
+            ) * Transform::translate(Vec3f::new(-screen.min.x, -screen.max.y, 0.0));
+
+        let raster_to_screen = screen_to_raster.inverse();
+        let raster_to_camera = camera_to_screen.inverse() * raster_to_screen;
+
+        Self {
+            raster_to_camera,
+            motion,
+            lens,
+        }
+    }
+
+    fn film_to_camera(&self, sample: &CameraSample) -> Point3f {
+        let p_film = Point3f::new(sample.film.x, sample.film.y, 0.0);
+        self.raster_to_camera.apply_point(p_film)
+    }
+
+    fn finish_ray(&self, mut ray: Ray, sample: &CameraSample) -> (Ray, Float) {
+        ray.time = self.motion.time(sample.time);
+
+        if let Some(lens) = &self.lens {
+            lens.refocus(&mut ray, sample.lens);
+        }
+
+        self.motion.to_world(&mut ray);
+        (ray, 1.0)
+    }
+}
+
+// An OpenCV style camera matrix, in pixels of the image it was calibrated on. That image is
+// the film without any overscan unless its resolution is given.
+#[derive(Debug, Copy, Clone)]
+pub struct Intrinsics {
+    pub fx: Float,
+    pub fy: Float,
+    pub cx: Float,
+    pub cy: Float,
+    pub resolution: Option<Point2f>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum FieldOfView {
+    // Spans the shorter side of the image
+    Degrees(Float),
+    Intrinsics(Intrinsics),
+}
+
+pub struct PerspectiveCamera {
+    base: ProjectiveCamera,
+    distortion: Option<BrownConrady>,
+}
+
+impl PerspectiveCamera {
+    // Overscan widens the view by that fraction of its size on every side, for rendering
+    // past the edges of a plate. The film resolution should include the extra border.
+    pub fn new(
+        motion: CameraMotion,
+        fov: FieldOfView,
+        distortion: Option<BrownConrady>,
+        overscan: Float,
+        lens: Option<ThinLens>,
+        film: &Film,
+    ) -> Self {
+        // With a 90 degree field of view, screen space is the plane at z = 1. OpenCV puts
+        // pixel centers at whole numbers, so the image starts half a pixel before zero.
+        let (fov_deg, screen) = match fov {
+            FieldOfView::Degrees(deg) => (deg, default_screen_window(film)),
+            FieldOfView::Intrinsics(k) => {
+                let res = k.resolution.unwrap_or_else(|| {
+                    Point2f::new(
+                        film.full_resolution.x as Float / (1.0 + 2.0 * overscan),
+                        film.full_resolution.y as Float / (1.0 + 2.0 * overscan),
+                    )
+                });
+                let screen = Bounds2f::new(
+                    Point2f::new((-0.5 - k.cx) / k.fx, (k.cy + 0.5 - res.y) / k.fy),
+                    Point2f::new((res.x - 0.5 - k.cx) / k.fx, (k.cy + 0.5) / k.fy),
+                );
+                (90.0, screen)
+            }
+        };
+
+        let border = (screen.max - screen.min) * overscan;
+        let screen = Bounds2f::new(screen.min - border, screen.max + border);
+
+        Self {
+            base: ProjectiveCamera::new(
+                Transform::perspective(fov_deg, 1e-2, 1000.0),
+                screen,
+                motion,
+                lens,
+                film,
+            ),
+            distortion,
+        }
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn generate_ray(&self, sample: &CameraSample) -> Option<(Ray, Float)> {
+        let mut p_camera = self.base.film_to_camera(sample);
+
+        // The film shows the distorted image, so find the undistorted direction that lands on
+        // this sample. Distortion models have y pointing down.
+        if let Some(distortion) = &self.distortion {
+            let distorted = Point2f::new(p_camera.x / p_camera.z, -p_camera.y / p_camera.z);
+            let p = distortion.undistort(distorted)?;
+            p_camera = Point3f::new(p.x, -p.y, 1.0);
+        }
+
+        let ray = Ray::new(Point3f::default(), p_camera.to_vec().normalized());
+        Some(self.base.finish_ray(ray, sample))
+    }
+}
+
+// Parallel rays along the view direction. The screen window is the extent of the view in
+// camera space units.
+pub struct OrthographicCamera {
+    base: ProjectiveCamera,
+}
+
+impl OrthographicCamera {
+    pub fn new(
+        motion: CameraMotion,
+        screen_window: Bounds2f,
+        lens: Option<ThinLens>,
+        film: &Film,
+    ) -> Self {
+        Self {
+            base: ProjectiveCamera::new(
+                Transform::orthographic(0.0, 1.0),
+                screen_window,
+                motion,
+                lens,
+                film,
+            ),
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn generate_ray(&self, sample: &CameraSample) -> Option<(Ray, Float)> {
+        let p_camera = self.base.film_to_camera(sample);
+        let ray = Ray::new(p_camera, Vec3f::new(0.0, 0.0, 1.0));
+        Some(self.base.finish_ray(ray, sample))
+    }
+}
@@ -0,0 +1,410 @@
+use super::{Camera, CameraMotion, CameraSample};
+use crate::film::Film;
+use crate::math::*;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+// Film radii the exit pupil is bounded over, and rear element samples per radius
+const PUPIL_INTERVALS: usize = 64;
+const PUPIL_SAMPLES: usize = 256 * 256;
+
+// One spherical surface of a lens system, in meters. Elements go from the front of the lens
+// to the film and each thickness is the distance along the axis to the next surface. A
+// curvature radius of zero marks the aperture stop.
+#[derive(Debug, Copy, Clone)]
+pub struct LensElement {
+    pub curvature_radius: Float,
+    pub thickness: Float,
+    // Index of refraction behind the surface, zero or one for air
+    pub eta: Float,
+    pub aperture_radius: Float,
+}
+
+impl LensElement {
+    fn is_stop(&self) -> bool {
+        self.curvature_radius == 0.0
+    }
+
+    fn eta(&self) -> Float {
+        if self.eta == 0.0 {
+            1.0
+        } else {
+            self.eta
+        }
+    }
+}
+
+// A focused lens system with the exit pupil precomputed. Lens space has the film at z = 0 and
+// the elements along negative z, so rays are flipped on the way in and out of camera space.
+#[derive(Debug)]
+pub struct LensSystem {
+    elements: Vec<LensElement>,
+    film_diagonal: Float,
+    // Bounds on the rear element of the rays that get through, per film radius
+    exit_pupil_bounds: Vec<Bounds2f>,
+    // How much of the pupil the center of the film sees through, so its weights average to one
+    center_pupil_area: Float,
+}
+
+impl LensSystem {
+    pub fn new(
+        mut elements: Vec<LensElement>,
+        aperture_radius: Option<Float>,
+        focus_distance: Float,
+        film_diagonal: Float,
+    ) -> Result<Self, String> {
+        if elements.is_empty() {
+            return Err("the lens has no elements".to_string());
+        }
+
+        if let Some(radius) = aperture_radius {
+            let stop = match elements.iter_mut().find(|e| e.is_stop()) {
+                Some(stop) => stop,
+                None => return Err("the lens has no aperture stop to set".to_string()),
+            };
+            if radius > stop.aperture_radius {
+                return Err(format!(
+                    "the aperture can't be wider than the lens' stop ({} mm across)",
+                    stop.aperture_radius * 2000.0
+                ));
+            }
+            stop.aperture_radius = radius;
+        }
+
+        let mut lens = Self {
+            elements,
+            film_diagonal,
+            exit_pupil_bounds: Vec::new(),
+            center_pupil_area: 0.0,
+        };
+
+        let rear_thickness = lens
+            .focus(focus_distance)
+            .ok_or_else(|| format!("the lens can't focus at {} m", focus_distance))?;
+        lens.elements.last_mut().unwrap().thickness = rear_thickness;
+
+        let half_diagonal = film_diagonal / 2.0;
+        lens.exit_pupil_bounds = (0..PUPIL_INTERVALS)
+            .into_par_iter()
+            .map(|i| {
+                let r0 = i as Float / PUPIL_INTERVALS as Float * half_diagonal;
+                let r1 = (i + 1) as Float / PUPIL_INTERVALS as Float * half_diagonal;
+                lens.bound_exit_pupil(r0, r1)
+            })
+            .collect();
+        lens.center_pupil_area = lens.pupil_area_at_center();
+        if lens.center_pupil_area == 0.0 {
+            return Err("no light gets through the lens".to_string());
+        }
+
+        Ok(lens)
+    }
+
+    fn rear_z(&self) -> Float {
+        self.elements.last().unwrap().thickness
+    }
+
+    fn front_z(&self) -> Float {
+        self.elements.iter().map(|e| e.thickness).sum()
+    }
+
+    fn rear_radius(&self) -> Float {
+        self.elements.last().unwrap().aperture_radius
+    }
+
+    // Follows a lens space ray from the film out of the front element, or None if it's blocked
+    fn trace_from_film(&self, ray: &Ray) -> Option<Ray> {
+        let mut r = Ray::new(ray.o, ray.d);
+        let mut z = 0.0;
+        for (i, element) in self.elements.iter().enumerate().rev() {
+            z -= element.thickness;
+            let n = self.cross(element, z, &mut r)?;
+            if let Some(n) = n {
+                let eta_t = if i > 0 {
+                    self.elements[i - 1].eta()
+                } else {
+                    1.0
+                };
+                r.d = Vec3f::refract(-r.d.normalized(), n, element.eta() / eta_t)?;
+            }
+        }
+        Some(r)
+    }
+
+    // Follows a lens space ray from the scene in through the rear element
+    fn trace_from_scene(&self, ray: &Ray) -> Option<Ray> {
+        let mut r = Ray::new(ray.o, ray.d);
+        let mut z = -self.front_z();
+        for (i, element) in self.elements.iter().enumerate() {
+            let n = self.cross(element, z, &mut r)?;
+            if let Some(n) = n {
+                let eta_i = if i > 0 {
+                    self.elements[i - 1].eta()
+                } else {
+                    1.0
+                };
+                r.d = Vec3f::refract(-r.d.normalized(), n, eta_i / element.eta())?;
+            }
+            z += element.thickness;
+        }
+        Some(r)
+    }
+
+    // Moves the ray to where it meets the element at z and returns the surface normal facing
+    // back along the ray, or no normal for the aperture stop
+    fn cross(&self, element: &LensElement, z: Float, r: &mut Ray) -> Option<Option<Vec3f>> {
+        let (t, n) = if element.is_stop() {
+            if r.d.z == 0.0 {
+                return None;
+            }
+            ((z - r.o.z) / r.d.z, None)
+        } else {
+            let radius = element.curvature_radius;
+            let o = Vec3f::new(r.o.x, r.o.y, r.o.z - (z + radius));
+            let a = r.d.length_squared();
+            let b = 2.0 * r.d.dot(o);
+            let c = o.length_squared() - radius * radius;
+            let (t0, t1) = solve_quadratic(a, b, c)?;
+
+            // Which of the two hits is on the lens depends on the direction of travel and the
+            // way the surface curves
+            let t = if (r.d.z > 0.0) ^ (radius < 0.0) {
+                t0.min(t1)
+            } else {
+                t0.max(t1)
+            };
+            let n = (o + r.d * t).normalized().face_forward(-r.d);
+            (t, Some(n))
+        };
+        if t < 0.0 {
+            return None;
+        }
+
+        let p = r.o + r.d * t;
+        if p.x * p.x + p.y * p.y > element.aperture_radius * element.aperture_radius {
+            return None;
+        }
+        r.o = p;
+        Some(n)
+    }
+
+    // Principal and focal planes on the scene and film sides, from where a ray parallel to the
+    // axis crosses it after passing through the lens
+    fn thick_lens(&self) -> Option<([Float; 2], [Float; 2])> {
+        let x = 0.001 * self.film_diagonal;
+
+        let from_scene = Ray::new(
+            Point3f::new(x, 0.0, -self.front_z() - 1.0),
+            Vec3f::new(0.0, 0.0, 1.0),
+        );
+        let (pz0, fz0) = cardinal_points(&from_scene, &self.trace_from_scene(&from_scene)?);
+
+        let from_film = Ray::new(
+            Point3f::new(x, 0.0, -self.rear_z() + 1.0),
+            Vec3f::new(0.0, 0.0, -1.0),
+        );
+        let (pz1, fz1) = cardinal_points(&from_film, &self.trace_from_film(&from_film)?);
+
+        Some(([pz0, pz1], [fz0, fz1]))
+    }
+
+    // The rear element thickness that brings the focus distance into focus on the film
+    fn focus(&self, focus_distance: Float) -> Option<Float> {
+        let (pz, fz) = self.thick_lens()?;
+        let f = fz[0] - pz[0];
+        let z = -focus_distance;
+        let c = (pz[1] - z - pz[0]) * (pz[1] - z - 4.0 * f - pz[0]);
+        if c <= 0.0 {
+            return None;
+        }
+        let delta = 0.5 * (pz[1] - z + pz[0] - c.sqrt());
+        let thickness = self.rear_z() + delta;
+        if thickness > 0.0 {
+            Some(thickness)
+        } else {
+            None
+        }
+    }
+
+    // Bounds of the points on the rear element that rays from film points between r0 and r1
+    // along x can pass through
+    fn bound_exit_pupil(&self, r0: Float, r1: Float) -> Bounds2f {
+        let extent = 1.5 * self.rear_radius();
+        let rear_z = -self.rear_z();
+
+        let mut min = Point2f::new(Float::INFINITY, Float::INFINITY);
+        let mut max = Point2f::new(-Float::INFINITY, -Float::INFINITY);
+        for i in 0..PUPIL_SAMPLES {
+            let p_film = Point3f::new(
+                lerp((i as Float + 0.5) / PUPIL_SAMPLES as Float, r0, r1),
+                0.0,
+                0.0,
+            );
+            let p_rear = Point3f::new(
+                lerp(radical_inverse(2, i), -extent, extent),
+                lerp(radical_inverse(3, i), -extent, extent),
+                rear_z,
+            );
+
+            let inside =
+                p_rear.x >= min.x && p_rear.x <= max.x && p_rear.y >= min.y && p_rear.y <= max.y;
+            if inside
+                || self
+                    .trace_from_film(&Ray::new(p_film, p_rear - p_film))
+                    .is_some()
+            {
+                min = Point2f::new(min.x.min(p_rear.x), min.y.min(p_rear.y));
+                max = Point2f::new(max.x.max(p_rear.x), max.y.max(p_rear.y));
+            }
+        }
+
+        if min.x > max.x {
+            return Bounds2f::new(Point2f::new(-extent, -extent), Point2f::new(extent, extent));
+        }
+
+        // Grow by the sample spacing so the bounds don't clip the pupil's edges
+        let pad = 4.0 * extent * (2.0 as Float).sqrt() / (PUPIL_SAMPLES as Float).sqrt();
+        Bounds2f::new(
+            Point2f::new(min.x - pad, min.y - pad),
+            Point2f::new(max.x + pad, max.y + pad),
+        )
+    }
+
+    fn pupil_area_at_center(&self) -> Float {
+        let n = 256;
+        let bounds = self.exit_pupil_bounds[0];
+        let p_film = Point3f::new(0.0, 0.0, 0.0);
+        let passed = (0..n * n)
+            .filter(|i| {
+                let p_rear = Point3f::new(
+                    lerp(
+                        ((i % n) as Float + 0.5) / n as Float,
+                        bounds.min.x,
+                        bounds.max.x,
+                    ),
+                    lerp(
+                        ((i / n) as Float + 0.5) / n as Float,
+                        bounds.min.y,
+                        bounds.max.y,
+                    ),
+                    -self.rear_z(),
+                );
+                self.trace_from_film(&Ray::new(p_film, p_rear - p_film))
+                    .is_some()
+            })
+            .count();
+        bounds.area() * passed as Float / (n * n) as Float
+    }
+
+    // A point on the rear element towards the exit pupil, and the area it was picked from
+    fn sample_exit_pupil(&self, p_film: Point2f, u: Point2f) -> (Point3f, Float) {
+        let r_film = (p_film.x * p_film.x + p_film.y * p_film.y).sqrt();
+        let i = (r_film / (self.film_diagonal / 2.0) * PUPIL_INTERVALS as Float) as usize;
+        let bounds = self.exit_pupil_bounds[i.min(PUPIL_INTERVALS - 1)];
+
+        let x = lerp(u.x, bounds.min.x, bounds.max.x);
+        let y = lerp(u.y, bounds.min.y, bounds.max.y);
+
+        // The bounds were found along x, rotate them to where the film point is
+        let (sin, cos) = if r_film != 0.0 {
+            (p_film.y / r_film, p_film.x / r_film)
+        } else {
+            (0.0, 1.0)
+        };
+        (
+            Point3f::new(cos * x - sin * y, sin * x + cos * y, -self.rear_z()),
+            bounds.area(),
+        )
+    }
+}
+
+fn cardinal_points(r_in: &Ray, r_out: &Ray) -> (Float, Float) {
+    let tf = -r_out.o.x / r_out.d.x;
+    let fz = r_out.o.z + r_out.d.z * tf;
+    let tp = (r_in.o.x - r_out.o.x) / r_out.d.x;
+    let pz = r_out.o.z + r_out.d.z * tp;
+    (pz, fz)
+}
+
+fn radical_inverse(base: usize, mut i: usize) -> Float {
+    let inv_base = 1.0 / base as Float;
+    let mut inv = inv_base;
+    let mut result = 0.0;
+    while i > 0 {
+        result += (i % base) as Float * inv;
+        i /= base;
+        inv *= inv_base;
+    }
+    result
+}
+
+// Simulates a real lens by tracing rays from the film through each of its elements. Rays
+// carry the vignetting of the lens in their weight.
+pub struct RealisticCamera {
+    motion: CameraMotion,
+    lens: Arc<LensSystem>,
+    resolution: Point2f,
+    // The film's physical extent in meters
+    extent: Bounds2f,
+}
+
+impl RealisticCamera {
+    pub fn new(motion: CameraMotion, lens: Arc<LensSystem>, film: &Film) -> Self {
+        let resolution = Point2f::new(
+            film.full_resolution.x as Float,
+            film.full_resolution.y as Float,
+        );
+
+        let aspect = resolution.y / resolution.x;
+        let x = (lens.film_diagonal * lens.film_diagonal / (1.0 + aspect * aspect)).sqrt();
+        let y = aspect * x;
+
+        Self {
+            motion,
+            lens,
+            resolution,
+            extent: Bounds2f::new(
+                Point2f::new(-x / 2.0, -y / 2.0),
+                Point2f::new(x / 2.0, y / 2.0),
+            ),
+        }
+    }
+}
+
+impl Camera for RealisticCamera {
+    fn generate_ray(&self, sample: &CameraSample) -> Option<(Ray, Float)> {
+        // The image is flipped through the lens, so the film is mirrored to keep it upright
+        let s = Point2f::new(
+            sample.film.x / self.resolution.x,
+            sample.film.y / self.resolution.y,
+        );
+        let p_film = Point2f::new(
+            -lerp(s.x, self.extent.min.x, self.extent.max.x),
+            lerp(s.y, self.extent.min.y, self.extent.max.y),
+        );
+
+        let (p_rear, pupil_area) = self.lens.sample_exit_pupil(p_film, sample.lens);
+        let p_film = Point3f::new(p_film.x, p_film.y, 0.0);
+        let from_film = Ray::new(p_film, p_rear - p_film);
+
+        let (lens_ray, weight) = match self.lens.trace_from_film(&from_film) {
+            Some(ray) => {
+                let cos_theta = -from_film.d.normalized().z;
+                let cos4_theta = (cos_theta * cos_theta) * (cos_theta * cos_theta);
+                (ray, cos4_theta * pupil_area / self.lens.center_pupil_area)
+            }
+            // Blocked rays still count towards the pixel, as black
+            None => (from_film, 0.0),
+        };
+
+        // Back to camera space, which looks down positive z
+        let mut ray = Ray::new(
+            Point3f::new(lens_ray.o.x, lens_ray.o.y, -lens_ray.o.z),
+            Vec3f::new(lens_ray.d.x, lens_ray.d.y, -lens_ray.d.z).normalized(),
+        );
+        ray.time = self.motion.time(sample.time);
+        self.motion.to_world(&mut ray);
+
+        Some((ray, weight))
+    }
+}
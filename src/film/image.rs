@@ -13,6 +13,7 @@ type ImgOut = u16;
 #[cfg(feature = "rgb16")]
 const PIXEL_RANGE: Float = 65535.99;
 
+#[derive(Debug, Copy, Clone)]
 pub enum Tonemap {
     Reinhard,
     ReinhardBurn(Float),
@@ -21,6 +22,13 @@ pub enum Tonemap {
     // TODO: Implement more camera response functions from http://www.cs.columbia.edu/CAVE/software/softlib/dorf.php
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    #[cfg(feature = "hdr")]
+    Exr,
+}
+
 #[derive(new)]
 pub struct Image {
     hdr_buffer: Vec<RGBSpectrum>,
@@ -30,8 +38,7 @@ pub struct Image {
 }
 
 impl Image {
-    #[cfg(not(feature = "hdr"))]
-    pub fn write_ldr(mut self, exposure: Float, tonemap: Tonemap) {
+    pub fn write_ldr(mut self, exposure: Float, tonemap: Tonemap) -> Result<(), String> {
         use num::traits::Float as _;
 
         // Apply exposure
@@ -60,10 +67,10 @@ impl Image {
         self.path.set_extension("png");
 
         #[cfg(not(feature = "rgb16"))]
-        imgbuf.save(self.path.to_str().unwrap()).unwrap();
+        let result = imgbuf.save(&self.path);
 
         #[cfg(feature = "rgb16")]
-        {
+        let result = {
             use byteorder::{BigEndian, WriteBytesExt};
 
             let raw = imgbuf.into_raw();
@@ -72,18 +79,19 @@ impl Image {
                 .for_each(|x| u8vec.write_u16::<BigEndian>(x).unwrap());
 
             image::save_buffer(
-                self.path,
+                &self.path,
                 &u8vec,
                 self.resx,
                 self.resy,
                 image::ColorType::RGB(16),
             )
-            .unwrap();
-        }
+        };
+
+        result.map_err(|e| write_error(&self.path, e))
     }
 
     #[cfg(feature = "hdr")]
-    pub fn write_hdr(mut self) {
+    pub fn write_hdr(mut self) -> Result<(), String> {
         use openexr::{FrameBuffer, Header, PixelType, ScanlineOutputFile};
 
         let buf = self
@@ -94,7 +102,8 @@ impl Image {
 
         self.path.set_extension("exr");
 
-        let mut file = std::fs::File::create(self.path).unwrap();
+        let path = &self.path;
+        let mut file = std::fs::File::create(path).map_err(|e| write_error(path, e))?;
         let mut output_file = ScanlineOutputFile::new(
             &mut file,
             Header::new()
@@ -103,12 +112,14 @@ impl Image {
                 .add_channel("G", PixelType::FLOAT)
                 .add_channel("B", PixelType::FLOAT),
         )
-        .unwrap();
+        .map_err(|e| write_error(path, e))?;
 
         let mut fb = FrameBuffer::new(self.resx, self.resy);
         fb.insert_channels(&["R", "G", "B"], &buf);
 
-        output_file.write_pixels(&fb).unwrap();
+        output_file
+            .write_pixels(&fb)
+            .map_err(|e| write_error(path, e))
     }
 
     // http://www.cs.utah.edu/~reinhard/cdrom/tonemap.pdf
@@ -138,6 +149,10 @@ impl Image {
     }
 }
 
+fn write_error(path: &std::path::Path, e: impl std::fmt::Display) -> String {
+    format!("couldn't write '{}': {}", path.display(), e)
+}
+
 // https://www.cl.cam.ac.uk/~rkm38/pdfs/mantiuk09cctm.pdf
 fn remap_color(c_in: RGBSpectrum, l_in: Float, l_out: Float) -> RGBSpectrum {
     ((c_in) / l_in) * l_out
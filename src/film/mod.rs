@@ -8,6 +8,8 @@ pub mod filter;
 pub mod image;
 pub mod spectrum;
 
+pub const TILE_SIZE: i32 = 16;
+
 #[derive(Copy, Clone)]
 pub struct Pixel {
     pub rgb: RGBSpectrum,
@@ -163,29 +165,44 @@ impl Film {
         }
     }
 
-    pub fn write_to_file(&self, filename: &str) {
+    fn resolve(&self) -> Vec<RGBSpectrum> {
         let pixels = self.pixels.lock().unwrap();
-        let (resx, resy) = (self.full_resolution.x as u32, self.full_resolution.y as u32);
 
-        let mut hdr_buffer = Vec::with_capacity((resx * resy) as usize);
+        pixels
+            .iter()
+            .map(|pixel| {
+                // Pixels that a camera can't see get no samples at all
+                if pixel.filter_weight_sum == 0.0 {
+                    return RGBSpectrum::all(0.0);
+                }
 
-        for y in 0..resy {
-            for x in 0..resx {
-                let pixel_in = pixels[(y * resx + x) as usize];
-                let rgb = pixel_in.rgb / pixel_in.filter_weight_sum;
+                let rgb = pixel.rgb / pixel.filter_weight_sum;
 
                 // Some filters have negative lobes. Clamp at zero
-                hdr_buffer.push(rgb.max(0.0));
-            }
-        }
+                rgb.max(0.0)
+            })
+            .collect()
+    }
 
-        let path = std::path::PathBuf::from(filename);
-        let image = image::Image::new(hdr_buffer, resx, resy, path);
+    // Linear RGB triples, row by row from the top left
+    pub fn to_rgb_buffer(&self) -> Vec<Float> {
+        self.resolve().iter().flat_map(|rgb| rgb.to_rgb()).collect()
+    }
 
-        #[cfg(not(feature = "hdr"))]
-        image.write_ldr(0.0, image::Tonemap::HableFilmic);
+    pub fn write_to_file(
+        &self,
+        path: &std::path::Path,
+        format: image::ImageFormat,
+        exposure: Float,
+        tonemap: image::Tonemap,
+    ) -> Result<(), String> {
+        let (resx, resy) = (self.full_resolution.x as u32, self.full_resolution.y as u32);
+        let image = image::Image::new(self.resolve(), resx, resy, path.to_path_buf());
 
-        #[cfg(feature = "hdr")]
-        image.write_hdr();
+        match format {
+            image::ImageFormat::Png => image.write_ldr(exposure, tonemap),
+            #[cfg(feature = "hdr")]
+            image::ImageFormat::Exr => image.write_hdr(),
+        }
     }
 }
@@ -39,6 +39,15 @@ pub fn gamma_correct(value: Float) -> Float {
     }
 }
 
+// Inverse of gamma_correct
+pub fn srgb_to_linear(value: Float) -> Float {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
 impl RGBSpectrum {
     pub fn from_rgb(r: Float, g: Float, b: Float) -> Self {
         let out = Self { r, g, b };
@@ -0,0 +1,416 @@
+use super::{Hit, SurfaceInteraction, AABB};
+use crate::math::*;
+
+// Bounding volume hierarchy built with the surface area heuristic and stored as a flat array
+// of nodes in depth-first order, so the first child of an interior node always follows it.
+
+const BUCKETS: usize = 12;
+const MAX_PRIMS_IN_NODE: usize = 4;
+// Traversal keeps its stack on the heap for trees deeper than this
+const STACK_SIZE: usize = 64;
+
+struct LinearNode {
+    bounds: Bounds3f,
+    // Leaves: index of the first primitive. Interior nodes: index of the second child.
+    offset: u32,
+    // Zero for interior nodes
+    count: u16,
+    // Interior nodes: the axis that the children were split along
+    axis: u8,
+}
+
+#[derive(Copy, Clone)]
+struct BuildPrimitive {
+    index: usize,
+    bounds: Bounds3f,
+    centroid: Point3f,
+}
+
+#[derive(Copy, Clone, Default)]
+struct Bucket {
+    count: usize,
+    bounds: Option<Bounds3f>,
+}
+
+pub struct BVH<T> {
+    primitives: Vec<T>,
+    nodes: Vec<LinearNode>,
+    // Most interior nodes on any path from the root, which bounds the traversal stack
+    depth: usize,
+}
+
+fn union_all<I: Iterator<Item = Bounds3f>>(mut bounds: I) -> Option<Bounds3f> {
+    let first = bounds.next()?;
+    Some(bounds.fold(first, Bounds3f::union))
+}
+
+// Moves the elements matching pred to the front and returns how many there are
+fn partition<F: Fn(&BuildPrimitive) -> bool>(prims: &mut [BuildPrimitive], pred: F) -> usize {
+    let mut mid = 0;
+    for i in 0..prims.len() {
+        if pred(&prims[i]) {
+            prims.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
+
+impl<T: AABB + Hit> BVH<T> {
+    pub fn new(primitives: Vec<T>) -> Self {
+        let mut build = primitives
+            .iter()
+            .enumerate()
+            .map(|(index, p)| {
+                let bounds = p.aabb();
+                BuildPrimitive {
+                    index,
+                    bounds,
+                    centroid: bounds.centroid(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut nodes = Vec::with_capacity(2 * primitives.len());
+        let mut order = Vec::with_capacity(primitives.len());
+        let depth = if build.is_empty() {
+            0
+        } else {
+            Self::build(&mut build, &mut nodes, &mut order)
+        };
+
+        // Reorder the primitives so that each leaf refers to a contiguous range
+        let mut slots = primitives.into_iter().map(Some).collect::<Vec<_>>();
+        let primitives = order
+            .into_iter()
+            .map(|i| slots[i].take().unwrap())
+            .collect();
+
+        Self {
+            primitives,
+            nodes,
+            depth,
+        }
+    }
+
+    // Returns the depth of the subtree in interior nodes
+    fn build(
+        prims: &mut [BuildPrimitive],
+        nodes: &mut Vec<LinearNode>,
+        order: &mut Vec<usize>,
+    ) -> usize {
+        let bounds = union_all(prims.iter().map(|p| p.bounds)).unwrap();
+        let centroid_bounds =
+            union_all(prims.iter().map(|p| Bounds3f::new(p.centroid, p.centroid))).unwrap();
+        let axis = centroid_bounds.maximum_extent();
+        let n = prims.len();
+
+        let leaf =
+            |prims: &[BuildPrimitive], nodes: &mut Vec<LinearNode>, order: &mut Vec<usize>| {
+                nodes.push(LinearNode {
+                    bounds,
+                    offset: order.len() as u32,
+                    count: prims.len() as u16,
+                    axis: 0,
+                });
+                order.extend(prims.iter().map(|p| p.index));
+            };
+
+        // When every centroid is in the same place there's nothing to split on
+        let degenerate = centroid_bounds.max[axis] == centroid_bounds.min[axis];
+        if n == 1 || (degenerate && n <= u16::MAX as usize) {
+            leaf(prims, nodes, order);
+            return 0;
+        }
+
+        let equal_split = |prims: &mut [BuildPrimitive]| {
+            let mid = prims.len() / 2;
+            // Broken input can have NaN centroids, which total_cmp still orders
+            prims.select_nth_unstable_by(mid, |a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+            mid
+        };
+
+        let mid = if n <= 2 || degenerate {
+            equal_split(prims)
+        } else {
+            let bucket_of = |p: &BuildPrimitive| {
+                let b = (BUCKETS as Float * centroid_bounds.offset(p.centroid)[axis]) as usize;
+                b.min(BUCKETS - 1)
+            };
+
+            let mut buckets = [Bucket::default(); BUCKETS];
+            for p in prims.iter() {
+                let bucket = &mut buckets[bucket_of(p)];
+                bucket.count += 1;
+                bucket.bounds = Some(match bucket.bounds {
+                    Some(b) => b.union(p.bounds),
+                    None => p.bounds,
+                });
+            }
+
+            // Cost of splitting after each bucket, relative to intersecting one primitive
+            let side_cost = |buckets: &[Bucket]| {
+                let count = buckets.iter().map(|b| b.count).sum::<usize>();
+                union_all(buckets.iter().filter_map(|b| b.bounds))
+                    .map_or(0.0, |b| count as Float * b.surface_area())
+            };
+            let (split, min_cost) = (0..BUCKETS - 1)
+                .map(|i| {
+                    let cost = 0.125
+                        + (side_cost(&buckets[..=i]) + side_cost(&buckets[i + 1..]))
+                            / bounds.surface_area();
+                    (i, cost)
+                })
+                .fold(
+                    (0, Float::INFINITY),
+                    |best, c| if c.1 < best.1 { c } else { best },
+                );
+
+            if n <= MAX_PRIMS_IN_NODE && min_cost >= n as Float {
+                leaf(prims, nodes, order);
+                return 0;
+            }
+
+            match partition(prims, |p| bucket_of(p) <= split) {
+                // Flat bounds can give NaN costs and leave one side empty
+                0 => equal_split(prims),
+                mid if mid == n => equal_split(prims),
+                mid => mid,
+            }
+        };
+
+        let index = nodes.len();
+        nodes.push(LinearNode {
+            bounds,
+            offset: 0,
+            count: 0,
+            axis: axis as u8,
+        });
+
+        let (left, right) = prims.split_at_mut(mid);
+        let left_depth = Self::build(left, nodes, order);
+        nodes[index].offset = nodes.len() as u32;
+        let right_depth = Self::build(right, nodes, order);
+        1 + left_depth.max(right_depth)
+    }
+
+    pub fn bounds(&self) -> Option<Bounds3f> {
+        self.nodes.first().map(|n| n.bounds)
+    }
+
+    // Calls visit for every primitive in the leaves the ray passes through, near children
+    // first. visit may shorten the ray, and returns true to stop early.
+    fn traverse<'a, F: FnMut(&'a T, &mut Ray) -> bool>(&'a self, ray: &Ray, visit: F) {
+        if self.depth <= STACK_SIZE {
+            self.traverse_with(&mut [0; STACK_SIZE], ray, visit);
+        } else {
+            self.traverse_with(&mut vec![0; self.depth], ray, visit);
+        }
+    }
+
+    fn traverse_with<'a, F: FnMut(&'a T, &mut Ray) -> bool>(
+        &'a self,
+        stack: &mut [usize],
+        ray: &Ray,
+        mut visit: F,
+    ) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut ray = *ray;
+        let inv_dir = Vec3f::new(1.0 / ray.d.x, 1.0 / ray.d.y, 1.0 / ray.d.z);
+        let dir_is_neg = [inv_dir.x < 0.0, inv_dir.y < 0.0, inv_dir.z < 0.0];
+
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+            if node.bounds.intersect_p(&ray, inv_dir, dir_is_neg) {
+                if node.count > 0 {
+                    let start = node.offset as usize;
+                    for p in &self.primitives[start..start + node.count as usize] {
+                        if visit(p, &mut ray) {
+                            return;
+                        }
+                    }
+                } else {
+                    // Visit the near child first and come back for the far one
+                    let (near, far) = if dir_is_neg[node.axis as usize] {
+                        (node.offset as usize, current + 1)
+                    } else {
+                        (current + 1, node.offset as usize)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+    }
+}
+
+impl<T: AABB + Hit> Hit for BVH<T> {
+    // The ray is shortened at every hit, so boxes behind the closest hit so far are skipped
+    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)> {
+        let mut closest = None;
+        self.traverse(ray, |p, ray| {
+            if let Some((si, t)) = p.intersect(ray) {
+                if t < ray.t_max {
+                    ray.t_max = t;
+                    closest = Some((si, t));
+                }
+            }
+            false
+        });
+        closest
+    }
+
+    fn intersect_p(&self, ray: &Ray) -> bool {
+        let mut hit = false;
+        self.traverse(ray, |p, ray| {
+            hit = p.intersect_p(ray);
+            hit
+        });
+        hit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A box that only answers shadow ray queries
+    struct Cube(Bounds3f);
+
+    impl AABB for Cube {
+        fn aabb(&self) -> Bounds3f {
+            self.0
+        }
+    }
+
+    impl Hit for Cube {
+        fn intersect(&self, _ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)> {
+            None
+        }
+
+        fn intersect_p(&self, ray: &Ray) -> bool {
+            let inv_dir = Vec3f::new(1.0 / ray.d.x, 1.0 / ray.d.y, 1.0 / ray.d.z);
+            let dir_is_neg = [inv_dir.x < 0.0, inv_dir.y < 0.0, inv_dir.z < 0.0];
+            self.0.intersect_p(ray, inv_dir, dir_is_neg)
+        }
+    }
+
+    fn cube(x: Float, size: Float) -> Cube {
+        Cube(Bounds3f::new(
+            Point3f::new(x - size, -size, -size),
+            Point3f::new(x + size, size, size),
+        ))
+    }
+
+    fn visited(bvh: &BVH<Cube>, ray: &Ray) -> usize {
+        let mut count = 0;
+        bvh.traverse(ray, |p, ray| {
+            count += p.intersect_p(ray) as usize;
+            false
+        });
+        count
+    }
+
+    #[test]
+    fn finds_every_primitive_along_a_ray() {
+        let bvh = BVH::new((0..100).map(|i| cube(i as Float * 3.0, 1.0)).collect());
+        let along = Ray::new(Point3f::new(-10.0, 0.0, 0.0), Vec3f::new(1.0, 0.0, 0.0));
+        assert_eq!(visited(&bvh, &along), 100);
+        assert!(bvh.intersect_p(&along));
+
+        let miss = Ray::new(Point3f::new(-10.0, 5.0, 0.0), Vec3f::new(1.0, 0.0, 0.0));
+        assert_eq!(visited(&bvh, &miss), 0);
+        assert!(!bvh.intersect_p(&miss));
+    }
+
+    // Longest run of interior nodes from the root to a leaf
+    fn measured_depth(nodes: &[LinearNode], index: usize) -> usize {
+        let node = &nodes[index];
+        if node.count > 0 {
+            return 0;
+        }
+        1 + measured_depth(nodes, index + 1).max(measured_depth(nodes, node.offset as usize))
+    }
+
+    #[test]
+    fn depth_matches_tree() {
+        let bvh = BVH::new((0..100).map(|i| cube((i * i) as Float, 0.5)).collect());
+        assert!(bvh.depth > 0);
+        assert_eq!(bvh.depth, measured_depth(&bvh.nodes, 0));
+    }
+
+    #[test]
+    fn deep_trees_use_a_larger_stack() {
+        // A chain where every interior node has a leaf as its first child, so a ray going
+        // towards -x always descends into the second child and pushes the leaf
+        let levels = 2 * STACK_SIZE;
+        let bounds = cube(0.0, 1.0).0;
+        let mut nodes = Vec::new();
+        for i in 0..levels {
+            nodes.push(LinearNode {
+                bounds,
+                offset: (nodes.len() + 2) as u32,
+                count: 0,
+                axis: 0,
+            });
+            nodes.push(LinearNode {
+                bounds,
+                offset: i as u32,
+                count: 1,
+                axis: 0,
+            });
+        }
+        nodes.push(LinearNode {
+            bounds,
+            offset: levels as u32,
+            count: 1,
+            axis: 0,
+        });
+        let bvh = BVH {
+            primitives: (0..=levels).map(|_| cube(0.0, 1.0)).collect(),
+            nodes,
+            depth: levels,
+        };
+        assert_eq!(bvh.depth, measured_depth(&bvh.nodes, 0));
+
+        let ray = Ray::new(Point3f::new(10.0, 0.0, 0.0), Vec3f::new(-1.0, 0.0, 0.0));
+        assert_eq!(visited(&bvh, &ray), levels + 1);
+    }
+
+    #[test]
+    fn nan_bounds_dont_panic() {
+        let mut cubes = (0..10)
+            .map(|i| cube(i as Float * 3.0, 1.0))
+            .collect::<Vec<_>>();
+        cubes.push(Cube(Bounds3f::new(
+            Point3f::new(Float::NAN, 0.0, 0.0),
+            Point3f::new(Float::NAN, 1.0, 1.0),
+        )));
+        let bvh = BVH::new(cubes);
+
+        let along = Ray::new(Point3f::new(-10.0, 0.0, 0.0), Vec3f::new(1.0, 0.0, 0.0));
+        assert!(bvh.intersect_p(&along));
+    }
+
+    #[test]
+    fn empty() {
+        let bvh = BVH::<Cube>::new(Vec::new());
+        assert!(bvh.bounds().is_none());
+        let ray = Ray::new(Point3f::new(0.0, 0.0, 0.0), Vec3f::new(1.0, 0.0, 0.0));
+        assert!(!bvh.intersect_p(&ray));
+    }
+}
@@ -17,13 +17,9 @@ impl LocalAABB for Disk {
     }
 }
 
-impl Geometry for Disk {
-    fn local_intersect(
-        &self,
-        ray: &LocalRay,
-        o_err: LocalVec3f,
-        d_err: LocalVec3f,
-    ) -> Option<(LocalGeometry, Float)> {
+impl Disk {
+    // Ray parameter, point and squared distance from the centre where the ray hits, if it does
+    fn hit(&self, ray: &LocalRay) -> Option<(Float, LocalPoint3f, Float)> {
         if ray.d.z == 0.0 {
             return None;
         }
@@ -33,12 +29,29 @@ impl Geometry for Disk {
             return None;
         }
 
-        let p_hit = ray.at(t_hit);
+        // Refine the hit onto the plane, otherwise the point is off by more than its zero error
+        // bound and spawned rays can hit the disk again
+        let mut p_hit = ray.at(t_hit);
+        p_hit.z = 0.0;
+
         let dist_2 = p_hit.x * p_hit.x + p_hit.y * p_hit.y;
         if dist_2 > self.radius * self.radius || dist_2 < self.inner_radius * self.inner_radius {
             return None;
         }
 
+        Some((t_hit, p_hit, dist_2))
+    }
+}
+
+impl Geometry for Disk {
+    fn local_intersect(
+        &self,
+        ray: &LocalRay,
+        _o_err: LocalVec3f,
+        _d_err: LocalVec3f,
+    ) -> Option<(LocalGeometry, Float)> {
+        let (t_hit, p_hit, dist_2) = self.hit(ray)?;
+
         let phi = p_hit.y.atan2(p_hit.x);
         let phi = if phi < 0.0 {
             phi + 2.0 * Float::PI()
@@ -69,12 +82,21 @@ impl Geometry for Disk {
                 uv: Point2f::new(u, v),
                 dpdu,
                 dpdv,
+                shading_dpdu: dpdu,
+                shading_dpdv: dpdv,
+                shading_dndu: LocalNormal3f::default(),
+                shading_dndv: LocalNormal3f::default(),
+                color: None,
                 time: ray.time,
             },
             t_hit,
         ))
     }
 
+    fn local_intersect_p(&self, ray: &LocalRay, _o_err: LocalVec3f, _d_err: LocalVec3f) -> bool {
+        self.hit(ray).is_some()
+    }
+
     fn area(&self) -> Float {
         Float::PI() * (self.radius.powi(2) - self.inner_radius.powi(2))
     }
@@ -0,0 +1,113 @@
+use super::bvh::BVH;
+use super::{receiver::Receiver, Hit, SurfaceInteraction, AABB};
+use crate::math::*;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+// Geometry shared between instances, with its own BVH in prototype space. Lights can't be
+// part of a prototype since the scene samples them individually.
+pub type Prototype = BVH<Receiver>;
+
+#[derive(Clone)]
+pub struct Instance {
+    prototype: Arc<Prototype>,
+    transform: AnimatedTransform,
+}
+
+impl Instance {
+    pub fn new(prototype: Arc<Prototype>, instance_to_world: AnimatedTransform) -> Self {
+        Self {
+            prototype,
+            transform: instance_to_world,
+        }
+    }
+
+    // The origin isn't offset by the transform's rounding error, so t and t_max mean the same
+    // in both spaces. The receivers in the prototype account for their own error.
+    fn to_prototype(&self, ray: &Ray) -> (Cow<'_, TransformPair>, Ray) {
+        let transform = self.transform.at(ray.time);
+        let local_ray = Ray {
+            o: transform.to_local.apply_point(ray.o),
+            d: transform.to_local.apply(ray.d),
+            ..*ray
+        };
+        (transform, local_ray)
+    }
+}
+
+impl AABB for Instance {
+    fn aabb(&self) -> Bounds3f {
+        let bounds = self.prototype.bounds().unwrap_or_default();
+        self.transform.motion_bounds(bounds)
+    }
+}
+
+impl Hit for Instance {
+    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)> {
+        let (transform, local_ray) = self.to_prototype(ray);
+        let (si, t) = self.prototype.intersect(&local_ray)?;
+
+        // The instance space direction isn't normalized, so t is the same in both spaces
+        Some((si.transformed(&transform, ray), t))
+    }
+
+    fn intersect_p(&self, ray: &Ray) -> bool {
+        let (_, local_ray) = self.to_prototype(ray);
+        self.prototype.intersect_p(&local_ray)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::sphere::Sphere;
+    use crate::material::matte::Matte;
+    use crate::texture::constant::ConstantTexture;
+
+    // A unit sphere scaled up to radius 2 and moved to x = 10
+    fn instance() -> Instance {
+        let matte = Matte::new(
+            Arc::new(ConstantTexture::new(crate::film::spectrum::Spectrum::all(
+                0.5,
+            ))),
+            None,
+            None,
+            None,
+        );
+        let receiver = Receiver::new(
+            Arc::new(Sphere::new(1.0)),
+            Arc::new(matte),
+            Transform::scale(1.0, 1.0, 1.0).into(),
+        );
+        let to_world =
+            Transform::translate(Vec3f::new(10.0, 0.0, 0.0)) * Transform::scale(2.0, 2.0, 2.0);
+        Instance::new(Arc::new(BVH::new(vec![receiver])), to_world.into())
+    }
+
+    // Hits the instance at t = 8, slightly off axis
+    fn ray(t_max: Float) -> Ray {
+        let mut ray = Ray::new(
+            Point3f::new(0.0, -0.01, 0.01),
+            Vec3f::new(1.0, 0.001, -0.001),
+        );
+        ray.t_max = t_max;
+        ray
+    }
+
+    #[test]
+    fn t_is_in_world_space() {
+        let instance = instance();
+        let (si, t) = instance.intersect(&ray(Float::INFINITY)).unwrap();
+        assert!((t - 8.0).abs() < 1e-4, "t = {}", t);
+        assert!((si.int.point.x - 8.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn respects_t_max() {
+        let instance = instance();
+        assert!(instance.intersect(&ray(7.9)).is_none());
+        assert!(!instance.intersect_p(&ray(7.9)));
+        assert!(instance.intersect(&ray(8.1)).is_some());
+        assert!(instance.intersect_p(&ray(8.1)));
+    }
+}
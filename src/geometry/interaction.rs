@@ -1,5 +1,6 @@
 use super::Geometry;
 use crate::bxdf::bsdf::BSDF;
+use crate::film::spectrum::Spectrum;
 use crate::light::Light;
 use crate::material::Material;
 use crate::math::*;
@@ -20,6 +21,8 @@ pub struct Shading {
     pub normal: Normal3f,
     pub dpdu: Vec3f,
     pub dpdv: Vec3f,
+    pub dndu: Normal3f,
+    pub dndv: Normal3f,
 }
 
 #[derive(Clone)]
@@ -29,6 +32,18 @@ pub struct SurfaceInteraction<'a> {
     pub uv: Point2f,
     pub dpdu: Vec3f,
     pub dpdv: Vec3f,
+    // How far the surface point and uv move between neighbouring pixels, or zero without ray
+    // differentials
+    pub dpdx: Vec3f,
+    pub dpdy: Vec3f,
+    pub dudx: Float,
+    pub dvdx: Float,
+    pub dudy: Float,
+    pub dvdy: Float,
+    // Interpolated vertex color, for meshes that have them
+    pub color: Option<Spectrum>,
+    // Space of the shape that was hit, including any instance transform
+    pub object_to_world: TransformPair,
     pub bsdf: Option<&'a BSDF<'a>>,
     pub material: Option<Arc<dyn Material + Send + Sync>>,
     pub geometry: Option<Arc<dyn Geometry + Send + Sync>>,
@@ -42,7 +57,174 @@ impl Interaction {
 }
 
 impl<'a> SurfaceInteraction<'a> {
-    pub fn compute_bsdf(&'a self, alloc: &'a Bump) -> BSDF {
+    // Moves an interaction found in an instance's space out to world space
+    pub fn transformed(self, m: &TransformPair, ray: &Ray) -> Self {
+        let (point, point_error) = m
+            .to_global
+            .apply_point_with_error(self.int.point, self.int.point_error);
+        let normal = m.to_local.apply_normal(self.int.normal).normalized();
+        let shading_normal = m
+            .to_local
+            .apply_normal(self.shading.normal)
+            .normalized()
+            .face_forward(normal);
+
+        Self {
+            int: Interaction {
+                point,
+                point_error,
+                normal,
+                wo: -ray.d,
+                time: self.int.time,
+            },
+            shading: Shading {
+                normal: shading_normal,
+                dpdu: m.to_global.apply(self.shading.dpdu),
+                dpdv: m.to_global.apply(self.shading.dpdv),
+                dndu: m.to_local.apply_normal(self.shading.dndu),
+                dndv: m.to_local.apply_normal(self.shading.dndv),
+            },
+            dpdu: m.to_global.apply(self.dpdu),
+            dpdv: m.to_global.apply(self.dpdv),
+            object_to_world: *m * self.object_to_world,
+            ..self
+        }
+    }
+
+    // Finds where the offset rays hit the tangent plane, and from that how uv changes across
+    // the pixel
+    pub fn compute_differentials(&mut self, ray: &RayDifferential) {
+        let info = match &ray.info {
+            Some(info) => info,
+            None => return,
+        };
+
+        let n = self.int.normal.to_vec();
+        let p = Vec3f::from(self.int.point);
+        let d = n.dot(p);
+        let tx = (d - n.dot(Vec3f::from(info.rx_origin))) / n.dot(info.rx_direction);
+        let ty = (d - n.dot(Vec3f::from(info.ry_origin))) / n.dot(info.ry_direction);
+        if !tx.is_finite() || !ty.is_finite() {
+            return;
+        }
+
+        let px = info.rx_origin + info.rx_direction * tx;
+        let py = info.ry_origin + info.ry_direction * ty;
+        self.dpdx = px - self.int.point;
+        self.dpdy = py - self.int.point;
+
+        // Least squares would work too, but dropping the axis the normal points along keeps the
+        // system well conditioned
+        let (a, b) = if n.x.abs() > n.y.abs() && n.x.abs() > n.z.abs() {
+            (1, 2)
+        } else if n.y.abs() > n.z.abs() {
+            (0, 2)
+        } else {
+            (0, 1)
+        };
+
+        let solve = |delta: Vec3f| {
+            let det = self.dpdu[a] * self.dpdv[b] - self.dpdv[a] * self.dpdu[b];
+            if det == 0.0 {
+                return (0.0, 0.0);
+            }
+            let du = (self.dpdv[b] * delta[a] - self.dpdv[a] * delta[b]) / det;
+            let dv = (self.dpdu[a] * delta[b] - self.dpdu[b] * delta[a]) / det;
+            if du.is_finite() && dv.is_finite() {
+                (du, dv)
+            } else {
+                (0.0, 0.0)
+            }
+        };
+        let (dudx, dvdx) = solve(self.dpdx);
+        let (dudy, dvdy) = solve(self.dpdy);
+        self.dudx = dudx;
+        self.dvdx = dvdx;
+        self.dudy = dudy;
+        self.dvdy = dvdy;
+    }
+
+    // Mirrors the ray differentials along with the ray, so textures seen in mirrors are still
+    // filtered
+    pub fn spawn_reflected_ray(&self, ray: &RayDifferential, wi: Vec3f) -> RayDifferential {
+        let spawned = self.int.spawn_ray(wi);
+        let info = match &ray.info {
+            Some(info) => info,
+            None => return RayDifferential::from(spawned),
+        };
+
+        let wo = self.int.wo;
+        let ns = self.shading.normal.to_vec();
+        let dndx = self.shading.dndu.to_vec() * self.dudx + self.shading.dndv.to_vec() * self.dvdx;
+        let dndy = self.shading.dndu.to_vec() * self.dudy + self.shading.dndv.to_vec() * self.dvdy;
+        let dwodx = -info.rx_direction - wo;
+        let dwody = -info.ry_direction - wo;
+        let ddndx = dwodx.dot(ns) + wo.dot(dndx);
+        let ddndy = dwody.dot(ns) + wo.dot(dndy);
+
+        RayDifferential {
+            ray: spawned,
+            info: Some(RayDifferentialInfo {
+                rx_origin: self.int.point + self.dpdx,
+                ry_origin: self.int.point + self.dpdy,
+                rx_direction: wi - dwodx + (dndx * wo.dot(ns) + ns * ddndx) * 2.0,
+                ry_direction: wi - dwody + (dndy * wo.dot(ns) + ns * ddndy) * 2.0,
+            }),
+        }
+    }
+
+    // Bends the ray differentials along with a ray refracted into or out of a surface with
+    // relative index of refraction eta
+    pub fn spawn_refracted_ray(
+        &self,
+        ray: &RayDifferential,
+        wi: Vec3f,
+        eta: Float,
+    ) -> RayDifferential {
+        let spawned = self.int.spawn_ray(wi);
+        let info = match &ray.info {
+            Some(info) => info,
+            None => return RayDifferential::from(spawned),
+        };
+
+        let wo = self.int.wo;
+        let mut ns = self.shading.normal.to_vec();
+        let mut dndx =
+            self.shading.dndu.to_vec() * self.dudx + self.shading.dndv.to_vec() * self.dvdx;
+        let mut dndy =
+            self.shading.dndu.to_vec() * self.dudy + self.shading.dndv.to_vec() * self.dvdy;
+
+        // eta here is outside over inside, flipped along with the normal when leaving
+        let mut eta = 1.0 / eta;
+        if wo.dot(ns) < 0.0 {
+            eta = 1.0 / eta;
+            ns = -ns;
+            dndx = -dndx;
+            dndy = -dndy;
+        }
+
+        let dwodx = -info.rx_direction - wo;
+        let dwody = -info.ry_direction - wo;
+        let ddndx = dwodx.dot(ns) + wo.dot(dndx);
+        let ddndy = dwody.dot(ns) + wo.dot(dndy);
+
+        let mu = eta * wo.dot(ns) - wi.dot(ns).abs();
+        let dmu = eta - (eta * eta * wo.dot(ns)) / wi.dot(ns).abs();
+        let dmudx = dmu * ddndx;
+        let dmudy = dmu * ddndy;
+
+        RayDifferential {
+            ray: spawned,
+            info: Some(RayDifferentialInfo {
+                rx_origin: self.int.point + self.dpdx,
+                ry_origin: self.int.point + self.dpdy,
+                rx_direction: wi - dwodx * eta + (dndx * mu + ns * dmudx),
+                ry_direction: wi - dwody * eta + (dndy * mu + ns * dmudy),
+            }),
+        }
+    }
+
+    pub fn compute_bsdf(&'a self, alloc: &'a Bump) -> BSDF<'a> {
         self.material
             .as_ref()
             .expect("no material found")
@@ -0,0 +1,432 @@
+use super::{Geometry, LocalAABB, LocalGeometry};
+use crate::film::spectrum::Spectrum;
+use crate::geometry::interaction::Interaction;
+use crate::geometry::Sampleable;
+use crate::math::*;
+use std::sync::Arc;
+
+// Vertex data is shared between all triangles and stored in object space
+pub struct TriangleMesh {
+    indices: Vec<u32>,
+    positions: Vec<LocalPoint3f>,
+    normals: Option<Vec<LocalNormal3f>>,
+    uvs: Option<Vec<Point2f>>,
+    tangents: Option<Vec<LocalVec3f>>,
+    colors: Option<Vec<Spectrum>>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<LocalPoint3f>,
+        indices: Vec<u32>,
+        normals: Option<Vec<LocalNormal3f>>,
+        uvs: Option<Vec<Point2f>>,
+        tangents: Option<Vec<LocalVec3f>>,
+    ) -> Result<Self, String> {
+        if indices.is_empty() || !indices.len().is_multiple_of(3) {
+            return Err(format!(
+                "index count must be a non-zero multiple of 3, found {}",
+                indices.len()
+            ));
+        }
+
+        if let Some(&i) = indices.iter().find(|&&i| i as usize >= positions.len()) {
+            return Err(format!(
+                "index {} is out of range for {} vertices",
+                i,
+                positions.len()
+            ));
+        }
+
+        let check_len = |name: &str, len: Option<usize>| match len {
+            Some(len) if len != positions.len() => Err(format!(
+                "expected {} {}, found {}",
+                positions.len(),
+                name,
+                len
+            )),
+            _ => Ok(()),
+        };
+
+        check_len("normals", normals.as_ref().map(Vec::len))?;
+        check_len("uvs", uvs.as_ref().map(Vec::len))?;
+        check_len("tangents", tangents.as_ref().map(Vec::len))?;
+
+        Ok(Self {
+            indices,
+            positions,
+            normals,
+            uvs,
+            tangents,
+            colors: None,
+        })
+    }
+
+    pub fn with_colors(mut self, colors: Vec<Spectrum>) -> Result<Self, String> {
+        if colors.len() != self.positions.len() {
+            return Err(format!(
+                "expected {} colors, found {}",
+                self.positions.len(),
+                colors.len()
+            ));
+        }
+        self.colors = Some(colors);
+        Ok(self)
+    }
+
+    pub fn num_triangles(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn into_triangles(self) -> Vec<Triangle> {
+        let mesh = Arc::new(self);
+        (0..mesh.num_triangles())
+            .map(|i| Triangle {
+                mesh: mesh.clone(),
+                first_index: 3 * i,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct Triangle {
+    mesh: Arc<TriangleMesh>,
+    first_index: usize,
+}
+
+impl Triangle {
+    fn vertex_indices(&self) -> [usize; 3] {
+        let idx = &self.mesh.indices[self.first_index..self.first_index + 3];
+        [idx[0] as usize, idx[1] as usize, idx[2] as usize]
+    }
+
+    fn positions(&self) -> [LocalPoint3f; 3] {
+        let [i0, i1, i2] = self.vertex_indices();
+        let p = &self.mesh.positions;
+        [p[i0], p[i1], p[i2]]
+    }
+
+    fn uvs(&self) -> [Point2f; 3] {
+        match &self.mesh.uvs {
+            Some(uvs) => {
+                let [i0, i1, i2] = self.vertex_indices();
+                [uvs[i0], uvs[i1], uvs[i2]]
+            }
+            None => [
+                Point2f::new(0.0, 0.0),
+                Point2f::new(1.0, 0.0),
+                Point2f::new(1.0, 1.0),
+            ],
+        }
+    }
+
+    fn interpolated_normal(&self, b: [Float; 3]) -> Option<LocalNormal3f> {
+        let normals = self.mesh.normals.as_ref()?;
+        let [i0, i1, i2] = self.vertex_indices();
+        let n = normals[i0] * b[0] + normals[i1] * b[1] + normals[i2] * b[2];
+        if n.length_squared() > 0.0 {
+            Some(n.normalized())
+        } else {
+            None
+        }
+    }
+
+    // How the interpolated normal changes along u and v, zero for flat triangles
+    fn normal_derivatives(
+        &self,
+        duv02: Vec2f,
+        duv12: Vec2f,
+        determinant: Float,
+    ) -> (LocalNormal3f, LocalNormal3f) {
+        let normals = match &self.mesh.normals {
+            Some(normals) if determinant.abs() >= 1e-8 => normals,
+            _ => return (LocalNormal3f::default(), LocalNormal3f::default()),
+        };
+
+        let [i0, i1, i2] = self.vertex_indices();
+        let dn02 = normals[i0] - normals[i2];
+        let dn12 = normals[i1] - normals[i2];
+        let inv_uv_det = 1.0 / determinant;
+        (
+            (dn02 * duv12.y - dn12 * duv02.y) * inv_uv_det,
+            (dn12 * duv02.x - dn02 * duv12.x) * inv_uv_det,
+        )
+    }
+
+    fn interpolated_color(&self, b: [Float; 3]) -> Option<Spectrum> {
+        let colors = self.mesh.colors.as_ref()?;
+        let [i0, i1, i2] = self.vertex_indices();
+        Some(colors[i0] * b[0] + colors[i1] * b[1] + colors[i2] * b[2])
+    }
+
+    fn interpolated_tangent(&self, b: [Float; 3]) -> Option<LocalVec3f> {
+        let tangents = self.mesh.tangents.as_ref()?;
+        let [i0, i1, i2] = self.vertex_indices();
+        let t = tangents[i0] * b[0] + tangents[i1] * b[1] + tangents[i2] * b[2];
+        if t.length_squared() > 0.0 {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    // Watertight ray-triangle test, see PBRT 3.6.2. Returns the barycentrics and t of the hit.
+    fn hit(&self, ray: &LocalRay) -> Option<([Float; 3], Float)> {
+        let [p0, p1, p2] = self.positions();
+
+        // Transform the triangle into a space where the ray starts at the origin and points
+        // along +z
+        let kz = max_dimension(ray.d.abs());
+        let kx = (kz + 1) % 3;
+        let ky = (kx + 1) % 3;
+        let d = permute(ray.d, kx, ky, kz);
+        let mut p0t = permute(p0 - ray.o, kx, ky, kz);
+        let mut p1t = permute(p1 - ray.o, kx, ky, kz);
+        let mut p2t = permute(p2 - ray.o, kx, ky, kz);
+
+        let sx = -d.x / d.z;
+        let sy = -d.y / d.z;
+        let sz = 1.0 / d.z;
+        for p in [&mut p0t, &mut p1t, &mut p2t].iter_mut() {
+            p.x += sx * p.z;
+            p.y += sy * p.z;
+        }
+
+        let e0 = p1t.x * p2t.y - p1t.y * p2t.x;
+        let e1 = p2t.x * p0t.y - p2t.y * p0t.x;
+        let e2 = p0t.x * p1t.y - p0t.y * p1t.x;
+
+        if (e0 < 0.0 || e1 < 0.0 || e2 < 0.0) && (e0 > 0.0 || e1 > 0.0 || e2 > 0.0) {
+            return None;
+        }
+
+        let det = e0 + e1 + e2;
+        if det == 0.0 {
+            return None;
+        }
+
+        p0t.z *= sz;
+        p1t.z *= sz;
+        p2t.z *= sz;
+
+        let t_scaled = e0 * p0t.z + e1 * p1t.z + e2 * p2t.z;
+        if (det < 0.0 && (t_scaled >= 0.0 || t_scaled < ray.t_max * det))
+            || (det > 0.0 && (t_scaled <= 0.0 || t_scaled > ray.t_max * det))
+        {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let b = [e0 * inv_det, e1 * inv_det, e2 * inv_det];
+        let t = t_scaled * inv_det;
+
+        // Conservatively check that t is positive
+        let max_zt = max_component(LocalVec3f::new(p0t.z, p1t.z, p2t.z).abs());
+        let delta_z = gamma(3) * max_zt;
+        let max_xt = max_component(LocalVec3f::new(p0t.x, p1t.x, p2t.x).abs());
+        let max_yt = max_component(LocalVec3f::new(p0t.y, p1t.y, p2t.y).abs());
+        let delta_x = gamma(5) * (max_xt + max_zt);
+        let delta_y = gamma(5) * (max_yt + max_zt);
+        let delta_e = 2.0 * (gamma(2) * max_xt * max_yt + delta_y * max_xt + delta_x * max_yt);
+        let max_e = max_component(LocalVec3f::new(e0, e1, e2).abs());
+        let delta_t =
+            3.0 * (gamma(3) * max_e * max_zt + delta_e * max_zt + delta_z * max_e) * inv_det.abs();
+        if t <= delta_t {
+            return None;
+        }
+
+        Some((b, t))
+    }
+}
+
+fn permute(v: LocalVec3f, kx: usize, ky: usize, kz: usize) -> LocalVec3f {
+    LocalVec3f::new(v[kx], v[ky], v[kz])
+}
+
+fn max_component(v: LocalVec3f) -> Float {
+    v.x.max(v.y).max(v.z)
+}
+
+fn max_dimension(v: LocalVec3f) -> usize {
+    if v.x > v.y {
+        if v.x > v.z {
+            0
+        } else {
+            2
+        }
+    } else if v.y > v.z {
+        1
+    } else {
+        2
+    }
+}
+
+fn normal_to_vec(n: LocalNormal3f) -> LocalVec3f {
+    LocalVec3f::new(n.x, n.y, n.z)
+}
+
+fn vec_to_normal(v: LocalVec3f) -> LocalNormal3f {
+    LocalNormal3f::new(v.x, v.y, v.z)
+}
+
+fn coordinate_system(v: LocalVec3f) -> (LocalVec3f, LocalVec3f) {
+    let (a, b) = v.as_global().coordinate_system();
+    (a.as_local().normalized(), b.as_local().normalized())
+}
+
+impl LocalAABB for Triangle {
+    fn local_aabb(&self) -> Bounds3f {
+        let [p0, p1, p2] = self.positions();
+        let mut out = Bounds3f::new(p0.as_global(), p0.as_global());
+        for p in &[p1, p2] {
+            for i in 0..3 {
+                out.min[i] = out.min[i].min(p[i]);
+                out.max[i] = out.max[i].max(p[i]);
+            }
+        }
+        out
+    }
+}
+
+impl Geometry for Triangle {
+    fn local_intersect(
+        &self,
+        ray: &LocalRay,
+        _o_err: LocalVec3f,
+        _d_err: LocalVec3f,
+    ) -> Option<(LocalGeometry, Float)> {
+        let (b, t) = self.hit(ray)?;
+        let [p0, p1, p2] = self.positions();
+
+        // Partial derivatives from the uv parameterization
+        let uv = self.uvs();
+        let duv02 = uv[0] - uv[2];
+        let duv12 = uv[1] - uv[2];
+        let dp02 = p0 - p2;
+        let dp12 = p1 - p2;
+        let determinant = duv02.x * duv12.y - duv02.y * duv12.x;
+
+        let mut derivatives = None;
+        if determinant.abs() >= 1e-8 {
+            let inv_uv_det = 1.0 / determinant;
+            let dpdu = (dp02 * duv12.y - dp12 * duv02.y) * inv_uv_det;
+            let dpdv = (dp12 * duv02.x - dp02 * duv12.x) * inv_uv_det;
+            if dpdu.cross(dpdv).length_squared() != 0.0 {
+                derivatives = Some((dpdu, dpdv));
+            }
+        }
+
+        let geometric_normal = dp02.cross(dp12);
+        let (dpdu, dpdv) = match derivatives {
+            Some(d) => d,
+            None => {
+                if geometric_normal.length_squared() == 0.0 {
+                    return None;
+                }
+                coordinate_system(geometric_normal.normalized())
+            }
+        };
+
+        let point =
+            LocalPoint3f::from(p0.to_vec() * b[0] + p1.to_vec() * b[1] + p2.to_vec() * b[2]);
+        let point_error =
+            (p0.to_vec() * b[0]).abs() + (p1.to_vec() * b[1]).abs() + (p2.to_vec() * b[2]).abs();
+        let point_error = point_error * gamma(7);
+
+        let uv_hit = Point2f::new(
+            uv[0].x * b[0] + uv[1].x * b[1] + uv[2].x * b[2],
+            uv[0].y * b[0] + uv[1].y * b[1] + uv[2].y * b[2],
+        );
+
+        let mut ng = vec_to_normal(geometric_normal.normalized());
+
+        // Shading frame from interpolated normals and tangents
+        let (ns, shading_dpdu, shading_dpdv) = match self.interpolated_normal(b) {
+            Some(ns) => {
+                ng = ng.face_forward(ns);
+
+                let ss = self.interpolated_tangent(b).unwrap_or(dpdu);
+                let ts = normal_to_vec(ns).cross(ss);
+                let (ss, ts) = if ts.length_squared() > 0.0 {
+                    let ts = ts.normalized();
+                    (ts.cross(normal_to_vec(ns)), ts)
+                } else {
+                    coordinate_system(normal_to_vec(ns))
+                };
+
+                (ns, ss, ts)
+            }
+            None => (ng, dpdu, dpdv),
+        };
+        let (shading_dndu, shading_dndv) = self.normal_derivatives(duv02, duv12, determinant);
+
+        Some((
+            LocalGeometry {
+                point,
+                point_error,
+                ns,
+                ng,
+                uv: uv_hit,
+                dpdu,
+                dpdv,
+                shading_dpdu,
+                shading_dpdv,
+                shading_dndu,
+                shading_dndv,
+                color: self.interpolated_color(b),
+                time: ray.time,
+            },
+            t,
+        ))
+    }
+
+    fn local_intersect_p(&self, ray: &LocalRay, _o_err: LocalVec3f, _d_err: LocalVec3f) -> bool {
+        self.hit(ray).is_some()
+    }
+
+    fn area(&self) -> Float {
+        let [p0, p1, p2] = self.positions();
+        0.5 * (p1 - p0).cross(p2 - p0).length()
+    }
+}
+
+impl Sampleable for Triangle {
+    fn sample_shape(
+        &self,
+        int: &Interaction,
+        transform: &TransformPair,
+        samples: (Float, Float),
+    ) -> Interaction {
+        let [p0, p1, p2] = self.positions();
+        let (b0, b1) = sample::uniform_triangle(samples);
+        let b = [b0, b1, 1.0 - b0 - b1];
+
+        let point = p0.to_vec() * b[0] + p1.to_vec() * b[1] + p2.to_vec() * b[2];
+        let point_error =
+            ((p0.to_vec() * b[0]).abs() + (p1.to_vec() * b[1]).abs() + (p2.to_vec() * b[2]).abs())
+                * gamma(6);
+
+        let mut normal = vec_to_normal((p1 - p0).cross(p2 - p0).normalized());
+        if let Some(ns) = self.interpolated_normal(b) {
+            normal = normal.face_forward(ns);
+        }
+
+        let (world_point, point_error) = transform
+            .to_global
+            .apply_point_with_error(Point3f::from(point.as_global()), point_error.as_global());
+
+        let world_normal = transform
+            .to_local
+            .apply_normal(normal.as_global())
+            .normalized();
+
+        Interaction {
+            point: world_point,
+            normal: world_normal,
+            point_error,
+            wo: Vec3f::default(),
+            time: int.time,
+        }
+    }
+}
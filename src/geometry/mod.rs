@@ -1,13 +1,17 @@
+use crate::film::spectrum::Spectrum;
 use crate::light::Light;
 use crate::material::Material;
 use crate::math::*;
 use std::sync::Arc;
 
+pub mod bvh;
+pub mod instance;
 pub mod interaction;
 pub mod primitive;
 pub mod receiver;
 
 pub mod disk;
+pub mod mesh;
 pub mod sphere;
 
 pub use interaction::{Interaction, Shading, SurfaceInteraction};
@@ -21,6 +25,11 @@ pub struct LocalGeometry {
     pub uv: Point2f,
     pub dpdu: LocalVec3f,
     pub dpdv: LocalVec3f,
+    pub shading_dpdu: LocalVec3f,
+    pub shading_dpdv: LocalVec3f,
+    pub shading_dndu: LocalNormal3f,
+    pub shading_dndv: LocalNormal3f,
+    pub color: Option<Spectrum>,
     pub time: Float,
 }
 
@@ -54,12 +63,22 @@ impl LocalGeometry {
             },
             shading: Shading {
                 normal: ns,
-                dpdu: m.to_global.apply(self.dpdu.as_global()),
-                dpdv: m.to_global.apply(self.dpdu.as_global()),
+                dpdu: m.to_global.apply(self.shading_dpdu.as_global()),
+                dpdv: m.to_global.apply(self.shading_dpdv.as_global()),
+                dndu: m.to_local.apply_normal(self.shading_dndu.as_global()),
+                dndv: m.to_local.apply_normal(self.shading_dndv.as_global()),
             },
             uv: self.uv,
             dpdu: m.to_global.apply(self.dpdu.as_global()),
             dpdv: m.to_global.apply(self.dpdv.as_global()),
+            dpdx: Vec3f::default(),
+            dpdy: Vec3f::default(),
+            dudx: 0.0,
+            dvdx: 0.0,
+            dudy: 0.0,
+            dvdy: 0.0,
+            color: self.color,
+            object_to_world: *m,
             bsdf: None,
             material: Some(material),
             geometry: Some(geometry),
@@ -80,6 +99,11 @@ pub trait Geometry: LocalAABB + IntoGeometry {
         d_err: LocalVec3f,
     ) -> Option<(LocalGeometry, Float)>;
 
+    // Whether the ray hits at all, without working out anything about the surface
+    fn local_intersect_p(&self, ray: &LocalRay, o_err: LocalVec3f, d_err: LocalVec3f) -> bool {
+        self.local_intersect(ray, o_err, d_err).is_some()
+    }
+
     fn area(&self) -> Float;
 }
 
@@ -93,7 +117,7 @@ pub trait Sampleable: Geometry {
 
     fn pdf(&self, int: &Interaction, transform: &TransformPair, dir: Vec3f) -> Float {
         let ray = int.spawn_ray(dir);
-        let (local_ray, o_err, d_err) = transform.to_local.apply_ray_with_error(&ray);
+        let (local_ray, o_err, d_err, _) = transform.to_local.apply_ray_with_error(&ray);
         match self.local_intersect(&local_ray.as_local(), o_err.as_local(), d_err.as_local()) {
             Some((lg, _)) => {
                 let hit_point = transform.to_global.apply_point(lg.point.as_global());
@@ -110,7 +134,9 @@ pub trait AABB {
 }
 
 pub trait Hit {
-    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction, Float)>;
+    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)>;
+    // Any hit before ray.t_max, for shadow rays
+    fn intersect_p(&self, ray: &Ray) -> bool;
 }
 
 // Allows casting Arc<dyn Sampleable> -> Arc<dyn Geometry>, for example.
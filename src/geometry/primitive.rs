@@ -1,14 +1,13 @@
-use super::{receiver, SurfaceInteraction};
+use super::{instance, receiver, SurfaceInteraction};
 use crate::geometry::{Hit, AABB};
 use crate::light::emitter;
 use crate::math::*;
-use bvh::aabb::Bounded;
-use bvh::bounding_hierarchy::BHShape;
 
 #[derive(Clone)]
 pub enum Primitive {
     Receiver(receiver::Receiver),
     Emitter(emitter::Emitter),
+    Instance(instance::Instance),
 }
 
 impl AABB for Primitive {
@@ -16,49 +15,25 @@ impl AABB for Primitive {
         match self {
             Primitive::Emitter(e) => e.aabb(),
             Primitive::Receiver(r) => r.aabb(),
+            Primitive::Instance(i) => i.aabb(),
         }
     }
 }
 
 impl Hit for Primitive {
-    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction, Float)> {
+    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)> {
         match self {
             Primitive::Emitter(e) => e.intersect(ray),
             Primitive::Receiver(r) => r.intersect(ray),
+            Primitive::Instance(i) => i.intersect(ray),
         }
     }
-}
-
-pub struct BVHPrimitive {
-    primitive: Primitive,
-    node_index: usize,
-}
 
-impl BVHPrimitive {
-    pub fn new(primitive: Primitive) -> Self {
-        Self {
-            primitive,
-            node_index: 0,
+    fn intersect_p(&self, ray: &Ray) -> bool {
+        match self {
+            Primitive::Emitter(e) => e.intersect_p(ray),
+            Primitive::Receiver(r) => r.intersect_p(ray),
+            Primitive::Instance(i) => i.intersect_p(ray),
         }
     }
-
-    pub fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction, Float)> {
-        self.primitive.intersect(ray)
-    }
-}
-
-impl BHShape for BVHPrimitive {
-    fn set_bh_node_index(&mut self, index: usize) {
-        self.node_index = index;
-    }
-
-    fn bh_node_index(&self) -> usize {
-        self.node_index
-    }
-}
-
-impl Bounded for BVHPrimitive {
-    fn aabb(&self) -> bvh::aabb::AABB {
-        self.primitive.aabb().to_aabb()
-    }
 }
@@ -7,34 +7,33 @@ use std::sync::Arc;
 pub struct Receiver {
     geometry: Arc<dyn Geometry + Send + Sync>,
     material: Arc<dyn Material + Send + Sync>,
-    transform: TransformPair,
+    transform: AnimatedTransform,
 }
 
 impl Receiver {
     pub fn new(
         geometry: Arc<dyn Geometry + Send + Sync>,
         material: Arc<dyn Material + Send + Sync>,
-        obj_to_world: Transform,
+        obj_to_world: AnimatedTransform,
     ) -> Self {
         Self {
             geometry,
             material,
-            transform: TransformPair::from(obj_to_world),
+            transform: obj_to_world,
         }
     }
 }
 
 impl AABB for Receiver {
     fn aabb(&self) -> Bounds3f {
-        self.transform
-            .to_global
-            .apply_bounds(self.geometry.local_aabb())
+        self.transform.motion_bounds(self.geometry.local_aabb())
     }
 }
 
 impl Hit for Receiver {
-    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction, Float)> {
-        let (local_ray, o_err, d_err) = self.transform.to_local.apply_ray_with_error(ray);
+    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)> {
+        let transform = self.transform.at(ray.time);
+        let (local_ray, o_err, d_err, dt) = transform.to_local.apply_ray_with_error(ray);
 
         let (lg, local_ray_t) = self.geometry.local_intersect(
             &local_ray.as_local(),
@@ -43,13 +42,21 @@ impl Hit for Receiver {
         )?;
 
         let si = lg.into_surface_interaction(
-            &self.transform,
+            &transform,
             ray,
             self.material.clone(),
             self.geometry.clone(),
             None,
         );
 
-        Some((si, local_ray.as_local().global_t(local_ray_t, ray)))
+        // The local ray direction isn't normalized, so t only differs by the origin offset
+        Some((si, local_ray_t + dt))
+    }
+
+    fn intersect_p(&self, ray: &Ray) -> bool {
+        let transform = self.transform.at(ray.time);
+        let (local_ray, o_err, d_err, _) = transform.to_local.apply_ray_with_error(ray);
+        self.geometry
+            .local_intersect_p(&local_ray.as_local(), o_err.as_local(), d_err.as_local())
     }
 }
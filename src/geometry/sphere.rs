@@ -25,33 +25,7 @@ impl Geometry for Sphere {
         o_err: LocalVec3f,
         d_err: LocalVec3f,
     ) -> Option<(LocalGeometry, Float)> {
-        let ox = EFloat::new(ray.o.x, o_err.x);
-        let oy = EFloat::new(ray.o.y, o_err.y);
-        let oz = EFloat::new(ray.o.z, o_err.z);
-        let dx = EFloat::new(ray.d.x, d_err.x);
-        let dy = EFloat::new(ray.d.y, d_err.y);
-        let dz = EFloat::new(ray.d.z, d_err.z);
-
-        let (t0, t1) = {
-            let a = dx * dx + dy * dy + dz * dz;
-            let b = (dx * ox + dy * oy + dz * oz) * 2.0.into();
-            let c = (ox * ox + oy * oy + oz * oz) - self.radius.powi(2).into();
-            solve_efloat_quadratic(a, b, c)?
-        };
-
-        if t0.upper_bound() > ray.t_max || t1.lower_bound() < 0.0 {
-            return None;
-        }
-
-        let t_hit = if t0.lower_bound() <= 0.0 {
-            if t1.upper_bound() > ray.t_max {
-                return None;
-            }
-
-            t1
-        } else {
-            t0
-        };
+        let t_hit = self.hit(ray, o_err, d_err)?;
 
         let point = {
             // Refine intersection
@@ -105,18 +79,59 @@ impl Geometry for Sphere {
                 uv: Point2f::new(u, v),
                 dpdu,
                 dpdv,
+                shading_dpdu: dpdu,
+                shading_dpdv: dpdv,
+                // The normal is the point over the radius, so it changes the same way
+                shading_dndu: LocalNormal3f::new(dpdu.x, dpdu.y, dpdu.z) / self.radius,
+                shading_dndv: LocalNormal3f::new(dpdv.x, dpdv.y, dpdv.z) / self.radius,
+                color: None,
                 time: ray.time,
             },
             t_hit.val(),
         ))
     }
 
+    fn local_intersect_p(&self, ray: &LocalRay, o_err: LocalVec3f, d_err: LocalVec3f) -> bool {
+        self.hit(ray, o_err, d_err).is_some()
+    }
+
     fn area(&self) -> Float {
         4.0 * Float::PI() * self.radius * self.radius
     }
 }
 
 impl Sphere {
+    // Nearest ray parameter at which the ray conservatively hits the sphere, if it does
+    fn hit(&self, ray: &LocalRay, o_err: LocalVec3f, d_err: LocalVec3f) -> Option<EFloat> {
+        let ox = EFloat::new(ray.o.x, o_err.x);
+        let oy = EFloat::new(ray.o.y, o_err.y);
+        let oz = EFloat::new(ray.o.z, o_err.z);
+        let dx = EFloat::new(ray.d.x, d_err.x);
+        let dy = EFloat::new(ray.d.y, d_err.y);
+        let dz = EFloat::new(ray.d.z, d_err.z);
+
+        let (t0, t1) = {
+            let a = dx * dx + dy * dy + dz * dz;
+            let b = (dx * ox + dy * oy + dz * oz) * 2.0.into();
+            let c = (ox * ox + oy * oy + oz * oz) - self.radius.powi(2).into();
+            solve_efloat_quadratic(a, b, c)?
+        };
+
+        if t0.upper_bound() > ray.t_max || t1.lower_bound() < 0.0 {
+            return None;
+        }
+
+        if t0.lower_bound() <= 0.0 {
+            if t1.upper_bound() > ray.t_max {
+                return None;
+            }
+
+            Some(t1)
+        } else {
+            Some(t0)
+        }
+    }
+
     fn sample_uniform(
         &self,
         int: &Interaction,
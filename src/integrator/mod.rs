@@ -3,11 +3,11 @@ use crate::film::spectrum::Spectrum;
 use crate::geometry::SurfaceInteraction;
 use crate::light::emitter::Emitter;
 use crate::light::Light;
-use crate::math::ray::Ray;
 use crate::math::*;
 use crate::sampler::Sampler;
 use crate::scene::Scene;
 use bumpalo::Bump;
+use std::sync::Arc;
 
 pub mod normals;
 pub mod path;
@@ -16,17 +16,18 @@ pub mod whitted;
 pub trait Integrator {
     fn radiance(
         &self,
-        ray: &Ray,
+        ray: &RayDifferential,
         scene: &Scene,
         sampler: &mut (dyn Sampler + Send + Sync),
         alloc: &Bump,
         depth: i32,
     ) -> Spectrum;
 
+    // Follows one perfectly specular lobe, reflected or refracted, chosen by the BSDF
     #[allow(clippy::too_many_arguments)]
-    fn specular_reflection(
+    fn specular_bounce(
         &self,
-        ray: &Ray,
+        ray: &RayDifferential,
         scene: &Scene,
         sampler: &mut (dyn Sampler + Send + Sync),
         alloc: &Bump,
@@ -35,18 +36,22 @@ pub trait Integrator {
         depth: i32,
     ) -> Spectrum {
         let ns = bsdf.ns.to_vec();
-        let wo = -ray.d;
-        let (f, wi, pdf, _types) = bsdf.sample(
+        let wo = -ray.ray.d;
+        let (f, wi, pdf, types) = bsdf.sample(
             wo,
-            BxDFType::REFLECTION | BxDFType::SPECULAR,
+            BxDFType::REFLECTION | BxDFType::TRANSMISSION | BxDFType::SPECULAR,
             sampler.get_2d(),
         );
 
         let n_dot_wi = wi.dot(ns).abs();
 
         if pdf > 0.0 && !f.is_black() && n_dot_wi != 0.0 {
-            let reflected_ray = hit.int.spawn_ray(wi);
-            let li = self.radiance(&reflected_ray, scene, sampler, alloc, depth + 1);
+            let spawned = if types.contains(BxDFType::TRANSMISSION) {
+                hit.spawn_refracted_ray(ray, wi, bsdf.eta)
+            } else {
+                hit.spawn_reflected_ray(ray, wi)
+            };
+            let li = self.radiance(&spawned, scene, sampler, alloc, depth + 1);
             f * li * n_dot_wi / pdf
         } else {
             Spectrum::all(0.0)
@@ -107,14 +112,15 @@ pub trait Integrator {
         if light_pdf > 0.0 && !li.is_black() {
             let f = bsdf.eval(hit.int.wo, wi, flags);
             let scattering_pdf = bsdf.pdf(hit.int.wo, wi, flags);
-            // TODO: Why is n dot l sometimes negative?
-            if !f.is_black() && wi.dot_nrm(hit.shading.normal) >= 0.0 && vis.visible(scene) {
+            // Lights behind the surface only contribute through transmission, which the BSDF
+            // takes care of
+            if !f.is_black() && vis.visible(scene) {
                 let weight = if light.is_delta() {
                     1.0
                 } else {
                     power_heuristic(1, light_pdf, 1, scattering_pdf)
                 };
-                out += f * li * wi.dot_nrm(bsdf.ns) * weight / light_pdf;
+                out += f * li * wi.dot_nrm(bsdf.ns).abs() * weight / light_pdf;
             }
         }
 
@@ -127,7 +133,7 @@ pub trait Integrator {
                 return out;
             }
 
-            let weight = if sampled_specular {
+            let weight = if !sampled_specular {
                 light_pdf = light.pdf_incoming(&hit.int, wi);
                 if light_pdf == 0.0 {
                     return out;
@@ -142,8 +148,11 @@ pub trait Integrator {
                 Some(isect) => {
                     let mut li = Spectrum::default();
                     if let Some(e) = isect.light {
-                        // Bit of a hack
-                        if e as *const Light == &*light.light as *const Light {
+                        // Only the data pointers are compared, vtable pointers for the same
+                        // type aren't guaranteed to be unique
+                        let hit_light = e as *const (dyn Light + Send + Sync) as *const u8;
+                        let sampled_light = Arc::as_ptr(&light.light) as *const u8;
+                        if hit_light == sampled_light {
                             li = e.radiance(&isect.int, -wi);
                         }
                     }
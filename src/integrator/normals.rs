@@ -12,13 +12,13 @@ pub struct Normals;
 impl Integrator for Normals {
     fn radiance(
         &self,
-        ray: &Ray,
+        ray: &RayDifferential,
         scene: &Scene,
         _sampler: &mut (dyn Sampler + Send + Sync),
         arena: &Bump,
         _depth: i32,
     ) -> Spectrum {
-        if let Some(hit) = scene.intersect(ray) {
+        if let Some(hit) = scene.intersect(&ray.ray) {
             let bsdf = hit.compute_bsdf(arena);
             let ns = bsdf.ns.normalized();
             Spectrum::from_rgb(ns.x, ns.y, ns.z) / 2.0 + 0.5
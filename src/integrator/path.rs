@@ -16,7 +16,7 @@ pub struct Path {
 impl Integrator for Path {
     fn radiance(
         &self,
-        ray: &Ray,
+        ray: &RayDifferential,
         scene: &Scene,
         sampler: &mut (dyn Sampler + Send + Sync),
         arena: &Bump,
@@ -26,18 +26,21 @@ impl Integrator for Path {
         let mut beta = Spectrum::all(1.0);
         let mut ray = *ray;
         let mut specular_bounce = false;
+        // Refraction scales radiance by the squared ratio of the indices, which Russian
+        // roulette shouldn't count as the path losing energy
+        let mut eta_scale: Float = 1.0;
 
         for bounces in 0..self.max_depth {
-            let opt_isect = scene.intersect(&ray);
+            match scene.intersect(&ray.ray) {
+                Some(mut hit) => {
+                    hit.compute_differentials(&ray);
 
-            match &opt_isect {
-                Some(hit) => {
                     assert!(out.y() >= 0.0);
                     if bounces == 0 || specular_bounce {
                         out += beta
                             * hit
                                 .light
-                                .map(|l| l.radiance(&hit.int, -ray.d))
+                                .map(|l| l.radiance(&hit.int, -ray.ray.d))
                                 .unwrap_or_default();
                         assert!(out.y() >= 0.0);
                     }
@@ -46,18 +49,33 @@ impl Integrator for Path {
                     out += beta * self.uniform_sample_one(scene, sampler, &bsdf, &hit);
                     assert!(out.y() >= 0.0);
 
-                    let wo = -ray.d;
+                    let wo = -ray.ray.d;
                     let (f, wi, pdf, flags) = bsdf.sample(wo, BxDFType::ALL, sampler.get_2d());
                     if pdf == 0.0 || f.is_black() {
                         break;
                     }
 
                     specular_bounce = flags.contains(BxDFType::SPECULAR);
-                    beta *= f * wi.dot_nrm(hit.shading.normal).abs() / pdf;
-                    ray = hit.int.spawn_ray(wi);
+                    beta *= f * wi.dot_nrm(bsdf.ns).abs() / pdf;
+                    if flags.contains(BxDFType::TRANSMISSION) {
+                        eta_scale *= if wo.dot_nrm(bsdf.ns) > 0.0 {
+                            bsdf.eta * bsdf.eta
+                        } else {
+                            1.0 / (bsdf.eta * bsdf.eta)
+                        };
+                    }
+
+                    // Differentials only stay meaningful through perfectly specular bounces
+                    ray = if flags.contains(BxDFType::SPECULAR | BxDFType::REFLECTION) {
+                        hit.spawn_reflected_ray(&ray, wi)
+                    } else if flags.contains(BxDFType::SPECULAR | BxDFType::TRANSMISSION) {
+                        hit.spawn_refracted_ray(&ray, wi, bsdf.eta)
+                    } else {
+                        RayDifferential::from(hit.int.spawn_ray(wi))
+                    };
 
                     if bounces > self.min_depth {
-                        let q = (1.0 - beta.y()).max(0.05);
+                        let q = (1.0 - (beta * eta_scale).y()).max(0.05);
                         if sampler.get_1d() < q {
                             break;
                         }
@@ -15,7 +15,7 @@ pub struct Whitted {
 impl Integrator for Whitted {
     fn radiance(
         &self,
-        ray: &Ray,
+        ray: &RayDifferential,
         scene: &Scene,
         sampler: &mut (dyn Sampler + Send + Sync),
         arena: &Bump,
@@ -27,10 +27,11 @@ impl Integrator for Whitted {
 
         let mut out = Spectrum::black();
 
-        if let Some(hit) = scene.intersect(ray) {
+        if let Some(mut hit) = scene.intersect(&ray.ray) {
+            hit.compute_differentials(ray);
             let bsdf = hit.compute_bsdf(arena);
 
-            let wo = -ray.d;
+            let wo = -ray.ray.d;
 
             if let Some(e) = hit.light {
                 out += e.radiance(&hit.int, wo);
@@ -64,7 +65,7 @@ impl Integrator for Whitted {
             }
 
             // Evaluate specular contribution
-            // out += self.specular_reflection(ray, scene, sampler, arena, &bsdf, &hit, depth);
+            out += self.specular_bounce(ray, scene, sampler, arena, &bsdf, &hit, depth);
         }
 
         out
@@ -0,0 +1,53 @@
+//! A physically based CPU ray tracer.
+//!
+//! The quickest way in is a scene in the text format (see `scenes/` in the repository), loaded
+//! with [`load_scene`] or [`parse_scene`] and rendered into memory with [`render_to_buffer`]:
+//!
+//! ```
+//! use std::path::Path;
+//!
+//! let src = r#"
+//!     film { resolution 8 4 }
+//!     sampler "random" { spp 1 }
+//!     camera "perspective" { fov 45 }
+//!     material "white" "matte" { diffuse 0.8 }
+//!     shape "sphere" { radius 1 material "white" transform { translate 0 0 4 } }
+//!     light "point" { intensity 10 position 0 2 0 }
+//! "#;
+//!
+//! let desc = rt::parse_scene(src, Path::new(".")).unwrap();
+//! let image = rt::render_to_buffer(&desc.scene, &desc.settings);
+//! assert_eq!((image.width, image.height, image.channels), (8, 4, 3));
+//! assert_eq!(image.pixels.len(), 8 * 4 * 3);
+//! ```
+//!
+//! Scenes can also be put together in code from the [`geometry`], [`material`], [`texture`] and
+//! [`light`] modules. [`render`] takes the film, camera, sampler and integrator separately and
+//! reports progress as tiles finish, which is what the `rt` command line tool uses.
+
+#[macro_use]
+extern crate derive_new;
+
+#[macro_use]
+extern crate bitflags;
+
+extern crate nalgebra as na;
+
+mod bxdf;
+pub mod film;
+pub mod geometry;
+pub mod integrator;
+pub mod light;
+pub mod loader;
+pub mod material;
+pub mod math;
+mod render;
+pub mod sampler;
+pub mod scene;
+pub mod settings;
+pub mod texture;
+
+pub use loader::{load_scene, parse_scene, SceneDescription};
+pub use render::{render, render_to_buffer, RenderedImage};
+pub use scene::Scene;
+pub use settings::RenderSettings;
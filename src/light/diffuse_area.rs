@@ -12,13 +12,13 @@ pub struct DiffuseArea {
     emission: Spectrum,
     geometry: Arc<dyn Sampleable + Send + Sync>,
     material: Arc<dyn Material + Send + Sync>,
-    transform: TransformPair,
+    transform: AnimatedTransform,
 }
 
 impl DiffuseArea {
     pub fn new(
         emission: Spectrum,
-        transform: Transform,
+        transform: AnimatedTransform,
         geometry: Arc<dyn Sampleable + Send + Sync>,
         material: Arc<dyn Material + Send + Sync>,
     ) -> Self {
@@ -26,7 +26,7 @@ impl DiffuseArea {
             emission,
             geometry,
             material,
-            transform: TransformPair::from(transform),
+            transform,
         }
     }
 }
@@ -45,10 +45,11 @@ impl Light for DiffuseArea {
         int: &Interaction,
         samples: (Float, Float),
     ) -> (Spectrum, Vec3f, Float) {
-        let light_int = self.geometry.sample_shape(int, &self.transform, samples);
+        let transform = self.transform.at(int.time);
+        let light_int = self.geometry.sample_shape(int, &transform, samples);
 
         let dir = light_int.point - int.point;
-        let pdf = self.geometry.pdf(int, &self.transform, dir);
+        let pdf = self.geometry.pdf(int, &transform, dir);
 
         (self.radiance(&light_int, -dir), dir, pdf)
     }
@@ -58,21 +59,20 @@ impl Light for DiffuseArea {
     }
 
     fn pdf_incoming(&self, int: &Interaction, wi: Vec3f) -> Float {
-        self.geometry.pdf(int, &self.transform, wi)
+        self.geometry.pdf(int, &self.transform.at(int.time), wi)
     }
 }
 
 impl AABB for DiffuseArea {
     fn aabb(&self) -> Bounds3f {
-        self.transform
-            .to_global
-            .apply_bounds(self.geometry.local_aabb())
+        self.transform.motion_bounds(self.geometry.local_aabb())
     }
 }
 
 impl Hit for DiffuseArea {
-    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction, Float)> {
-        let (local_ray, o_err, d_err) = self.transform.to_local.apply_ray_with_error(ray);
+    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)> {
+        let transform = self.transform.at(ray.time);
+        let (local_ray, o_err, d_err, dt) = transform.to_local.apply_ray_with_error(ray);
 
         let (lg, local_ray_t) = self.geometry.local_intersect(
             &local_ray.as_local(),
@@ -81,13 +81,21 @@ impl Hit for DiffuseArea {
         )?;
 
         let si = lg.into_surface_interaction(
-            &self.transform,
+            &transform,
             ray,
             self.material.clone(),
             self.geometry.clone().into_geometry(),
             Some(self),
         );
 
-        Some((si, local_ray.as_local().global_t(local_ray_t, ray)))
+        // The local ray direction isn't normalized, so t only differs by the origin offset
+        Some((si, local_ray_t + dt))
+    }
+
+    fn intersect_p(&self, ray: &Ray) -> bool {
+        let transform = self.transform.at(ray.time);
+        let (local_ray, o_err, d_err, _) = transform.to_local.apply_ray_with_error(ray);
+        self.geometry
+            .local_intersect_p(&local_ray.as_local(), o_err.as_local(), d_err.as_local())
     }
 }
@@ -46,7 +46,7 @@ impl Emitter {
     #[allow(dead_code)]
     pub fn new_area(
         intensity: Spectrum,
-        transform: Transform,
+        transform: AnimatedTransform,
         geometry: Arc<dyn Sampleable + Send + Sync>,
         material: Arc<dyn Material + Send + Sync>,
     ) -> Self {
@@ -96,7 +96,11 @@ impl AABB for Emitter {
 }
 
 impl Hit for Emitter {
-    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction, Float)> {
+    fn intersect(&self, ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)> {
         self.light.intersect(ray)
     }
+
+    fn intersect_p(&self, ray: &Ray) -> bool {
+        self.light.intersect_p(ray)
+    }
 }
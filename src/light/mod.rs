@@ -46,7 +46,7 @@ impl Visibility {
             self.normal,
             self.time,
         );
-        scene.intersect(&ray).is_none()
+        !scene.intersect_p(&ray)
     }
 }
 
@@ -45,7 +45,11 @@ impl AABB for Point {
 }
 
 impl Hit for Point {
-    fn intersect(&self, _ray: &Ray) -> Option<(SurfaceInteraction, Float)> {
+    fn intersect(&self, _ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)> {
+        unreachable!()
+    }
+
+    fn intersect_p(&self, _ray: &Ray) -> bool {
         unreachable!()
     }
 }
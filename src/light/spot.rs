@@ -79,7 +79,11 @@ impl AABB for Spot {
 }
 
 impl Hit for Spot {
-    fn intersect(&self, _ray: &Ray) -> Option<(SurfaceInteraction, Float)> {
+    fn intersect(&self, _ray: &Ray) -> Option<(SurfaceInteraction<'_>, Float)> {
+        unreachable!()
+    }
+
+    fn intersect_p(&self, _ray: &Ray) -> bool {
         unreachable!()
     }
 }
@@ -0,0 +1,1648 @@
+use super::parser::Node;
+use super::{gltf, lens, obj, ply, Error, SceneDescription};
+use crate::film::camera::{
+    BrownConrady, CameraMotion, FieldOfView, FisheyeMapping, Intrinsics, LensSystem, ThinLens,
+};
+use crate::film::spectrum::Spectrum;
+use crate::geometry::Sampleable;
+use crate::geometry::{
+    disk::Disk,
+    instance::{Instance, Prototype},
+    mesh::TriangleMesh,
+    primitive::Primitive,
+    receiver::Receiver,
+    sphere::Sphere,
+};
+use crate::light::emitter::Emitter;
+use crate::material::{
+    glass::Glass,
+    matte::Matte,
+    mirror::Mirror,
+    plastic::Plastic,
+    rough_glass::{Distribution, RoughGlass},
+    Material, NormalMap, NormalMapConvention,
+};
+use crate::math::*;
+use crate::scene::Scene;
+use crate::settings::{CameraSettings, FilterSettings, IntegratorSettings, RenderSettings};
+use crate::texture::{
+    bilerp::BilerpTexture,
+    checkerboard::{Checkerboard2D, Checkerboard3D},
+    constant::ConstantTexture,
+    dots::DotsTexture,
+    grid::GridTexture,
+    image::ImageTexture,
+    mapping::{TextureMapping2D, TextureMapping3D, TexturePlacement},
+    marble::MarbleTexture,
+    mipmap::{FilterMode, WrapMode},
+    mix::MixTexture,
+    noise::{FBmTexture, WrinkledTexture},
+    scale::ScaleTexture,
+    vertex_color::VertexColorTexture,
+    wood::WoodTexture,
+    Tex, Texel,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type SpectrumTexture = Tex<Spectrum>;
+type FloatTexture = Tex<Float>;
+
+// Lets texture classes that work for both spectrum and float textures share their parsing
+trait TextureValue: Texel + Send + Sync + 'static {
+    const TYPE: &'static str;
+    // Colors are usually stored as sRGB and data like roughness as is
+    const DEFAULT_ENCODING: &'static str;
+
+    fn parse(node: &Node) -> Result<Self, Error>;
+    fn textures(builder: &SceneBuilder) -> &HashMap<String, Tex<Self>>;
+}
+
+impl TextureValue for Spectrum {
+    const TYPE: &'static str = "spectrum";
+    const DEFAULT_ENCODING: &'static str = "srgb";
+
+    fn parse(node: &Node) -> Result<Self, Error> {
+        node.spectrum()
+    }
+
+    fn textures(builder: &SceneBuilder) -> &HashMap<String, SpectrumTexture> {
+        &builder.spectrum_textures
+    }
+}
+
+impl TextureValue for Float {
+    const TYPE: &'static str = "float";
+    const DEFAULT_ENCODING: &'static str = "linear";
+
+    fn parse(node: &Node) -> Result<Self, Error> {
+        node.float()
+    }
+
+    fn textures(builder: &SceneBuilder) -> &HashMap<String, FloatTexture> {
+        &builder.float_textures
+    }
+}
+
+fn unknown_texture_class(node: &Node, class: &str, specific: &str) -> Error {
+    node.error(format!(
+        "unknown texture class '{}' (expected constant, image, checkerboard, grid, dots, scale, \
+         mix, bilerp, {})",
+        class, specific
+    ))
+}
+
+fn octaves(node: &Node) -> Result<u32, Error> {
+    let octaves = node.int_or("octaves", 8)?;
+    if octaves < 1 {
+        return Err(node.error("'octaves' must be at least 1"));
+    }
+    Ok(octaves as u32)
+}
+
+// 2D textures take a 'mapping' child, or just a 'scale' for uv coordinates
+fn texture_mapping_2d(node: &Node) -> Result<TextureMapping2D, Error> {
+    let mapping = match node.child("mapping") {
+        Some(mapping) => mapping,
+        None => {
+            let scale = node.float_or("scale", 1.0)?;
+            return Ok(TextureMapping2D::UV {
+                scale: Vec2f::new(scale, scale),
+                offset: Vec2f::new(0.0, 0.0),
+                rotation: 0.0,
+            });
+        }
+    };
+    if node.child("scale").is_some() {
+        return Err(node.error("'scale' can't be combined with a 'mapping'"));
+    }
+
+    let placement = || texture_placement(mapping, transform(mapping.child("transform"))?);
+    Ok(match mapping.string_arg(0, "a texture mapping")? {
+        "uv" => {
+            mapping.expect_children(&["scale", "offset", "rotate"])?;
+            let scale = match mapping.child("scale") {
+                Some(child) => {
+                    let f = child.floats()?;
+                    match f.len() {
+                        1 => Vec2f::new(f[0], f[0]),
+                        2 => Vec2f::new(f[0], f[1]),
+                        n => {
+                            return Err(
+                                child.error(format!("'scale' expects 1 or 2 numbers, found {}", n))
+                            );
+                        }
+                    }
+                }
+                None => Vec2f::new(1.0, 1.0),
+            };
+            let offset = match mapping.child("offset") {
+                Some(child) => {
+                    let f = child.floats_n(2)?;
+                    Vec2f::new(f[0], f[1])
+                }
+                None => Vec2f::new(0.0, 0.0),
+            };
+            TextureMapping2D::UV {
+                scale,
+                offset,
+                rotation: mapping.float_or("rotate", 0.0)?.to_radians(),
+            }
+        }
+        "spherical" => {
+            mapping.expect_children(&["space", "transform"])?;
+            TextureMapping2D::Spherical {
+                placement: placement()?,
+            }
+        }
+        "cylindrical" => {
+            mapping.expect_children(&["space", "transform"])?;
+            TextureMapping2D::Cylindrical {
+                placement: placement()?,
+            }
+        }
+        "planar" => {
+            mapping.expect_children(&["space", "transform"])?;
+            TextureMapping2D::Planar {
+                placement: placement()?,
+            }
+        }
+        "triplanar" => {
+            mapping.expect_children(&["sharpness", "space", "transform"])?;
+            let sharpness = mapping.float_or("sharpness", 4.0)?;
+            if sharpness < 0.0 {
+                return Err(mapping.error("'sharpness' can't be negative"));
+            }
+            TextureMapping2D::Triplanar {
+                placement: placement()?,
+                sharpness,
+            }
+        }
+        other => {
+            return Err(mapping.error(format!(
+                "unknown texture mapping '{}' (expected uv, spherical, cylindrical, planar or \
+                 triplanar)",
+                other
+            )));
+        }
+    })
+}
+
+// 3D textures are placed by a 'transform' child, and 'scale' repeats them that many times per
+// unit
+fn texture_mapping_3d(node: &Node) -> Result<TextureMapping3D, Error> {
+    let scale = node.float_or("scale", 1.0)?;
+    if scale <= 0.0 {
+        return Err(node.error("'scale' must be positive"));
+    }
+    let to_world = transform(node.child("transform"))?;
+    Ok(TextureMapping3D {
+        placement: texture_placement(
+            node,
+            to_world * Transform::scale(1.0 / scale, 1.0 / scale, 1.0 / scale),
+        )?,
+    })
+}
+
+// Texture space is placed in world space unless 'space' says it's relative to the object
+fn texture_placement(node: &Node, to_world: Transform) -> Result<TexturePlacement, Error> {
+    let object_space = match node.child("space") {
+        Some(space) => match space.string_arg(0, "a texture space")? {
+            "world" => false,
+            "object" => true,
+            other => {
+                return Err(space.error(format!(
+                    "unknown texture space '{}' (expected world or object)",
+                    other
+                )));
+            }
+        },
+        None => false,
+    };
+    Ok(TexturePlacement {
+        transform: TransformPair::from(to_world),
+        object_space,
+    })
+}
+
+pub struct SceneBuilder {
+    base_dir: PathBuf,
+    spectrum_textures: HashMap<String, SpectrumTexture>,
+    float_textures: HashMap<String, FloatTexture>,
+    materials: HashMap<String, Arc<dyn Material + Send + Sync>>,
+    primitives: Vec<Primitive>,
+    objects: HashMap<String, Arc<Prototype>>,
+    imported_camera: Option<gltf::Camera>,
+    warnings: Vec<String>,
+}
+
+fn set_once<'a>(slot: &mut Option<&'a Node>, node: &'a Node) -> Result<(), Error> {
+    if slot.is_some() {
+        return Err(node.error(format!("'{}' is specified more than once", node.name)));
+    }
+    *slot = Some(node);
+    Ok(())
+}
+
+impl SceneBuilder {
+    pub fn new(base_dir: &Path) -> Self {
+        Self {
+            base_dir: base_dir.to_path_buf(),
+            spectrum_textures: HashMap::new(),
+            float_textures: HashMap::new(),
+            materials: HashMap::new(),
+            primitives: Vec::new(),
+            objects: HashMap::new(),
+            imported_camera: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    // Builds a scene from a model file alone, with default render settings
+    pub fn build_model(mut self, path: &Path) -> Result<SceneDescription, Error> {
+        self.import_model(path, Transform::scale(1.0, 1.0, 1.0), None)
+            .map_err(Error::global)?;
+        self.build(&[])
+    }
+
+    pub fn build(mut self, nodes: &[Node]) -> Result<SceneDescription, Error> {
+        let mut film_node = None;
+        let mut camera_node = None;
+        let mut sampler_node = None;
+        let mut integrator_node = None;
+
+        for node in nodes {
+            match node.name.as_str() {
+                "film" => set_once(&mut film_node, node)?,
+                "camera" => set_once(&mut camera_node, node)?,
+                "sampler" => set_once(&mut sampler_node, node)?,
+                "integrator" => set_once(&mut integrator_node, node)?,
+                "texture" => self.texture(node)?,
+                "material" => self.material(node)?,
+                "shape" => self.shape(node)?,
+                "light" => self.light(node)?,
+                "import" => self.import(node)?,
+                "object" => self.object(node)?,
+                "instance" => self.instance(node)?,
+                name => {
+                    return Err(node.error(format!(
+                        "unknown directive '{}' (expected film, camera, sampler, integrator, \
+                         texture, material, shape, light, import, object or instance)",
+                        name
+                    )));
+                }
+            }
+        }
+
+        let camera = match (camera_node, &self.imported_camera) {
+            (Some(node), _) => camera(node, &self.base_dir)?,
+            (None, Some(imported)) => CameraSettings::Perspective {
+                motion: CameraMotion::from(imported.camera_to_world),
+                fov: FieldOfView::Degrees(imported.yfov_deg),
+                distortion: None,
+                overscan: 0.0,
+                lens: None,
+            },
+            (None, None) => return Err(Error::global("scene has no camera")),
+        };
+
+        let mut settings = RenderSettings {
+            width: 500,
+            height: 500,
+            filter: FilterSettings::default(),
+            spp: 16,
+            integrator: IntegratorSettings::default(),
+            camera,
+        };
+
+        if let Some(node) = film_node {
+            film(node, &mut settings)?;
+        }
+
+        if let (None, Some(imported)) = (camera_node, &self.imported_camera) {
+            if let (None, Some(aspect)) = (film_node, imported.aspect) {
+                settings.width = (settings.height as Float * aspect).round().max(1.0) as i32;
+            }
+
+            // Our field of view spans the shorter side of the image
+            let aspect = settings.width as Float / settings.height as Float;
+            if aspect < 1.0 {
+                let half_fov = (imported.yfov_deg.to_radians() / 2.0).tan() * aspect;
+                settings.camera = CameraSettings::Perspective {
+                    motion: CameraMotion::from(imported.camera_to_world),
+                    fov: FieldOfView::Degrees(2.0 * half_fov.atan().to_degrees()),
+                    distortion: None,
+                    overscan: 0.0,
+                    lens: None,
+                };
+            }
+        }
+
+        if let Some(node) = sampler_node {
+            settings.spp = sampler(node)?;
+        }
+
+        if let Some(node) = integrator_node {
+            settings.integrator = integrator(node)?;
+        }
+
+        if self.primitives.is_empty() {
+            return Err(Error::global("scene has no shapes or lights"));
+        }
+
+        Ok(SceneDescription {
+            scene: Scene::new(self.primitives),
+            settings,
+            warnings: self.warnings,
+        })
+    }
+
+    fn texture(&mut self, node: &Node) -> Result<(), Error> {
+        let name = node.string_arg(0, "a texture name")?.to_string();
+        let ty = node.string_arg(1, "a texture type")?;
+        let class = node.string_arg(2, "a texture class")?;
+
+        match ty {
+            "spectrum" => {
+                let texture: SpectrumTexture = match self.generic_texture(node, class)? {
+                    Some(texture) => texture,
+                    None => match class {
+                        "vertex_color" => {
+                            node.expect_children(&["default"])?;
+                            let fallback = match node.child("default") {
+                                Some(child) => child.spectrum()?,
+                                None => Spectrum::all(1.0),
+                            };
+                            Arc::new(VertexColorTexture::new(fallback))
+                        }
+                        "marble" => {
+                            node.expect_children(&[
+                                "scale",
+                                "space",
+                                "transform",
+                                "variation",
+                                "omega",
+                                "octaves",
+                            ])?;
+                            Arc::new(MarbleTexture::new(
+                                texture_mapping_3d(node)?,
+                                node.float_or("variation", 0.2)?,
+                                node.float_or("omega", 0.5)?,
+                                octaves(node)?,
+                            ))
+                        }
+                        "wood" => {
+                            node.expect_children(&[
+                                "light",
+                                "dark",
+                                "scale",
+                                "space",
+                                "transform",
+                                "distortion",
+                                "octaves",
+                            ])?;
+                            let color = |name, default| match node.child(name) {
+                                Some(child) => child.spectrum(),
+                                None => Ok(default),
+                            };
+                            Arc::new(WoodTexture::new(
+                                color("light", Spectrum::from_rgb(0.65, 0.45, 0.25))?,
+                                color("dark", Spectrum::from_rgb(0.35, 0.2, 0.1))?,
+                                texture_mapping_3d(node)?,
+                                node.float_or("distortion", 0.5)?,
+                                octaves(node)?,
+                            ))
+                        }
+                        _ => {
+                            return Err(unknown_texture_class(
+                                node,
+                                class,
+                                "vertex_color, marble, wood",
+                            ))
+                        }
+                    },
+                };
+                self.spectrum_textures.insert(name, texture);
+            }
+            "float" => {
+                let texture: FloatTexture = match self.generic_texture(node, class)? {
+                    Some(texture) => texture,
+                    None => match class {
+                        "fbm" => {
+                            node.expect_children(&[
+                                "scale",
+                                "space",
+                                "transform",
+                                "omega",
+                                "octaves",
+                            ])?;
+                            Arc::new(FBmTexture::new(
+                                texture_mapping_3d(node)?,
+                                node.float_or("omega", 0.5)?,
+                                octaves(node)?,
+                            ))
+                        }
+                        // PBRT's name for turbulence, both give the same texture
+                        "turbulence" | "wrinkled" => {
+                            node.expect_children(&[
+                                "scale",
+                                "space",
+                                "transform",
+                                "omega",
+                                "octaves",
+                            ])?;
+                            Arc::new(WrinkledTexture::new(
+                                texture_mapping_3d(node)?,
+                                node.float_or("omega", 0.5)?,
+                                octaves(node)?,
+                            ))
+                        }
+                        _ => {
+                            return Err(unknown_texture_class(
+                                node,
+                                class,
+                                "fbm, turbulence, wrinkled",
+                            ))
+                        }
+                    },
+                };
+                self.float_textures.insert(name, texture);
+            }
+            _ => {
+                return Err(node.error(format!(
+                    "unknown texture type '{}' (expected spectrum or float)",
+                    ty
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Classes that work the same way for spectrum and float textures, or None if the class
+    // isn't one of them
+    fn generic_texture<T: TextureValue>(
+        &self,
+        node: &Node,
+        class: &str,
+    ) -> Result<Option<Tex<T>>, Error> {
+        let texture: Tex<T> = match class {
+            "constant" => {
+                node.expect_children(&["value"])?;
+                Arc::new(ConstantTexture::new(T::parse(node.required("value")?)?))
+            }
+            "image" => Arc::new(self.image_texture::<T>(node, T::DEFAULT_ENCODING)?),
+            "checkerboard" => {
+                node.expect_children(&[
+                    "tex1",
+                    "tex2",
+                    "scale",
+                    "mapping",
+                    "space",
+                    "transform",
+                    "dimension",
+                ])?;
+                let tex1 = self.texture_or(node, "tex1", T::from_rgb(Spectrum::all(1.0)))?;
+                let tex2 = self.texture_or(node, "tex2", T::default())?;
+                match node.int_or("dimension", 2)? {
+                    2 => {
+                        if let Some(child) = node.child("transform").or(node.child("space")) {
+                            return Err(child.error(format!(
+                                "2D checkerboards are placed by a 'mapping', not a '{}'",
+                                child.name
+                            )));
+                        }
+                        Arc::new(Checkerboard2D::new(tex1, tex2, texture_mapping_2d(node)?))
+                    }
+                    3 => {
+                        if let Some(child) = node.child("mapping") {
+                            return Err(child.error(
+                                "3D checkerboards are placed by a 'transform', not a 'mapping'",
+                            ));
+                        }
+                        Arc::new(Checkerboard3D::new(tex1, tex2, texture_mapping_3d(node)?))
+                    }
+                    _ => return Err(node.error("checkerboard 'dimension' must be 2 or 3")),
+                }
+            }
+            "grid" => {
+                node.expect_children(&["line", "background", "line_width", "scale", "mapping"])?;
+                let line_width = node.float_or("line_width", 0.05)?;
+                if !(0.0..=1.0).contains(&line_width) {
+                    return Err(node.error("'line_width' must be between 0 and 1"));
+                }
+                Arc::new(GridTexture::new(
+                    self.texture_or(node, "line", T::default())?,
+                    self.texture_or(node, "background", T::from_rgb(Spectrum::all(1.0)))?,
+                    line_width,
+                    texture_mapping_2d(node)?,
+                ))
+            }
+            "dots" => {
+                node.expect_children(&["inside", "outside", "scale", "mapping"])?;
+                Arc::new(DotsTexture::new(
+                    self.texture_or(node, "inside", T::from_rgb(Spectrum::all(1.0)))?,
+                    self.texture_or(node, "outside", T::default())?,
+                    texture_mapping_2d(node)?,
+                ))
+            }
+            "scale" => {
+                node.expect_children(&["tex", "scale"])?;
+                Arc::new(ScaleTexture::new(
+                    self.texture_param(node.required("tex")?)?,
+                    self.float_texture(node.required("scale")?)?,
+                ))
+            }
+            "mix" => {
+                node.expect_children(&["tex1", "tex2", "amount"])?;
+                Arc::new(MixTexture::new(
+                    self.texture_param(node.required("tex1")?)?,
+                    self.texture_param(node.required("tex2")?)?,
+                    self.float_texture_or(node, "amount", 0.5)?,
+                ))
+            }
+            "bilerp" => {
+                node.expect_children(&["v00", "v01", "v10", "v11", "scale", "mapping"])?;
+                Arc::new(BilerpTexture::new(
+                    self.texture_param(node.required("v00")?)?,
+                    self.texture_param(node.required("v01")?)?,
+                    self.texture_param(node.required("v10")?)?,
+                    self.texture_param(node.required("v11")?)?,
+                    texture_mapping_2d(node)?,
+                ))
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(texture))
+    }
+
+    fn image_texture<T: Texel>(
+        &self,
+        node: &Node,
+        default_encoding: &str,
+    ) -> Result<ImageTexture<T>, Error> {
+        node.expect_children(&[
+            "file",
+            "encoding",
+            "wrap",
+            "filter",
+            "max_anisotropy",
+            "scale",
+            "mapping",
+        ])?;
+
+        let srgb = match node.child("encoding") {
+            Some(child) => child.string_arg(0, "an encoding")?,
+            None => default_encoding,
+        };
+        let srgb = match srgb {
+            "srgb" => true,
+            "linear" => false,
+            other => {
+                return Err(node.error(format!(
+                    "unknown encoding '{}' (expected srgb or linear)",
+                    other
+                )));
+            }
+        };
+
+        let wrap = match node.child("wrap") {
+            Some(child) => match child.string_arg(0, "a wrap mode")? {
+                "repeat" => WrapMode::Repeat,
+                "clamp" => WrapMode::Clamp,
+                "mirror" => WrapMode::Mirror,
+                other => {
+                    return Err(child.error(format!(
+                        "unknown wrap mode '{}' (expected repeat, clamp or mirror)",
+                        other
+                    )));
+                }
+            },
+            None => WrapMode::Repeat,
+        };
+
+        let max_anisotropy = node.float_or("max_anisotropy", 8.0)?;
+        if max_anisotropy < 1.0 {
+            return Err(node.error("'max_anisotropy' must be at least 1"));
+        }
+        let filter = match node.child("filter") {
+            Some(child) => match child.string_arg(0, "a texture filter")? {
+                "bilinear" => FilterMode::Bilinear,
+                "trilinear" => FilterMode::Trilinear,
+                "ewa" => FilterMode::Ewa { max_anisotropy },
+                other => {
+                    return Err(child.error(format!(
+                        "unknown texture filter '{}' (expected bilinear, trilinear or ewa)",
+                        other
+                    )));
+                }
+            },
+            None => FilterMode::Ewa { max_anisotropy },
+        };
+
+        let file = node.required("file")?;
+        let path = self.base_dir.join(file.string_arg(0, "an image file")?);
+        let mapping = texture_mapping_2d(node)?;
+        ImageTexture::open(&path, srgb, wrap, filter, mapping).map_err(|e| file.error(e))
+    }
+
+    // A texture parameter is either the name of a texture or an inline constant
+    fn texture_param<T: TextureValue>(&self, node: &Node) -> Result<Tex<T>, Error> {
+        if node.has_string_arg() {
+            let name = node.string_arg(0, "a texture name")?;
+            T::textures(self)
+                .get(name)
+                .cloned()
+                .ok_or_else(|| node.error(format!("unknown {} texture '{}'", T::TYPE, name)))
+        } else {
+            Ok(Arc::new(ConstantTexture::new(T::parse(node)?)))
+        }
+    }
+
+    fn texture_or<T: TextureValue>(
+        &self,
+        node: &Node,
+        name: &str,
+        default: T,
+    ) -> Result<Tex<T>, Error> {
+        match node.child(name) {
+            Some(child) => self.texture_param(child),
+            None => Ok(Arc::new(ConstantTexture::new(default))),
+        }
+    }
+
+    fn float_texture(&self, node: &Node) -> Result<FloatTexture, Error> {
+        self.texture_param(node)
+    }
+
+    fn spectrum_texture_or(
+        &self,
+        node: &Node,
+        name: &str,
+        default: Spectrum,
+    ) -> Result<SpectrumTexture, Error> {
+        self.texture_or(node, name, default)
+    }
+
+    fn float_texture_or(
+        &self,
+        node: &Node,
+        name: &str,
+        default: Float,
+    ) -> Result<FloatTexture, Error> {
+        self.texture_or(node, name, default)
+    }
+
+    // Bump maps are float textures of how far the surface is displaced along its normal
+    fn bump(&self, node: &Node) -> Result<Option<FloatTexture>, Error> {
+        match node.child("bump") {
+            Some(child) => Ok(Some(self.float_texture(child)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Normal maps name a spectrum texture, which should be an image with 'encoding "linear"'
+    fn normal_map(&self, node: &Node) -> Result<Option<NormalMap>, Error> {
+        let child = match node.child("normal_map") {
+            Some(child) => child,
+            None => return Ok(None),
+        };
+        child.expect_children(&["convention", "strength"])?;
+
+        let convention = match child.child("convention") {
+            Some(c) => match c.string_arg(0, "a normal map convention")? {
+                "opengl" => NormalMapConvention::OpenGL,
+                "directx" => NormalMapConvention::DirectX,
+                other => {
+                    return Err(c.error(format!(
+                        "unknown normal map convention '{}' (expected opengl or directx)",
+                        other
+                    )));
+                }
+            },
+            None => NormalMapConvention::OpenGL,
+        };
+        let strength = child.float_or("strength", 1.0)?;
+        if strength < 0.0 {
+            return Err(child.error("normal map 'strength' can't be negative"));
+        }
+
+        Ok(Some(NormalMap::new(
+            self.texture_param(child)?,
+            convention,
+            strength,
+        )))
+    }
+
+    fn material(&mut self, node: &Node) -> Result<(), Error> {
+        let name = node.string_arg(0, "a material name")?.to_string();
+        let class = node.string_arg(1, "a material class")?;
+
+        let material: Arc<dyn Material + Send + Sync> = match class {
+            "matte" => {
+                node.expect_children(&["diffuse", "roughness", "bump", "normal_map"])?;
+                let roughness = match node.child("roughness") {
+                    Some(child) => Some(self.float_texture(child)?),
+                    None => None,
+                };
+                Arc::new(Matte::new(
+                    self.spectrum_texture_or(node, "diffuse", Spectrum::all(0.5))?,
+                    roughness,
+                    self.bump(node)?,
+                    self.normal_map(node)?,
+                ))
+            }
+            "plastic" => {
+                node.expect_children(&["kd", "ks", "roughness", "bump", "normal_map"])?;
+                Arc::new(Plastic::new(
+                    self.spectrum_texture_or(node, "kd", Spectrum::all(0.25))?,
+                    self.spectrum_texture_or(node, "ks", Spectrum::all(0.25))?,
+                    self.float_texture_or(node, "roughness", 0.1)?,
+                    self.bump(node)?,
+                    self.normal_map(node)?,
+                ))
+            }
+            "mirror" => {
+                node.expect_children(&["kr", "bump", "normal_map"])?;
+                Arc::new(Mirror::new(
+                    self.spectrum_texture_or(node, "kr", Spectrum::all(0.9))?,
+                    self.bump(node)?,
+                    self.normal_map(node)?,
+                ))
+            }
+            "glass" => {
+                node.expect_children(&["kr", "kt", "eta", "bump", "normal_map"])?;
+                Arc::new(Glass::new(
+                    self.spectrum_texture_or(node, "kr", Spectrum::all(1.0))?,
+                    self.spectrum_texture_or(node, "kt", Spectrum::all(1.0))?,
+                    self.float_texture_or(node, "eta", 1.5)?,
+                    self.bump(node)?,
+                    self.normal_map(node)?,
+                ))
+            }
+            "rough_glass" => {
+                node.expect_children(&[
+                    "kr",
+                    "kt",
+                    "eta",
+                    "roughness",
+                    "roughness_u",
+                    "roughness_v",
+                    "distribution",
+                    "bump",
+                    "normal_map",
+                ])?;
+                let roughness = self.float_texture_or(node, "roughness", 0.1)?;
+                let directional = |name| match node.child(name) {
+                    Some(child) => self.float_texture(child),
+                    None => Ok(roughness.clone()),
+                };
+                let distribution = match node.child("distribution") {
+                    Some(child) => match child.string_arg(0, "a microfacet distribution")? {
+                        "ggx" => Distribution::TrowbridgeReitz,
+                        "beckmann" => Distribution::Beckmann,
+                        other => {
+                            return Err(child.error(format!(
+                                "unknown microfacet distribution '{}' (expected ggx or beckmann)",
+                                other
+                            )));
+                        }
+                    },
+                    None => Distribution::TrowbridgeReitz,
+                };
+                Arc::new(RoughGlass::new(
+                    self.spectrum_texture_or(node, "kr", Spectrum::all(1.0))?,
+                    self.spectrum_texture_or(node, "kt", Spectrum::all(1.0))?,
+                    self.float_texture_or(node, "eta", 1.5)?,
+                    directional("roughness_u")?,
+                    directional("roughness_v")?,
+                    distribution,
+                    self.bump(node)?,
+                    self.normal_map(node)?,
+                ))
+            }
+            _ => {
+                return Err(node.error(format!(
+                    "unknown material class '{}' (expected matte, plastic, mirror, glass or \
+                     rough_glass)",
+                    class
+                )));
+            }
+        };
+
+        self.materials.insert(name, material);
+        Ok(())
+    }
+
+    fn material_ref(&self, node: &Node) -> Result<Arc<dyn Material + Send + Sync>, Error> {
+        let name = node.string_arg(0, "a material name")?;
+        self.materials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| node.error(format!("unknown material '{}'", name)))
+    }
+
+    fn shape(&mut self, node: &Node) -> Result<(), Error> {
+        let geometry = geometry(node, &["material", "transform"], &self.base_dir)?;
+        let material = self.material_ref(node.required("material")?)?;
+        let transform = animated_transform(node.child("transform"))?;
+
+        for geometry in geometry {
+            self.primitives.push(Primitive::Receiver(Receiver::new(
+                geometry.into_geometry(),
+                material.clone(),
+                transform.clone(),
+            )));
+        }
+
+        Ok(())
+    }
+
+    // Model files are imported by extension; 'material' overrides the file's own materials
+    fn import(&mut self, node: &Node) -> Result<(), Error> {
+        let file = node.string_arg(0, "a file name")?;
+        let path = self.base_dir.join(file);
+        node.expect_children(&["material", "transform"])?;
+
+        let material = match node.child("material") {
+            Some(child) => Some(self.material_ref(child)?),
+            None => None,
+        };
+        let transform = transform(node.child("transform"))?;
+
+        self.import_model(&path, transform, material)
+            .map_err(|e| node.error(e))
+    }
+
+    fn import_model(
+        &mut self,
+        path: &Path,
+        transform: Transform,
+        material: Option<Arc<dyn Material + Send + Sync>>,
+    ) -> Result<(), String> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+
+        match extension.as_deref() {
+            Some("obj") => self
+                .primitives
+                .extend(obj::load(path, transform, material)?),
+            Some("gltf") | Some("glb") => {
+                let model = gltf::load(path, transform, material)?;
+                self.primitives.extend(model.primitives);
+                self.warnings.extend(
+                    model
+                        .warnings
+                        .into_iter()
+                        .map(|w| format!("{}: {}", path.display(), w)),
+                );
+                if self.imported_camera.is_none() {
+                    self.imported_camera = model.camera;
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "unsupported model format '{}' (expected .obj, .gltf or .glb)",
+                    path.display()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Shapes and models inside an object are built once and shared by all of its instances
+    fn object(&mut self, node: &Node) -> Result<(), Error> {
+        let name = node.string_arg(0, "an object name")?.to_string();
+
+        let outer = std::mem::take(&mut self.primitives);
+        let result = self.object_contents(node);
+        let contents = std::mem::replace(&mut self.primitives, outer);
+        result?;
+
+        let receivers = contents
+            .into_iter()
+            .map(|p| match p {
+                Primitive::Receiver(r) => Ok(r),
+                _ => Err(node.error(format!("object '{}' can't contain lights", name))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if receivers.is_empty() {
+            return Err(node.error(format!("object '{}' has no shapes", name)));
+        }
+
+        self.objects
+            .insert(name, Arc::new(Prototype::new(receivers)));
+        Ok(())
+    }
+
+    fn object_contents(&mut self, node: &Node) -> Result<(), Error> {
+        for child in &node.children {
+            match child.name.as_str() {
+                "shape" => self.shape(child)?,
+                "import" => self.import(child)?,
+                name => {
+                    return Err(child.error(format!(
+                        "unknown object contents '{}' (expected shape or import)",
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn instance(&mut self, node: &Node) -> Result<(), Error> {
+        let name = node.string_arg(0, "an object name")?;
+        node.expect_children(&["transform"])?;
+
+        let prototype = self
+            .objects
+            .get(name)
+            .cloned()
+            .ok_or_else(|| node.error(format!("unknown object '{}'", name)))?;
+        let transform = animated_transform(node.child("transform"))?;
+
+        self.primitives
+            .push(Primitive::Instance(Instance::new(prototype, transform)));
+        Ok(())
+    }
+
+    fn light(&mut self, node: &Node) -> Result<(), Error> {
+        let class = node.string_arg(0, "a light class")?;
+
+        let emitters = match class {
+            "point" => {
+                node.expect_children(&["intensity", "position"])?;
+                vec![Emitter::new_point(
+                    node.required("intensity")?.spectrum()?,
+                    node.required("position")?.point3()?,
+                )]
+            }
+            "spot" => {
+                node.expect_children(&[
+                    "intensity",
+                    "position",
+                    "target",
+                    "up",
+                    "falloff_start",
+                    "falloff_end",
+                ])?;
+                let falloff_start = node.float_or("falloff_start", 30.0)?;
+                let falloff_end = node.float_or("falloff_end", 45.0)?;
+                if falloff_start >= falloff_end {
+                    return Err(node.error("'falloff_start' must be less than 'falloff_end'"));
+                }
+                vec![Emitter::new_spot(
+                    node.required("intensity")?.spectrum()?,
+                    node.required("position")?.point3()?,
+                    node.required("target")?.point3()?,
+                    match node.child("up") {
+                        Some(up) => up.vec3()?,
+                        None => Vec3f::new(0.0, 1.0, 0.0),
+                    },
+                    falloff_start,
+                    falloff_end,
+                )]
+            }
+            "area" => {
+                node.expect_children(&["emission", "shape", "material", "transform"])?;
+                let material = match node.child("material") {
+                    Some(child) => self.material_ref(child)?,
+                    None => Arc::new(Matte::new(
+                        Arc::new(ConstantTexture::new(Spectrum::all(1.0))),
+                        None,
+                        None,
+                        None,
+                    )),
+                };
+                let emission = node.required("emission")?.spectrum()?;
+                let transform = animated_transform(node.child("transform"))?;
+
+                // Each triangle of a mesh becomes its own light
+                geometry(node.required("shape")?, &[], &self.base_dir)?
+                    .into_iter()
+                    .map(|geometry| {
+                        Emitter::new_area(emission, transform.clone(), geometry, material.clone())
+                    })
+                    .collect()
+            }
+            _ => {
+                return Err(node.error(format!(
+                    "unknown light class '{}' (expected point, spot or area)",
+                    class
+                )));
+            }
+        };
+
+        self.primitives
+            .extend(emitters.into_iter().map(Primitive::Emitter));
+        Ok(())
+    }
+}
+
+// Meshes expand to one geometry per triangle so that each gets its own BVH leaf
+fn geometry(
+    node: &Node,
+    extra: &[&str],
+    base_dir: &Path,
+) -> Result<Vec<Arc<dyn Sampleable + Send + Sync>>, Error> {
+    let class = node.string_arg(0, "a shape class")?;
+
+    let allowed = |params: &[&'static str]| {
+        let mut all = params.to_vec();
+        all.extend_from_slice(extra);
+        node.expect_children(&all)
+    };
+
+    match class {
+        "sphere" => {
+            allowed(&["radius"])?;
+            Ok(vec![Arc::new(Sphere::new(node.float_or("radius", 1.0)?))])
+        }
+        "disk" => {
+            allowed(&["radius", "inner_radius"])?;
+            let radius = node.float_or("radius", 1.0)?;
+            let inner_radius = node.float_or("inner_radius", 0.0)?;
+            if inner_radius >= radius {
+                return Err(node.error("'inner_radius' must be less than 'radius'"));
+            }
+            Ok(vec![Arc::new(Disk::new(radius, inner_radius))])
+        }
+        "mesh" => {
+            allowed(&["positions", "indices", "normals", "uvs", "tangents"])?;
+            let positions = node
+                .required("positions")?
+                .tuples(3)?
+                .into_iter()
+                .map(|p| LocalPoint3f::new(p[0], p[1], p[2]))
+                .collect();
+            let normals = match node.child("normals") {
+                Some(n) => Some(
+                    n.tuples(3)?
+                        .into_iter()
+                        .map(|n| LocalNormal3f::new(n[0], n[1], n[2]))
+                        .collect(),
+                ),
+                None => None,
+            };
+            let uvs = match node.child("uvs") {
+                Some(n) => Some(
+                    n.tuples(2)?
+                        .into_iter()
+                        .map(|uv| Point2f::new(uv[0], uv[1]))
+                        .collect(),
+                ),
+                None => None,
+            };
+            let tangents = match node.child("tangents") {
+                Some(n) => Some(
+                    n.tuples(3)?
+                        .into_iter()
+                        .map(|t| LocalVec3f::new(t[0], t[1], t[2]))
+                        .collect(),
+                ),
+                None => None,
+            };
+
+            let mesh = TriangleMesh::new(
+                positions,
+                node.required("indices")?.uints()?,
+                normals,
+                uvs,
+                tangents,
+            )
+            .map_err(|e| node.error(e))?;
+
+            Ok(triangles(mesh))
+        }
+        "ply" => {
+            allowed(&["file"])?;
+            let file = node.required("file")?.string_arg(0, "a file name")?;
+            let mesh = ply::load(&base_dir.join(file)).map_err(|e| node.error(e))?;
+            Ok(triangles(mesh))
+        }
+        _ => Err(node.error(format!(
+            "unknown shape class '{}' (expected sphere, disk, mesh or ply)",
+            class
+        ))),
+    }
+}
+
+fn triangles(mesh: TriangleMesh) -> Vec<Arc<dyn Sampleable + Send + Sync>> {
+    mesh.into_triangles()
+        .into_iter()
+        .map(|t| Arc::new(t) as Arc<dyn Sampleable + Send + Sync>)
+        .collect()
+}
+
+// Operations are composed in the order they are written, so the last one is applied first
+fn transform(node: Option<&Node>) -> Result<Transform, Error> {
+    let mut out = Transform::scale(1.0, 1.0, 1.0);
+
+    let node = match node {
+        Some(node) => node,
+        None => return Ok(out),
+    };
+
+    for op in &node.children {
+        if op.name == "key" {
+            return Err(op.error("this transform can't be animated"));
+        }
+
+        let t = match op.name.as_str() {
+            "translate" => Transform::translate(op.vec3()?),
+            "scale" => {
+                let f = op.floats()?;
+                match f.len() {
+                    1 => Transform::scale(f[0], f[0], f[0]),
+                    3 => Transform::scale(f[0], f[1], f[2]),
+                    n => {
+                        return Err(
+                            op.error(format!("'scale' expects 1 or 3 numbers, found {}", n))
+                        );
+                    }
+                }
+            }
+            "rotate" => {
+                let f = op.floats_n(4)?;
+                Transform::rotation(Vec3f::new(f[1], f[2], f[3]), f[0])
+            }
+            "look_at" => {
+                let f = op.floats_n(9)?;
+                Transform::look_at(
+                    Point3f::new(f[0], f[1], f[2]),
+                    Point3f::new(f[3], f[4], f[5]),
+                    Vec3f::new(f[6], f[7], f[8]),
+                )
+                .inverse()
+            }
+            name => {
+                return Err(op.error(format!(
+                    "unknown transform '{}' (expected translate, scale, rotate or look_at)",
+                    name
+                )));
+            }
+        };
+        out = out * t;
+    }
+
+    Ok(out)
+}
+
+// Moving transforms are a list of 'key <time> { ... }' blocks, each holding the operations
+// for that point in time
+fn animated_transform(node: Option<&Node>) -> Result<AnimatedTransform, Error> {
+    let node = match node {
+        Some(node) if node.child("key").is_some() => node,
+        _ => return Ok(AnimatedTransform::from(transform(node)?)),
+    };
+
+    let mut keyframes: Vec<(Float, Transform)> = Vec::new();
+    for key in &node.children {
+        if key.name != "key" {
+            return Err(key.error(format!(
+                "'{}' can't be mixed with keyframes (put it inside a 'key')",
+                key.name
+            )));
+        }
+
+        let time = key.float()?;
+        if let Some(&(prev, _)) = keyframes.last() {
+            if time <= prev {
+                return Err(key.error("keyframe times must be increasing"));
+            }
+        }
+        keyframes.push((time, transform(Some(key))?));
+    }
+
+    Ok(AnimatedTransform::new(&keyframes))
+}
+
+fn film(node: &Node, settings: &mut RenderSettings) -> Result<(), Error> {
+    node.expect_children(&["resolution", "filter"])?;
+
+    if let Some(res) = node.child("resolution") {
+        let f = res.floats_n(2)?;
+        if f[0] < 1.0 || f[1] < 1.0 || f[0].fract() != 0.0 || f[1].fract() != 0.0 {
+            return Err(res.error("'resolution' must be two positive integers"));
+        }
+        settings.width = f[0] as i32;
+        settings.height = f[1] as i32;
+    }
+
+    if let Some(f) = node.child("filter") {
+        settings.filter = match f.string_arg(0, "a filter class")? {
+            "mitchell" => {
+                f.expect_children(&["radius", "b", "c"])?;
+                FilterSettings::Mitchell {
+                    radius: f.float_or("radius", 2.0)?,
+                    b: f.float_or("b", 1.0 / 3.0)?,
+                    c: f.float_or("c", 1.0 / 3.0)?,
+                }
+            }
+            "triangle" => {
+                f.expect_children(&["radius"])?;
+                FilterSettings::Triangle {
+                    radius: f.float_or("radius", 2.0)?,
+                }
+            }
+            class => {
+                return Err(f.error(format!(
+                    "unknown filter '{}' (expected mitchell or triangle)",
+                    class
+                )));
+            }
+        };
+
+        if settings.filter.radius() <= 0.0 {
+            return Err(f.error("filter 'radius' must be positive"));
+        }
+    }
+
+    Ok(())
+}
+
+const LENS_PARAMS: &[&str] = &["lens_radius", "focal_distance", "blades", "blade_rotation"];
+
+fn camera(node: &Node, base_dir: &Path) -> Result<CameraSettings, Error> {
+    match node.string_arg(0, "a camera class")? {
+        "perspective" => {
+            node.expect_children(
+                &[
+                    &[
+                        "fov",
+                        "intrinsics",
+                        "calibration_resolution",
+                        "distortion",
+                        "overscan",
+                        "shutter",
+                        "transform",
+                    ],
+                    LENS_PARAMS,
+                ]
+                .concat(),
+            )?;
+
+            let overscan = node.float_or("overscan", 0.0)?;
+            if overscan < 0.0 {
+                return Err(node.error("'overscan' can't be negative"));
+            }
+
+            Ok(CameraSettings::Perspective {
+                motion: camera_motion(node)?,
+                fov: field_of_view(node)?,
+                distortion: distortion(node)?,
+                overscan,
+                lens: thin_lens(node)?,
+            })
+        }
+        "orthographic" => {
+            node.expect_children(
+                &[&["screen_window", "shutter", "transform"], LENS_PARAMS].concat(),
+            )?;
+
+            let screen_window = match node.child("screen_window") {
+                Some(child) => {
+                    let f = child.floats_n(4)?;
+                    if f[0] >= f[1] || f[2] >= f[3] {
+                        return Err(child.error(
+                            "'screen_window' must be 'min_x max_x min_y max_y' with each \
+                             minimum less than its maximum",
+                        ));
+                    }
+                    Some(Bounds2f::new(
+                        Point2f::new(f[0], f[2]),
+                        Point2f::new(f[1], f[3]),
+                    ))
+                }
+                None => None,
+            };
+
+            Ok(CameraSettings::Orthographic {
+                motion: camera_motion(node)?,
+                screen_window,
+                lens: thin_lens(node)?,
+            })
+        }
+        "equirectangular" => {
+            node.expect_children(&["shutter", "transform"])?;
+            Ok(CameraSettings::Equirectangular {
+                motion: camera_motion(node)?,
+            })
+        }
+        "fisheye" => {
+            node.expect_children(&["fov", "mapping", "shutter", "transform"])?;
+            let fov = node.float_or("fov", 180.0)?;
+            if fov <= 0.0 || fov > 360.0 {
+                return Err(node.error("fisheye 'fov' must be between 0 and 360 degrees"));
+            }
+
+            let mapping = match node.child("mapping") {
+                Some(child) => match child.string_arg(0, "a fisheye mapping")? {
+                    "equidistant" => FisheyeMapping::Equidistant,
+                    "equisolid" => FisheyeMapping::Equisolid,
+                    name => {
+                        return Err(child.error(format!(
+                            "unknown fisheye mapping '{}' (expected equidistant or equisolid)",
+                            name
+                        )));
+                    }
+                },
+                None => FisheyeMapping::Equidistant,
+            };
+
+            Ok(CameraSettings::Fisheye {
+                motion: camera_motion(node)?,
+                mapping,
+                fov,
+            })
+        }
+        "ods" => {
+            node.expect_children(&[
+                "ipd",
+                "convergence_distance",
+                "pole_merge",
+                "shutter",
+                "transform",
+            ])?;
+
+            let ipd = node.float_or("ipd", 0.065)?;
+            if ipd < 0.0 {
+                return Err(node.error("'ipd' can't be negative"));
+            }
+
+            let convergence_distance = match node.child("convergence_distance") {
+                Some(child) => {
+                    let distance = child.float()?;
+                    if distance <= 0.0 {
+                        return Err(child.error("'convergence_distance' must be positive"));
+                    }
+                    Some(distance)
+                }
+                None => None,
+            };
+
+            // Elevations in degrees where the eyes start and finish merging
+            let (pole_merge_from, pole_merge_to) = match node.child("pole_merge") {
+                Some(child) => {
+                    let f = child.floats_n(2)?;
+                    if f[0] < 0.0 || f[0] > f[1] || f[1] > 90.0 {
+                        return Err(child.error(
+                            "'pole_merge' must be two angles in degrees with \
+                             0 <= from <= to <= 90",
+                        ));
+                    }
+                    (f[0], f[1])
+                }
+                None => (60.0, 75.0),
+            };
+
+            Ok(CameraSettings::Ods {
+                motion: camera_motion(node)?,
+                ipd,
+                convergence_distance,
+                pole_merge_from,
+                pole_merge_to,
+            })
+        }
+        "realistic" => {
+            node.expect_children(&[
+                "lens",
+                "aperture_diameter",
+                "focus_distance",
+                "film_diagonal",
+                "shutter",
+                "transform",
+            ])?;
+
+            let file = node.required("lens")?;
+            let elements = lens::load(&base_dir.join(file.string_arg(0, "a lens file")?))
+                .map_err(|e| file.error(e))?;
+
+            // Lens sizes are in millimeters like the lens file, distances in the scene in meters
+            let aperture_radius = match node.child("aperture_diameter") {
+                Some(child) => {
+                    let diameter = child.float()?;
+                    if diameter <= 0.0 {
+                        return Err(child.error("'aperture_diameter' must be positive"));
+                    }
+                    Some(diameter / 2000.0)
+                }
+                None => None,
+            };
+
+            let focus_distance = node.float_or("focus_distance", 10.0)?;
+            if focus_distance <= 0.0 {
+                return Err(node.error("'focus_distance' must be positive"));
+            }
+
+            let film_diagonal = node.float_or("film_diagonal", 35.0)?;
+            if film_diagonal <= 0.0 {
+                return Err(node.error("'film_diagonal' must be positive"));
+            }
+
+            let lens = LensSystem::new(
+                elements,
+                aperture_radius,
+                focus_distance,
+                film_diagonal / 1000.0,
+            )
+            .map_err(|e| node.error(e))?;
+
+            Ok(CameraSettings::Realistic {
+                motion: camera_motion(node)?,
+                lens: Arc::new(lens),
+            })
+        }
+        class => Err(node.error(format!(
+            "unknown camera class '{}' (expected perspective, orthographic, equirectangular, \
+             fisheye, ods or realistic)",
+            class
+        ))),
+    }
+}
+
+// Either an angle, or a camera matrix from a calibration: [fx 0 cx  0 fy cy  0 0 1]
+fn field_of_view(node: &Node) -> Result<FieldOfView, Error> {
+    let k = match node.child("intrinsics") {
+        Some(child) => child,
+        None => {
+            if let Some(child) = node.child("calibration_resolution") {
+                return Err(child.error("'calibration_resolution' needs 'intrinsics'"));
+            }
+
+            let fov = node.float_or("fov", 90.0)?;
+            if fov <= 0.0 || fov >= 180.0 {
+                return Err(node.error("'fov' must be between 0 and 180 degrees"));
+            }
+            return Ok(FieldOfView::Degrees(fov));
+        }
+    };
+
+    if let Some(fov) = node.child("fov") {
+        return Err(fov.error("'fov' and 'intrinsics' can't be used together"));
+    }
+
+    let m = k.floats_n(9)?;
+    if m[1] != 0.0 {
+        return Err(k.error("skewed camera matrices aren't supported"));
+    }
+    if m[3] != 0.0 || m[6] != 0.0 || m[7] != 0.0 || m[8] != 1.0 {
+        return Err(k.error("'intrinsics' must be a matrix like [fx 0 cx  0 fy cy  0 0 1]"));
+    }
+    if m[0] <= 0.0 || m[4] <= 0.0 {
+        return Err(k.error("focal lengths in 'intrinsics' must be positive"));
+    }
+
+    let resolution = match node.child("calibration_resolution") {
+        Some(child) => {
+            let f = child.floats_n(2)?;
+            if f[0] <= 0.0 || f[1] <= 0.0 {
+                return Err(child.error("'calibration_resolution' must be positive"));
+            }
+            Some(Point2f::new(f[0], f[1]))
+        }
+        None => None,
+    };
+
+    Ok(FieldOfView::Intrinsics(Intrinsics {
+        fx: m[0],
+        fy: m[4],
+        cx: m[2],
+        cy: m[5],
+        resolution,
+    }))
+}
+
+// Coefficients in OpenCV's order: k1 k2 p1 p2, and optionally k3
+fn distortion(node: &Node) -> Result<Option<BrownConrady>, Error> {
+    let child = match node.child("distortion") {
+        Some(child) => child,
+        None => return Ok(None),
+    };
+
+    let f = child.floats()?;
+    if f.len() != 4 && f.len() != 5 {
+        return Err(child.error(format!(
+            "'distortion' expects 4 or 5 numbers (k1 k2 p1 p2 [k3]), found {}",
+            f.len()
+        )));
+    }
+
+    Ok(Some(BrownConrady {
+        k1: f[0],
+        k2: f[1],
+        p1: f[2],
+        p2: f[3],
+        k3: f.get(4).copied().unwrap_or(0.0),
+    }))
+}
+
+// Rays are spread over the shutter interval, in the same units as keyframe times
+fn camera_motion(node: &Node) -> Result<CameraMotion, Error> {
+    let (shutter_open, shutter_close) = match node.child("shutter") {
+        Some(shutter) => {
+            let f = shutter.floats_n(2)?;
+            if f[0] > f[1] {
+                return Err(shutter.error("the shutter must open before it closes"));
+            }
+            (f[0], f[1])
+        }
+        None => (0.0, 1.0),
+    };
+
+    Ok(CameraMotion {
+        camera_to_world: animated_transform(node.child("transform"))?,
+        shutter_open,
+        shutter_close,
+    })
+}
+
+// Depth of field is off unless the camera has a lens radius
+fn thin_lens(node: &Node) -> Result<Option<ThinLens>, Error> {
+    let radius = match node.child("lens_radius") {
+        Some(child) => child.float()?,
+        None => {
+            for name in &LENS_PARAMS[1..] {
+                if let Some(child) = node.child(name) {
+                    return Err(child.error(format!("'{}' needs a 'lens_radius'", name)));
+                }
+            }
+            return Ok(None);
+        }
+    };
+    if radius < 0.0 {
+        return Err(node.error("'lens_radius' can't be negative"));
+    }
+
+    let focal_distance = node.required("focal_distance")?.float()?;
+    if focal_distance <= 0.0 {
+        return Err(node.error("'focal_distance' must be positive"));
+    }
+
+    let blades = node.int_or("blades", 0)?;
+    if blades != 0 && blades < 3 {
+        return Err(node.error("'blades' must be 0 for a round aperture, or at least 3"));
+    }
+
+    Ok(Some(ThinLens {
+        radius,
+        focal_distance,
+        blades: blades as u32,
+        blade_rotation_deg: node.float_or("blade_rotation", 0.0)?,
+    }))
+}
+
+fn sampler(node: &Node) -> Result<u32, Error> {
+    match node.string_arg(0, "a sampler class")? {
+        "random" => {
+            node.expect_children(&["spp"])?;
+            let spp = node.int_or("spp", 16)?;
+            if spp < 1 {
+                return Err(node.error("'spp' must be at least 1"));
+            }
+            Ok(spp as u32)
+        }
+        class => Err(node.error(format!(
+            "unknown sampler class '{}' (expected random)",
+            class
+        ))),
+    }
+}
+
+fn integrator(node: &Node) -> Result<IntegratorSettings, Error> {
+    let settings = match node.string_arg(0, "an integrator class")? {
+        "path" => {
+            node.expect_children(&["min_depth", "max_depth"])?;
+            IntegratorSettings::Path {
+                min_depth: node.int_or("min_depth", 4)?,
+                max_depth: node.int_or("max_depth", 8)?,
+            }
+        }
+        "whitted" => {
+            node.expect_children(&["max_depth"])?;
+            IntegratorSettings::Whitted {
+                max_depth: node.int_or("max_depth", 10)?,
+            }
+        }
+        "normals" => {
+            node.expect_children(&[])?;
+            IntegratorSettings::Normals
+        }
+        class => {
+            return Err(node.error(format!(
+                "unknown integrator class '{}' (expected path, whitted or normals)",
+                class
+            )));
+        }
+    };
+
+    settings.validate().map_err(|e| node.error(e))?;
+    Ok(settings)
+}
@@ -0,0 +1,470 @@
+use crate::bxdf::microfacet;
+use crate::film::spectrum::Spectrum;
+use crate::geometry::mesh::TriangleMesh;
+use crate::geometry::{primitive::Primitive, receiver::Receiver, IntoGeometry};
+use crate::light::emitter::Emitter;
+use crate::material::{
+    matte::Matte, mirror::Mirror, plastic::Plastic, Material, NormalMap, NormalMapConvention,
+};
+use crate::math::*;
+use crate::texture::{constant::ConstantTexture, image::ImageTexture, Texture};
+use ::gltf::khr_lights_punctual::Kind;
+use ::gltf::mesh::Mode;
+use ::gltf::{camera::Projection, image::Format};
+use na::{Matrix4, Projective3};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+// glTF 2.0 importer for .gltf and .glb files. Only the default scene is loaded.
+
+pub struct Camera {
+    pub camera_to_world: Transform,
+    pub yfov_deg: Float,
+    pub aspect: Option<Float>,
+}
+
+pub struct Model {
+    pub primitives: Vec<Primitive>,
+    // The first camera found in the node hierarchy
+    pub camera: Option<Camera>,
+    // Parts of the file that were skipped
+    pub warnings: Vec<String>,
+}
+
+struct Importer {
+    buffers: Vec<::gltf::buffer::Data>,
+    images: Vec<::gltf::image::Data>,
+    // Keyed by image and whether it's sRGB encoded
+    textures: HashMap<(usize, bool), Arc<ImageTexture<Spectrum>>>,
+    materials: HashMap<usize, Arc<dyn Material + Send + Sync>>,
+    material_override: Option<Arc<dyn Material + Send + Sync>>,
+    model: Model,
+}
+
+fn to_transform(m: [[f32; 4]; 4]) -> Transform {
+    // glTF matrices are column-major
+    Transform::new(Projective3::from_matrix_unchecked(Matrix4::from_fn(
+        |row, col| m[col][row] as Float,
+    )))
+}
+
+fn spectrum(c: [f32; 3]) -> Spectrum {
+    Spectrum::from_rgb(c[0] as Float, c[1] as Float, c[2] as Float)
+}
+
+impl Importer {
+    fn image_texture(
+        &mut self,
+        index: usize,
+        srgb: bool,
+    ) -> Result<Arc<ImageTexture<Spectrum>>, String> {
+        if let Some(texture) = self.textures.get(&(index, srgb)) {
+            return Ok(texture.clone());
+        }
+
+        let image = &self.images[index];
+        let channels = match image.format {
+            Format::R8 => 1,
+            Format::R8G8 => 2,
+            Format::R8G8B8 => 3,
+            Format::R8G8B8A8 => 4,
+            format => {
+                return Err(format!("unsupported texture format {:?}", format));
+            }
+        };
+
+        // Grey and grey-alpha images are expanded to RGB, alpha is dropped
+        let rgb = image
+            .pixels
+            .chunks(channels)
+            .flat_map(|p| match channels {
+                1 | 2 => vec![p[0], p[0], p[0]],
+                _ => p[..3].to_vec(),
+            })
+            .collect::<Vec<_>>();
+
+        let texture = Arc::new(ImageTexture::from_rgb8(
+            image.width as usize,
+            image.height as usize,
+            &rgb,
+            srgb,
+        ));
+        self.textures.insert((index, srgb), texture.clone());
+        Ok(texture)
+    }
+
+    // Metallic-roughness materials are approximated with the materials we have: smooth metals
+    // become mirrors, everything else is plastic. Only the base color and normals may be
+    // textured, and primitives without a material are white matte.
+    fn material(
+        &mut self,
+        material: &::gltf::Material,
+    ) -> Result<Arc<dyn Material + Send + Sync>, String> {
+        let index = match (&self.material_override, material.index()) {
+            (Some(m), _) => return Ok(m.clone()),
+            (None, Some(index)) => index,
+            (None, None) => {
+                return Ok(Arc::new(Matte::new(
+                    Arc::new(ConstantTexture::new(Spectrum::all(1.0))),
+                    None,
+                    None,
+                    None,
+                )));
+            }
+        };
+
+        if let Some(m) = self.materials.get(&index) {
+            return Ok(m.clone());
+        }
+
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor();
+        let base_color = Spectrum::from_rgb(r as Float, g as Float, b as Float);
+
+        let base: Arc<dyn Texture<Spectrum> + Send + Sync> = match pbr.base_color_texture() {
+            Some(info) => self.image_texture(info.texture().source().index(), true)?,
+            None => Arc::new(ConstantTexture::new(base_color)),
+        };
+
+        // glTF normal maps are linear, OpenGL style, with a scale for the tangent components
+        let normal_map = match material.normal_texture() {
+            Some(info) => Some(NormalMap::new(
+                self.image_texture(info.texture().source().index(), false)?,
+                NormalMapConvention::OpenGL,
+                info.scale() as Float,
+            )),
+            None => None,
+        };
+
+        let roughness = pbr.roughness_factor() as Float;
+        let metallic = pbr.metallic_factor() as Float;
+
+        let out: Arc<dyn Material + Send + Sync> = if metallic >= 0.5 && roughness < 0.1 {
+            Arc::new(Mirror::new(base, None, normal_map))
+        } else {
+            // glTF's alpha is the square of its perceptual roughness
+            let roughness = Arc::new(ConstantTexture::new(microfacet::alpha_to_roughness(
+                roughness * roughness,
+            )));
+            let ks = if metallic >= 0.5 {
+                base.clone()
+            } else {
+                Arc::new(ConstantTexture::new(Spectrum::all(1.0)))
+            };
+            Arc::new(Plastic::new(base, ks, roughness, None, normal_map))
+        };
+
+        self.materials.insert(index, out.clone());
+        Ok(out)
+    }
+
+    fn mesh(&mut self, mesh: &::gltf::Mesh, transform: Transform) -> Result<(), String> {
+        let transform = AnimatedTransform::from(transform);
+        for primitive in mesh.primitives() {
+            // Points and lines have no surface
+            if primitive.mode() != Mode::Triangles {
+                continue;
+            }
+
+            let buffers = &self.buffers;
+            let reader = primitive.reader(|b| Some(&buffers[b.index()]));
+
+            let positions = match reader.read_positions() {
+                Some(p) => p
+                    .map(|p| LocalPoint3f::new(p[0] as Float, p[1] as Float, p[2] as Float))
+                    .collect::<Vec<_>>(),
+                None => continue,
+            };
+            let indices = match reader.read_indices() {
+                Some(i) => i.into_u32().collect(),
+                None => (0..positions.len() as u32).collect(),
+            };
+            let normals = reader.read_normals().map(|n| {
+                n.map(|n| LocalNormal3f::new(n[0] as Float, n[1] as Float, n[2] as Float))
+                    .collect()
+            });
+            // glTF puts the texture origin at the top left
+            let uvs = reader.read_tex_coords(0).map(|uv| {
+                uv.into_f32()
+                    .map(|uv| Point2f::new(uv[0] as Float, 1.0 - uv[1] as Float))
+                    .collect()
+            });
+            let tangents = reader.read_tangents().map(|t| {
+                t.map(|t| LocalVec3f::new(t[0] as Float, t[1] as Float, t[2] as Float))
+                    .collect()
+            });
+
+            let mesh = TriangleMesh::new(positions, indices, normals, uvs, tangents)
+                .map_err(|e| format!("mesh {}: {}", mesh.index(), e))?;
+
+            let gltf_material = primitive.material();
+            let material = self.material(&gltf_material)?;
+
+            let emission = spectrum(gltf_material.emissive_factor())
+                * gltf_material.emissive_strength().unwrap_or(1.0) as Float;
+
+            for triangle in mesh.into_triangles() {
+                let triangle = Arc::new(triangle);
+                self.model.primitives.push(if emission.is_black() {
+                    Primitive::Receiver(Receiver::new(
+                        triangle.into_geometry(),
+                        material.clone(),
+                        transform.clone(),
+                    ))
+                } else {
+                    Primitive::Emitter(Emitter::new_area(
+                        emission,
+                        transform.clone(),
+                        triangle,
+                        material.clone(),
+                    ))
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Punctual lights are in candela, which we use directly as intensity
+    fn light(
+        &mut self,
+        light: &::gltf::khr_lights_punctual::Light,
+        transform: Transform,
+    ) -> Result<(), String> {
+        let intensity = spectrum(light.color()) * light.intensity() as Float;
+        let position = transform.apply_point(Point3f::new(0.0, 0.0, 0.0));
+
+        let emitter = match light.kind() {
+            Kind::Point => Emitter::new_point(intensity, position),
+            Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => {
+                if outer_cone_angle <= 0.0 {
+                    return Err(format!(
+                        "spot light {} has an outer cone angle of {}, it must be greater than 0",
+                        light.index(),
+                        outer_cone_angle
+                    ));
+                }
+
+                let outer = (outer_cone_angle as Float).to_degrees();
+                let inner = (inner_cone_angle as Float).to_degrees().min(outer * 0.999);
+                Emitter::new_spot(
+                    intensity,
+                    position,
+                    transform.apply_point(Point3f::new(0.0, 0.0, -1.0)),
+                    transform.apply(Vec3f::new(0.0, 1.0, 0.0)),
+                    inner,
+                    outer,
+                )
+            }
+            // There's no light at infinity to map these onto
+            Kind::Directional => {
+                self.model.warnings.push(format!(
+                    "skipping directional light {}, directional lights aren't supported",
+                    light.index()
+                ));
+                return Ok(());
+            }
+        };
+
+        self.model.primitives.push(Primitive::Emitter(emitter));
+        Ok(())
+    }
+
+    fn node(&mut self, node: &::gltf::Node, parent: Transform) -> Result<(), String> {
+        let transform = parent * to_transform(node.transform().matrix());
+
+        if let Some(mesh) = node.mesh() {
+            self.mesh(&mesh, transform)?;
+        }
+
+        if let Some(light) = node.light() {
+            self.light(&light, transform)?;
+        }
+
+        if let Some(camera) = node.camera() {
+            if let (Projection::Perspective(p), None) = (camera.projection(), &self.model.camera) {
+                // glTF cameras look down -z, ours look down +z
+                self.model.camera = Some(Camera {
+                    camera_to_world: transform * Transform::scale(1.0, 1.0, -1.0),
+                    yfov_deg: (p.yfov() as Float).to_degrees(),
+                    aspect: p.aspect_ratio().map(|a| a as Float),
+                });
+            }
+        }
+
+        for child in node.children() {
+            self.node(&child, transform)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn load(
+    path: &Path,
+    transform: Transform,
+    material_override: Option<Arc<dyn Material + Send + Sync>>,
+) -> Result<Model, String> {
+    let (document, buffers, images) =
+        ::gltf::import(path).map_err(|e| format!("couldn't load '{}': {}", path.display(), e))?;
+
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or_else(|| format!("'{}' has no scenes", path.display()))?;
+
+    let mut importer = Importer {
+        buffers,
+        images,
+        textures: HashMap::new(),
+        materials: HashMap::new(),
+        material_override,
+        model: Model {
+            primitives: Vec::new(),
+            camera: None,
+            warnings: Vec::new(),
+        },
+    };
+
+    for node in scene.nodes() {
+        importer
+            .node(&node, transform)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    Ok(importer.model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::TempDir;
+
+    // A single triangle in front of a camera, with the given punctual lights attached to nodes
+    fn fixture(dir: &TempDir, lights: &str) -> std::path::PathBuf {
+        let positions: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let bytes = positions
+            .iter()
+            .flat_map(|f| f.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        dir.write("tri.bin", bytes);
+
+        let light_count = lights.matches("\"type\"").count();
+        let light_nodes = (0..light_count)
+            .map(|i| {
+                let light = format!(r#"{{ "KHR_lights_punctual": {{ "light": {} }} }}"#, i);
+                format!(
+                    r#", {{ "translation": [0, 2, 0], "extensions": {} }}"#,
+                    light
+                )
+            })
+            .collect::<String>();
+        let node_indices = (0..2 + light_count)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>();
+
+        let json = format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "extensionsUsed": ["KHR_lights_punctual"],
+                "extensions": {{ "KHR_lights_punctual": {{ "lights": [{lights}] }} }},
+                "scene": 0,
+                "scenes": [{{ "nodes": [{nodes}] }}],
+                "nodes": [
+                    {{ "mesh": 0 }},
+                    {{ "camera": 0, "translation": [0, 0, 5] }}
+                    {light_nodes}
+                ],
+                "cameras": [{{
+                    "type": "perspective",
+                    "perspective": {{ "yfov": 1.0, "znear": 0.1 }}
+                }}],
+                "meshes": [{{
+                    "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "material": 0 }}]
+                }}],
+                "materials": [{{
+                    "pbrMetallicRoughness": {{ "metallicFactor": 1.0, "roughnessFactor": 0.0 }}
+                }}],
+                "buffers": [{{ "uri": "tri.bin", "byteLength": 36 }}],
+                "bufferViews": [{{ "buffer": 0, "byteLength": 36 }}],
+                "accessors": [{{
+                    "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                    "min": [0, 0, 0], "max": [1, 1, 0]
+                }}]
+            }}"#,
+            lights = lights,
+            nodes = node_indices.join(", "),
+            light_nodes = light_nodes,
+        );
+        dir.write("scene.gltf", json)
+    }
+
+    fn load_fixture(name: &str, lights: &str) -> Result<Model, String> {
+        let dir = TempDir::new(&format!("gltf-{}", name));
+        load(
+            &fixture(&dir, lights),
+            Transform::scale(1.0, 1.0, 1.0),
+            None,
+        )
+    }
+
+    fn emitters(model: &Model) -> usize {
+        model
+            .primitives
+            .iter()
+            .filter(|p| matches!(p, Primitive::Emitter(_)))
+            .count()
+    }
+
+    #[test]
+    fn mesh_camera_and_point_light() {
+        let model = load_fixture("point", r#"{ "type": "point", "intensity": 10 }"#).unwrap();
+        assert_eq!(model.primitives.len(), 2);
+        assert_eq!(emitters(&model), 1);
+
+        let camera = model.camera.unwrap();
+        assert!((camera.yfov_deg - (1.0 as Float).to_degrees()).abs() < 1e-4);
+        assert_eq!(camera.aspect, None);
+        let eye = camera
+            .camera_to_world
+            .apply_point(Point3f::new(0.0, 0.0, 0.0));
+        assert!((eye - Point3f::new(0.0, 0.0, 5.0)).length() < 1e-5);
+        // glTF cameras look down -z, ours look down +z
+        let forward = camera.camera_to_world.apply(Vec3f::new(0.0, 0.0, 1.0));
+        assert!((forward - Vec3f::new(0.0, 0.0, -1.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn spot_light() {
+        let spot =
+            r#"{ "type": "spot", "spot": { "innerConeAngle": 0.2, "outerConeAngle": 0.4 } }"#;
+        let model = load_fixture("spot", spot).unwrap();
+        assert_eq!(emitters(&model), 1);
+    }
+
+    #[test]
+    fn zero_outer_cone_angle_is_an_error() {
+        let spot = r#"{ "type": "spot", "spot": { "innerConeAngle": 0, "outerConeAngle": 0 } }"#;
+        let err = load_fixture("zero_cone", spot).err().unwrap();
+        assert!(
+            err.contains("spot light 0 has an outer cone angle of 0"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn directional_lights_are_skipped() {
+        let lights = r#"{ "type": "directional" }, { "type": "point" }"#;
+        let model = load_fixture("directional", lights).unwrap();
+        assert_eq!(emitters(&model), 1);
+        assert_eq!(
+            model.warnings,
+            vec!["skipping directional light 0, directional lights aren't supported"]
+        );
+    }
+}
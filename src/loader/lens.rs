@@ -0,0 +1,112 @@
+use crate::film::camera::LensElement;
+use crate::math::*;
+use std::fs;
+use std::path::Path;
+
+// Tabulated lens prescriptions, one surface per line from the front of the lens to the film:
+//
+//     curvature_radius thickness ior aperture_diameter
+//
+// in millimeters, with '#' starting a comment. A radius of zero is the aperture stop.
+pub fn load(path: &Path) -> Result<Vec<LensElement>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("couldn't read '{}': {}", path.display(), e))?;
+
+    let mut elements = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = |e: &str| format!("{}:{}: {}", path.display(), i + 1, e);
+        let f = line
+            .split_whitespace()
+            .map(|s| s.parse::<Float>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| error("expected numbers"))?;
+        if f.len() != 4 {
+            return Err(error(
+                "expected 'curvature_radius thickness ior aperture_diameter'",
+            ));
+        }
+        if f[1] < 0.0 || f[2] < 0.0 || f[3] <= 0.0 {
+            return Err(error(
+                "thickness and ior can't be negative and the aperture must be positive",
+            ));
+        }
+
+        elements.push(LensElement {
+            curvature_radius: f[0] / 1000.0,
+            thickness: f[1] / 1000.0,
+            eta: f[2],
+            aperture_radius: f[3] / 2000.0,
+        });
+    }
+
+    if elements.is_empty() {
+        return Err(format!("{}: the lens has no elements", path.display()));
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::TempDir;
+
+    fn error(name: &str, contents: &str) -> String {
+        let dir = TempDir::new(&format!("lens-{}", name));
+        let path = dir.write("lens.dat", contents);
+        let err = load(&path).unwrap_err();
+        assert!(err.starts_with(&path.display().to_string()), "{}", err);
+        err[path.display().to_string().len()..].to_string()
+    }
+
+    #[test]
+    fn elements_in_meters() {
+        let dir = TempDir::new("lens-ok");
+        let path = dir.write(
+            "lens.dat",
+            "# a single lens and a stop\n\n  29.475 3.76 1.67 25.2  # front\n0 1 0 20\n",
+        );
+        let elements = load(&path).unwrap();
+        assert_eq!(elements.len(), 2);
+        assert!((elements[0].curvature_radius - 0.029475).abs() < 1e-7);
+        assert!((elements[0].thickness - 0.00376).abs() < 1e-7);
+        assert_eq!(elements[0].eta, 1.67);
+        assert!((elements[0].aperture_radius - 0.0126).abs() < 1e-7);
+        assert_eq!(elements[1].curvature_radius, 0.0);
+        assert_eq!(elements[1].eta, 0.0);
+    }
+
+    #[test]
+    fn bundled_lens_loads() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/lenses/dgauss50mm.dat");
+        assert!(load(&path).unwrap().len() > 1);
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        assert_eq!(error("nan", "1 2 1.5 4\n1 x 1 1\n"), ":2: expected numbers");
+        assert_eq!(
+            error("count", "# comment\n1 2 1.5\n"),
+            ":2: expected 'curvature_radius thickness ior aperture_diameter'"
+        );
+        assert_eq!(
+            error("aperture", "\n\n1 2 1.5 0\n"),
+            ":3: thickness and ior can't be negative and the aperture must be positive"
+        );
+        assert_eq!(error("empty", "# nothing\n"), ": the lens has no elements");
+    }
+
+    #[test]
+    fn missing_file() {
+        let err = load(Path::new("does/not/exist.dat")).unwrap_err();
+        assert!(
+            err.starts_with("couldn't read 'does/not/exist.dat'"),
+            "{}",
+            err
+        );
+    }
+}
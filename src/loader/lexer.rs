@@ -0,0 +1,236 @@
+use super::{Error, Pos};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub pos: Pos,
+}
+
+pub struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    pos: Pos,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            chars: src.chars().peekable(),
+            pos: Pos { line: 1, col: 1 },
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.pos.line += 1;
+            self.pos.col = 1;
+        } else {
+            self.pos.col += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '#' {
+                while let Some(c) = self.bump() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else if c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn string(&mut self, start: Pos) -> Result<TokenKind, Error> {
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(TokenKind::Str(out)),
+                Some('\\') => match self.bump() {
+                    Some(c @ '"') | Some(c @ '\\') => out.push(c),
+                    Some('n') => out.push('\n'),
+                    _ => return Err(Error::new(self.pos, "invalid escape sequence")),
+                },
+                Some('\n') | None => return Err(Error::new(start, "unterminated string")),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self, start: Pos) -> Result<TokenKind, Error> {
+        let mut text = String::new();
+        while let Some(&c) = self.chars.peek() {
+            let sign =
+                (c == '-' || c == '+') && (text.is_empty() || text.ends_with(&['e', 'E'][..]));
+            if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || sign {
+                text.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+
+        text.parse::<f64>()
+            .map(TokenKind::Num)
+            .map_err(|_| Error::new(start, format!("invalid number '{}'", text)))
+    }
+
+    fn ident(&mut self) -> TokenKind {
+        let mut out = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                out.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        TokenKind::Ident(out)
+    }
+
+    pub fn next_token(&mut self) -> Result<Option<Token>, Error> {
+        self.skip_whitespace_and_comments();
+
+        let pos = self.pos;
+        let c = match self.chars.peek() {
+            Some(&c) => c,
+            None => return Ok(None),
+        };
+
+        let kind = match c {
+            '{' => {
+                self.bump();
+                TokenKind::LBrace
+            }
+            '}' => {
+                self.bump();
+                TokenKind::RBrace
+            }
+            '[' => {
+                self.bump();
+                TokenKind::LBracket
+            }
+            ']' => {
+                self.bump();
+                TokenKind::RBracket
+            }
+            '"' => {
+                self.bump();
+                self.string(pos)?
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => self.number(pos)?,
+            c if c.is_alphabetic() || c == '_' => self.ident(),
+            c => return Err(Error::new(pos, format!("unexpected character '{}'", c))),
+        };
+
+        Ok(Some(Token { kind, pos }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(src: &str) -> Result<Vec<Token>, Error> {
+        let mut lexer = Lexer::new(src);
+        let mut out = Vec::new();
+        while let Some(token) = lexer.next_token()? {
+            out.push(token);
+        }
+        Ok(out)
+    }
+
+    fn kinds(src: &str) -> Vec<TokenKind> {
+        tokens(src).unwrap().into_iter().map(|t| t.kind).collect()
+    }
+
+    fn error(src: &str) -> String {
+        tokens(src).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn token_kinds() {
+        assert_eq!(
+            kinds(r#"shape "disk" { radius [ 1 -2.5 3e2 +.5 ] }"#),
+            vec![
+                TokenKind::Ident("shape".into()),
+                TokenKind::Str("disk".into()),
+                TokenKind::LBrace,
+                TokenKind::Ident("radius".into()),
+                TokenKind::LBracket,
+                TokenKind::Num(1.0),
+                TokenKind::Num(-2.5),
+                TokenKind::Num(300.0),
+                TokenKind::Num(0.5),
+                TokenKind::RBracket,
+                TokenKind::RBrace,
+            ]
+        );
+    }
+
+    #[test]
+    fn exponent_signs() {
+        assert_eq!(
+            kinds("1e-3 2E+1"),
+            vec![TokenKind::Num(1e-3), TokenKind::Num(20.0)]
+        );
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(
+            kinds(r#""a\"b\\c\nd""#),
+            vec![TokenKind::Str("a\"b\\c\nd".into())]
+        );
+    }
+
+    #[test]
+    fn comments_and_positions() {
+        let tokens = tokens("# header\n  film # trailing\n\t{ }").unwrap();
+        let positions: Vec<_> = tokens.iter().map(|t| (t.pos.line, t.pos.col)).collect();
+        assert_eq!(positions, vec![(2, 3), (3, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn empty_input() {
+        assert!(kinds("  # nothing here\n").is_empty());
+    }
+
+    #[test]
+    fn unterminated_string() {
+        assert_eq!(error("film\n  \"abc\nx"), "2:3: unterminated string");
+        assert_eq!(error("\"abc"), "1:1: unterminated string");
+    }
+
+    #[test]
+    fn invalid_escape() {
+        assert_eq!(error(r#""a\qb""#), "1:5: invalid escape sequence");
+    }
+
+    #[test]
+    fn invalid_number() {
+        assert_eq!(error("x 1.2.3"), "1:3: invalid number '1.2.3'");
+        assert_eq!(error("-"), "1:1: invalid number '-'");
+    }
+
+    #[test]
+    fn unexpected_character() {
+        assert_eq!(error("film {\n  @\n}"), "2:3: unexpected character '@'");
+    }
+}
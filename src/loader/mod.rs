@@ -0,0 +1,183 @@
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use std::path::Path;
+
+mod builder;
+mod gltf;
+mod lens;
+mod lexer;
+mod obj;
+mod parser;
+mod ply;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pos {
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub pos: Option<Pos>,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new<S: Into<String>>(pos: Pos, message: S) -> Self {
+        Self {
+            pos: Some(pos),
+            message: message.into(),
+        }
+    }
+
+    pub fn global<S: Into<String>>(message: S) -> Self {
+        Self {
+            pos: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.pos {
+            Some(pos) => write!(f, "{}:{}: {}", pos.line, pos.col, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub struct SceneDescription {
+    pub scene: Scene,
+    pub settings: RenderSettings,
+    // Things the loader skipped over, for the caller to report
+    pub warnings: Vec<String>,
+}
+
+// glTF files can be rendered directly, anything else is treated as a scene description
+pub fn load_scene(path: &Path) -> Result<SceneDescription, Error> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let extension = path.extension().and_then(|e| e.to_str());
+    if let Some("gltf") | Some("glb") = extension.map(str::to_lowercase).as_deref() {
+        return builder::SceneBuilder::new(base_dir).build_model(path);
+    }
+
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| Error::global(format!("couldn't read file: {}", e)))?;
+    parse_scene(&src, base_dir)
+}
+
+// Files referenced by the scene are resolved relative to base_dir
+pub fn parse_scene(src: &str, base_dir: &Path) -> Result<SceneDescription, Error> {
+    let nodes = parser::parse(src)?;
+    builder::SceneBuilder::new(base_dir).build(&nodes)
+}
+
+// Scratch directory for the importer tests, deleted again when it goes out of scope
+#[cfg(test)]
+struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("rt-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    fn write<C: AsRef<[u8]>>(&self, file: &str, contents: C) -> std::path::PathBuf {
+        let path = self.0.join(file);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(src: &str) -> String {
+        match parse_scene(src, Path::new("")) {
+            Ok(_) => panic!("scene should not load"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    const CAMERA: &str = r#"camera "perspective" { fov 60 }"#;
+    const SPHERE: &str = r#"shape "sphere" { radius 1 material "m" }"#;
+
+    #[test]
+    fn minimal_scene() {
+        let src = format!(
+            "{}\nmaterial \"m\" \"matte\" {{ diffuse 0.5 }}\n{}",
+            CAMERA, SPHERE
+        );
+        assert!(parse_scene(&src, Path::new("")).is_ok());
+    }
+
+    #[test]
+    fn turbulence_is_wrinkled() {
+        for class in &["turbulence", "wrinkled"] {
+            let src = format!(
+                "{}\ntexture \"t\" \"float\" \"{}\" {{ octaves 4 }}\n\
+                 material \"m\" \"matte\" {{ bump \"t\" }}\n{}",
+                CAMERA, class, SPHERE
+            );
+            assert!(parse_scene(&src, Path::new("")).is_ok(), "{}", class);
+        }
+        assert!(
+            error(&format!("{}\ntexture \"t\" \"float\" \"swirl\"", CAMERA))
+                .contains("fbm, turbulence, wrinkled")
+        );
+    }
+
+    #[test]
+    fn texture_space() {
+        let texture = |space: &str| {
+            format!(
+                "{}\ntexture \"t\" \"float\" \"fbm\" {{ space \"{}\" }}\n\
+                 material \"m\" \"matte\" {{ bump \"t\" }}\n{}",
+                CAMERA, space, SPHERE
+            )
+        };
+        assert!(parse_scene(&texture("object"), Path::new("")).is_ok());
+        assert_eq!(
+            error(&texture("screen")),
+            "2:29: unknown texture space 'screen' (expected world or object)"
+        );
+    }
+
+    #[test]
+    fn missing_camera() {
+        assert_eq!(error(""), "scene has no camera");
+    }
+
+    #[test]
+    fn unknown_directive() {
+        assert!(error(&format!("{}\n\n  lamp {{ }}", CAMERA))
+            .starts_with("3:3: unknown directive 'lamp'"));
+    }
+
+    #[test]
+    fn unknown_material_reference() {
+        let err = error(&format!(
+            "{}\nshape \"sphere\" {{\n    material \"nope\"\n}}",
+            CAMERA
+        ));
+        assert!(err.starts_with("3:5:"), "{}", err);
+    }
+
+    #[test]
+    fn syntax_errors_have_positions() {
+        assert_eq!(error("film {\n  resolution 4 4\n"), "1:6: unclosed '{'");
+    }
+}
@@ -0,0 +1,749 @@
+use crate::film::spectrum::Spectrum;
+use crate::geometry::mesh::TriangleMesh;
+use crate::geometry::{primitive::Primitive, receiver::Receiver, IntoGeometry};
+use crate::light::emitter::Emitter;
+use crate::material::{matte::Matte, mirror::Mirror, plastic::Plastic, Material};
+use crate::math::*;
+use crate::texture::{
+    constant::ConstantTexture,
+    image::ImageTexture,
+    mapping::TextureMapping2D,
+    mipmap::{FilterMode, WrapMode},
+    Texture,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Wavefront OBJ and MTL importer. Each group / material pair becomes a separate triangle mesh.
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+struct VertexRef {
+    v: usize,
+    vt: Option<usize>,
+    vn: Option<usize>,
+}
+
+struct Group {
+    material: Option<String>,
+    faces: Vec<Vec<VertexRef>>,
+}
+
+struct Mtl {
+    kd: Spectrum,
+    ks: Spectrum,
+    ke: Spectrum,
+    ns: Float,
+    dissolve: Float,
+    illum: i32,
+    map_kd: Option<PathBuf>,
+}
+
+impl Default for Mtl {
+    fn default() -> Self {
+        Self {
+            kd: Spectrum::all(0.8),
+            ks: Spectrum::black(),
+            ke: Spectrum::black(),
+            ns: 0.0,
+            dissolve: 1.0,
+            illum: 2,
+            map_kd: None,
+        }
+    }
+}
+
+fn floats(args: &[&str], min: usize) -> Result<Vec<Float>, String> {
+    if args.len() < min {
+        return Err(format!(
+            "expected at least {} numbers, found {}",
+            min,
+            args.len()
+        ));
+    }
+
+    args.iter()
+        .map(|a| {
+            a.parse::<Float>()
+                .map_err(|_| format!("invalid number '{}'", a))
+        })
+        .collect()
+}
+
+fn spectrum(args: &[&str]) -> Result<Spectrum, String> {
+    let f = floats(args, 1)?;
+    match f.len() {
+        1 => Ok(Spectrum::all(f[0])),
+        3 => Ok(Spectrum::from_rgb(f[0], f[1], f[2])),
+        n => Err(format!("expected 1 or 3 numbers, found {}", n)),
+    }
+}
+
+// Splits a line into its keyword and arguments, ignoring comments
+fn tokenize(line: &str) -> Option<(&str, Vec<&str>)> {
+    let mut tokens = line.split('#').next().unwrap().split_whitespace();
+    let keyword = tokens.next()?;
+    Some((keyword, tokens.collect()))
+}
+
+// The rest of the line after the keyword, for names that may contain spaces
+fn rest_of_line<'a>(line: &'a str, keyword: &str) -> &'a str {
+    line.split('#').next().unwrap().trim_start()[keyword.len()..].trim()
+}
+
+// OBJ indices start at 1; negative indices count back from the most recent element
+fn resolve_index(token: &str, count: usize) -> Result<usize, String> {
+    let i = token
+        .parse::<i64>()
+        .map_err(|_| format!("invalid index '{}'", token))?;
+
+    let resolved = if i > 0 {
+        i - 1
+    } else if i < 0 {
+        count as i64 + i
+    } else {
+        -1
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!("index {} is out of range ({} elements)", i, count));
+    }
+
+    Ok(resolved as usize)
+}
+
+fn optional_index(token: Option<&str>, count: usize) -> Result<Option<usize>, String> {
+    match token {
+        Some(t) if !t.is_empty() => Ok(Some(resolve_index(t, count)?)),
+        _ => Ok(None),
+    }
+}
+
+struct MtlParser<'a> {
+    dir: &'a Path,
+    materials: &'a mut HashMap<String, Mtl>,
+    current: Option<(String, Mtl)>,
+}
+
+impl<'a> MtlParser<'a> {
+    fn line(&mut self, text: &str) -> Result<(), String> {
+        let (keyword, args) = match tokenize(text) {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+
+        if keyword == "newmtl" {
+            self.finish();
+            self.current = Some((rest_of_line(text, keyword).to_string(), Mtl::default()));
+            return Ok(());
+        }
+
+        let mtl = match self.current.as_mut() {
+            Some((_, mtl)) => mtl,
+            None => return Ok(()),
+        };
+
+        match keyword {
+            "Kd" => mtl.kd = spectrum(&args)?,
+            "Ks" => mtl.ks = spectrum(&args)?,
+            "Ke" => mtl.ke = spectrum(&args)?,
+            "Ns" => mtl.ns = floats(&args, 1)?[0],
+            "d" => mtl.dissolve = floats(&args, 1)?[0],
+            "Tr" => mtl.dissolve = 1.0 - floats(&args, 1)?[0],
+            "illum" => mtl.illum = floats(&args, 1)?[0] as i32,
+            // Texture options (-s, -o, ...) come before the file name
+            "map_Kd" => match args.last() {
+                Some(file) => mtl.map_kd = Some(self.dir.join(file)),
+                None => return Err("missing file name".to_string()),
+            },
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        if let Some((name, mtl)) = self.current.take() {
+            self.materials.insert(name, mtl);
+        }
+    }
+}
+
+fn parse_mtl(path: &Path, materials: &mut HashMap<String, Mtl>) -> Result<(), String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("couldn't read '{}': {}", path.display(), e))?;
+
+    let mut parser = MtlParser {
+        dir: path.parent().unwrap_or_else(|| Path::new("")),
+        materials,
+        current: None,
+    };
+
+    for (idx, text) in src.lines().enumerate() {
+        parser
+            .line(text)
+            .map_err(|e| format!("{}:{}: {}", path.display(), idx + 1, e))?;
+    }
+
+    parser.finish();
+    Ok(())
+}
+
+// Ear clipping in the plane of the polygon's dominant axis. Falls back to a fan when no ear can
+// be found (e.g. self-intersecting or degenerate polygons).
+fn triangulate(points: &[LocalPoint3f]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Newell's method
+    let mut normal = [0.0 as Float; 3];
+    for i in 0..n {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        normal[0] += (a.y - b.y) * (a.z + b.z);
+        normal[1] += (a.z - b.z) * (a.x + b.x);
+        normal[2] += (a.x - b.x) * (a.y + b.y);
+    }
+
+    let axis = if normal[0].abs() > normal[1].abs() && normal[0].abs() > normal[2].abs() {
+        0
+    } else if normal[1].abs() > normal[2].abs() {
+        1
+    } else {
+        2
+    };
+
+    // Project so that the polygon winds counter-clockwise
+    let (ix, iy) = [(1, 2), (2, 0), (0, 1)][axis];
+    let flip = if normal[axis] < 0.0 { -1.0 } else { 1.0 };
+    let p = points
+        .iter()
+        .map(|p| (p[ix], p[iy] * flip))
+        .collect::<Vec<_>>();
+
+    let cross = |a: usize, b: usize, c: usize| {
+        (p[b].0 - p[a].0) * (p[c].1 - p[a].1) - (p[b].1 - p[a].1) * (p[c].0 - p[a].0)
+    };
+
+    let mut remaining = (0..n).collect::<Vec<_>>();
+    let mut out = Vec::with_capacity(n - 2);
+
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let corner = |i: usize| {
+            (
+                remaining[(i + m - 1) % m],
+                remaining[i],
+                remaining[(i + 1) % m],
+            )
+        };
+
+        let ear = (0..m).find(|&i| {
+            let (a, b, c) = corner(i);
+            cross(a, b, c) > 0.0
+                && remaining.iter().all(|&j| {
+                    j == a
+                        || j == b
+                        || j == c
+                        || cross(a, b, j) < 0.0
+                        || cross(b, c, j) < 0.0
+                        || cross(c, a, j) < 0.0
+                })
+        });
+
+        match ear {
+            Some(i) => {
+                let (a, b, c) = corner(i);
+                out.push([a, b, c]);
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+
+    for i in 1..remaining.len() - 1 {
+        out.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+
+    out
+}
+
+struct ObjData {
+    dir: PathBuf,
+    positions: Vec<LocalPoint3f>,
+    texcoords: Vec<Point2f>,
+    normals: Vec<LocalNormal3f>,
+    groups: Vec<Group>,
+    materials: HashMap<String, Mtl>,
+}
+
+impl ObjData {
+    // Starts a new group unless the current one is still empty
+    fn new_group(&mut self, material: Option<String>) {
+        match self.groups.last_mut() {
+            Some(g) if g.faces.is_empty() => g.material = material,
+            _ => self.groups.push(Group {
+                material,
+                faces: Vec::new(),
+            }),
+        }
+    }
+
+    fn line(&mut self, text: &str) -> Result<(), String> {
+        let (keyword, args) = match tokenize(text) {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+
+        match keyword {
+            "v" => {
+                let f = floats(&args, 3)?;
+                self.positions.push(LocalPoint3f::new(f[0], f[1], f[2]));
+            }
+            "vt" => {
+                let f = floats(&args, 1)?;
+                let v = f.get(1).cloned().unwrap_or(0.0);
+                self.texcoords.push(Point2f::new(f[0], v));
+            }
+            "vn" => {
+                let f = floats(&args, 3)?;
+                self.normals.push(LocalNormal3f::new(f[0], f[1], f[2]));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(format!(
+                        "face has {} vertices, expected at least 3",
+                        args.len()
+                    ));
+                }
+
+                let mut face = Vec::with_capacity(args.len());
+                for arg in &args {
+                    let mut parts = arg.split('/');
+                    face.push(VertexRef {
+                        v: resolve_index(parts.next().unwrap(), self.positions.len())?,
+                        vt: optional_index(parts.next(), self.texcoords.len())?,
+                        vn: optional_index(parts.next(), self.normals.len())?,
+                    });
+                }
+
+                self.groups.last_mut().unwrap().faces.push(face);
+            }
+            "g" | "o" => {
+                let material = self.groups.last().unwrap().material.clone();
+                self.new_group(material);
+            }
+            "usemtl" => {
+                let name = rest_of_line(text, keyword).to_string();
+                if !self.materials.contains_key(&name) {
+                    return Err(format!("unknown material '{}'", name));
+                }
+                self.new_group(Some(name));
+            }
+            "mtllib" => {
+                for file in &args {
+                    parse_mtl(&self.dir.join(file), &mut self.materials)?;
+                }
+            }
+            // Smoothing groups, free-form geometry, lines and points aren't supported
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_obj(path: &Path) -> Result<ObjData, String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("couldn't read '{}': {}", path.display(), e))?;
+
+    let mut data = ObjData {
+        dir: path.parent().unwrap_or_else(|| Path::new("")).to_path_buf(),
+        positions: Vec::new(),
+        texcoords: Vec::new(),
+        normals: Vec::new(),
+        groups: vec![Group {
+            material: None,
+            faces: Vec::new(),
+        }],
+        materials: HashMap::new(),
+    };
+
+    for (idx, text) in src.lines().enumerate() {
+        data.line(text)
+            .map_err(|e| format!("{}:{}: {}", path.display(), idx + 1, e))?;
+    }
+
+    Ok(data)
+}
+
+// Triangulates the faces, sharing vertices that use the same position, uv and normal
+fn weld(data: &ObjData, faces: &[Vec<VertexRef>]) -> (Vec<VertexRef>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut lookup = HashMap::new();
+    let mut indices = Vec::new();
+
+    for face in faces {
+        let points = face.iter().map(|r| data.positions[r.v]).collect::<Vec<_>>();
+
+        for tri in triangulate(&points) {
+            for &corner in &tri {
+                let vertex = face[corner];
+                let index = *lookup.entry(vertex).or_insert_with(|| {
+                    vertices.push(vertex);
+                    vertices.len() as u32 - 1
+                });
+                indices.push(index);
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+fn build_mesh(data: &ObjData, faces: &[Vec<VertexRef>]) -> Result<TriangleMesh, String> {
+    let (vertices, indices) = weld(data, faces);
+    let positions = vertices
+        .iter()
+        .map(|r| data.positions[r.v])
+        .collect::<Vec<_>>();
+
+    let uvs = if vertices.iter().any(|r| r.vt.is_some()) {
+        Some(
+            vertices
+                .iter()
+                .map(|r| r.vt.map_or(Point2f::new(0.0, 0.0), |i| data.texcoords[i]))
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let normals = vertex_normals(data, &vertices, &positions, &indices);
+
+    TriangleMesh::new(positions, indices, normals, uvs, None)
+}
+
+// Vertices without a normal get the area-weighted average of the faces around them. Every
+// normal is then made unit length, so that ones from the file and computed ones weigh the same
+// when they're interpolated and differenced.
+fn vertex_normals(
+    data: &ObjData,
+    vertices: &[VertexRef],
+    positions: &[LocalPoint3f],
+    indices: &[u32],
+) -> Option<Vec<LocalNormal3f>> {
+    if vertices.iter().all(|r| r.vn.is_none()) {
+        return None;
+    }
+
+    let mut normals = vertices
+        .iter()
+        .map(|r| r.vn.map_or(LocalNormal3f::default(), |i| data.normals[i]))
+        .collect::<Vec<_>>();
+
+    for tri in indices.chunks(3) {
+        let [i0, i1, i2] = [tri[0] as usize, tri[1] as usize, tri[2] as usize];
+        let n = (positions[i1] - positions[i0]).cross(positions[i2] - positions[i0]);
+        for &i in &[i0, i1, i2] {
+            if vertices[i].vn.is_none() {
+                normals[i] += LocalNormal3f::new(n.x, n.y, n.z);
+            }
+        }
+    }
+
+    for n in &mut normals {
+        if n.length_squared() > 0.0 {
+            *n = n.normalized();
+        }
+    }
+
+    Some(normals)
+}
+
+fn build_material(
+    mtl: &Mtl,
+    textures: &mut HashMap<PathBuf, Arc<ImageTexture<Spectrum>>>,
+) -> Result<Arc<dyn Material + Send + Sync>, String> {
+    let kd: Arc<dyn Texture<Spectrum> + Send + Sync> = match &mtl.map_kd {
+        Some(path) => match textures.get(path) {
+            Some(texture) => texture.clone(),
+            None => {
+                let texture = Arc::new(ImageTexture::open(
+                    path,
+                    true,
+                    WrapMode::Repeat,
+                    FilterMode::default(),
+                    TextureMapping2D::default(),
+                )?);
+                textures.insert(path.clone(), texture.clone());
+                texture
+            }
+        },
+        None => Arc::new(ConstantTexture::new(mtl.kd)),
+    };
+    let ks = Arc::new(ConstantTexture::new(mtl.ks));
+
+    // illum 3 is "reflection on and ray trace on"
+    let material: Arc<dyn Material + Send + Sync> =
+        if !mtl.ks.is_black() && (mtl.illum == 3 || (mtl.kd.is_black() && mtl.map_kd.is_none())) {
+            Arc::new(Mirror::new(ks, None, None))
+        } else if !mtl.ks.is_black() {
+            // Approximate mapping from a Phong exponent
+            let roughness = (2.0 / (mtl.ns.max(0.0) + 2.0)).sqrt();
+            Arc::new(Plastic::new(
+                kd,
+                ks,
+                Arc::new(ConstantTexture::new(roughness)),
+                None,
+                None,
+            ))
+        } else {
+            Arc::new(Matte::new(kd, None, None, None))
+        };
+
+    Ok(material)
+}
+
+// Partially transparent materials are rendered as opaque, fully transparent ones are skipped
+pub fn load(
+    path: &Path,
+    transform: Transform,
+    material_override: Option<Arc<dyn Material + Send + Sync>>,
+) -> Result<Vec<Primitive>, String> {
+    let data = parse_obj(path)?;
+    let transform = AnimatedTransform::from(transform);
+
+    let default_mtl = Mtl::default();
+    let mut built_materials = HashMap::new();
+    let mut textures = HashMap::new();
+    let mut primitives = Vec::new();
+
+    for group in data.groups.iter().filter(|g| !g.faces.is_empty()) {
+        let mtl = match &group.material {
+            Some(name) => &data.materials[name],
+            None => &default_mtl,
+        };
+
+        if mtl.dissolve <= 0.0 {
+            continue;
+        }
+
+        let material = match &material_override {
+            Some(material) => material.clone(),
+            None => match built_materials.get(&group.material) {
+                Some(material) => Arc::clone(material),
+                None => {
+                    let material = build_material(mtl, &mut textures)?;
+                    built_materials.insert(group.material.clone(), material.clone());
+                    material
+                }
+            },
+        };
+
+        let mesh =
+            build_mesh(&data, &group.faces).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        for triangle in mesh.into_triangles() {
+            let triangle = Arc::new(triangle);
+            primitives.push(if mtl.ke.is_black() {
+                Primitive::Receiver(Receiver::new(
+                    triangle.into_geometry(),
+                    material.clone(),
+                    transform.clone(),
+                ))
+            } else {
+                Primitive::Emitter(Emitter::new_area(
+                    mtl.ke,
+                    transform.clone(),
+                    triangle,
+                    material.clone(),
+                ))
+            });
+        }
+    }
+
+    Ok(primitives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::TempDir;
+
+    // Writes the given files into a fresh directory and returns the path of the first one
+    fn fixture(name: &str, files: &[(&str, &str)]) -> (TempDir, PathBuf) {
+        let dir = TempDir::new(&format!("obj-{}", name));
+        for (file, contents) in files {
+            dir.write(file, contents);
+        }
+        let path = dir.0.join(files[0].0);
+        (dir, path)
+    }
+
+    const CUBE_FACES: &str = "
+        mtllib cube.mtl
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vt 0 1
+        vn 0 0 2
+        g front
+        usemtl red
+        f 1/1/1 2/2/1 3/3/1 4/4/1
+        g back # comment
+        usemtl glow
+        f -4 -1 -2
+        usemtl hidden
+        f 1 2 3
+    ";
+
+    const CUBE_MTL: &str = "
+        newmtl red
+        Kd 1 0 0
+        Ks 0.5
+        Ns 100
+        newmtl glow
+        Ke 4 4 4
+        newmtl hidden
+        d 0
+    ";
+
+    #[test]
+    fn groups_materials_and_indices() {
+        let (_dir, path) = fixture(
+            "groups",
+            &[("cube.obj", CUBE_FACES), ("cube.mtl", CUBE_MTL)],
+        );
+        let data = parse_obj(&path).unwrap();
+
+        assert_eq!(data.positions.len(), 4);
+        assert_eq!(data.texcoords.len(), 4);
+        let groups = data
+            .groups
+            .iter()
+            .map(|g| (g.material.as_deref(), g.faces.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            vec![(Some("red"), 1), (Some("glow"), 1), (Some("hidden"), 1)]
+        );
+
+        let quad = &data.groups[0].faces[0];
+        assert_eq!(quad.len(), 4);
+        assert_eq!((quad[2].v, quad[2].vt, quad[2].vn), (2, Some(2), Some(0)));
+
+        // Negative indices count back from the last vertex
+        let tri = &data.groups[1].faces[0];
+        assert_eq!(tri.iter().map(|r| r.v).collect::<Vec<_>>(), vec![0, 3, 2]);
+        assert_eq!(tri[0].vt, None);
+
+        assert_eq!(data.materials["red"].kd.to_rgb(), [1.0, 0.0, 0.0]);
+        assert_eq!(data.materials["red"].ns, 100.0);
+        assert_eq!(data.materials["glow"].ke.to_rgb(), [4.0; 3]);
+        assert_eq!(data.materials["hidden"].dissolve, 0.0);
+    }
+
+    #[test]
+    fn load_skips_transparent_groups() {
+        let (_dir, path) = fixture("load", &[("cube.obj", CUBE_FACES), ("cube.mtl", CUBE_MTL)]);
+        let primitives = load(&path, Transform::scale(1.0, 1.0, 1.0), None).unwrap();
+
+        // Two triangles from the quad and one emissive triangle; the hidden group is dropped
+        assert_eq!(primitives.len(), 3);
+        let emitters = primitives
+            .iter()
+            .filter(|p| matches!(p, Primitive::Emitter(_)))
+            .count();
+        assert_eq!(emitters, 1);
+    }
+
+    #[test]
+    fn errors_name_file_and_line() {
+        let (_dir, path) = fixture("errors", &[("bad.obj", "v 0 0 0\nv 1 0 0\nf 1 2 3\n")]);
+        let err = parse_obj(&path).err().unwrap();
+        assert!(
+            err.ends_with("bad.obj:3: index 3 is out of range (2 elements)"),
+            "{}",
+            err
+        );
+
+        let (_dir, path) = fixture("usemtl", &[("bad.obj", "# no mtllib\nusemtl red\n")]);
+        let err = parse_obj(&path).err().unwrap();
+        assert!(
+            err.ends_with("bad.obj:2: unknown material 'red'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn concave_polygons_are_ear_clipped() {
+        // An L shape, which a fan from the first vertex would get wrong
+        let points = [
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ]
+        .iter()
+        .map(|&(x, y)| LocalPoint3f::new(x, y, 0.0))
+        .collect::<Vec<_>>();
+
+        let triangles = triangulate(&points);
+        assert_eq!(triangles.len(), 4);
+
+        let area: Float = triangles
+            .iter()
+            .map(|t| {
+                let (a, b, c) = (points[t[0]], points[t[1]], points[t[2]]);
+                let n = (b - a).cross(c - a);
+                assert!(n.z > 0.0, "triangle {:?} is flipped", t);
+                0.5 * n.z
+            })
+            .sum();
+        assert!((area - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn normals_are_unit_length() {
+        // The first face has a (non-unit) normal from the file, the second face's normals are
+        // computed from its area
+        let src = "
+            v 0 0 0
+            v 4 0 0
+            v 4 4 0
+            v 0 4 0
+            v 0 0 -4
+            vn 0 0 3
+            f 1//1 2//1 3//1
+            f 1 4 5
+        ";
+        let (_dir, path) = fixture("normals", &[("normals.obj", src)]);
+        let data = parse_obj(&path).unwrap();
+        let (vertices, indices) = weld(&data, &data.groups[0].faces);
+        let positions = vertices
+            .iter()
+            .map(|r| data.positions[r.v])
+            .collect::<Vec<_>>();
+        let normals = vertex_normals(&data, &vertices, &positions, &indices).unwrap();
+
+        assert_eq!(normals.len(), 6);
+        for (vertex, n) in vertices.iter().zip(&normals) {
+            assert!((n.length() - 1.0).abs() < 1e-5, "{:?}", n);
+            let expected = if vertex.vn.is_some() {
+                LocalNormal3f::new(0.0, 0.0, 1.0)
+            } else {
+                LocalNormal3f::new(-1.0, 0.0, 0.0)
+            };
+            assert!((*n - expected).length() < 1e-5, "{:?}", n);
+        }
+    }
+}
@@ -0,0 +1,388 @@
+use super::lexer::{Lexer, Token, TokenKind};
+use super::{Error, Pos};
+use crate::film::spectrum::Spectrum;
+use crate::math::*;
+
+#[derive(Debug, Clone)]
+pub enum ArgValue {
+    Num(f64),
+    Str(String),
+    List(Vec<f64>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Arg {
+    pub value: ArgValue,
+    pub pos: Pos,
+}
+
+// A statement of the form `name arg* { children }?`
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub pos: Pos,
+    pub args: Vec<Arg>,
+    pub children: Vec<Node>,
+}
+
+pub fn parse(src: &str) -> Result<Vec<Node>, Error> {
+    let mut lexer = Lexer::new(src);
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next_token()? {
+        tokens.push(token);
+    }
+
+    let mut parser = Parser {
+        tokens: tokens.into_iter().peekable(),
+    };
+
+    let nodes = parser.nodes()?;
+    match parser.tokens.next() {
+        Some(token) => Err(Error::new(token.pos, "unmatched '}'")),
+        None => Ok(nodes),
+    }
+}
+
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn nodes(&mut self) -> Result<Vec<Node>, Error> {
+        let mut nodes = Vec::new();
+        while let Some(token) = self.tokens.peek() {
+            match token.kind {
+                TokenKind::Ident(_) => nodes.push(self.node()?),
+                TokenKind::RBrace => break,
+                _ => return Err(Error::new(token.pos, "expected a name")),
+            }
+        }
+        Ok(nodes)
+    }
+
+    fn node(&mut self) -> Result<Node, Error> {
+        let (name, pos) = match self.tokens.next() {
+            Some(Token {
+                kind: TokenKind::Ident(name),
+                pos,
+            }) => (name, pos),
+            _ => unreachable!(),
+        };
+
+        let mut args = Vec::new();
+        while let Some(token) = self.tokens.peek() {
+            let pos = token.pos;
+            let value = match token.kind {
+                TokenKind::Num(n) => {
+                    self.tokens.next();
+                    ArgValue::Num(n)
+                }
+                TokenKind::Str(_) => match self.tokens.next().map(|t| t.kind) {
+                    Some(TokenKind::Str(s)) => ArgValue::Str(s),
+                    _ => unreachable!(),
+                },
+                TokenKind::LBracket => {
+                    self.tokens.next();
+                    ArgValue::List(self.list(pos)?)
+                }
+                _ => break,
+            };
+            args.push(Arg { value, pos });
+        }
+
+        let mut children = Vec::new();
+        if let Some(TokenKind::LBrace) = self.tokens.peek().map(|t| &t.kind) {
+            let open = self.tokens.next().unwrap().pos;
+            children = self.nodes()?;
+            match self.tokens.next() {
+                Some(Token {
+                    kind: TokenKind::RBrace,
+                    ..
+                }) => (),
+                _ => return Err(Error::new(open, "unclosed '{'")),
+            }
+        }
+
+        Ok(Node {
+            name,
+            pos,
+            args,
+            children,
+        })
+    }
+
+    fn list(&mut self, open: Pos) -> Result<Vec<f64>, Error> {
+        let mut out = Vec::new();
+        loop {
+            match self.tokens.next() {
+                Some(Token {
+                    kind: TokenKind::Num(n),
+                    ..
+                }) => out.push(n),
+                Some(Token {
+                    kind: TokenKind::RBracket,
+                    ..
+                }) => return Ok(out),
+                Some(token) => return Err(Error::new(token.pos, "expected a number or ']'")),
+                None => return Err(Error::new(open, "unclosed '['")),
+            }
+        }
+    }
+}
+
+impl Node {
+    pub fn error<S: Into<String>>(&self, message: S) -> Error {
+        Error::new(self.pos, message)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Node> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn expect_children(&self, allowed: &[&str]) -> Result<(), Error> {
+        match self
+            .children
+            .iter()
+            .find(|c| !allowed.contains(&c.name.as_str()))
+        {
+            Some(c) => Err(c.error(format!(
+                "unknown parameter '{}' for '{}' (expected one of: {})",
+                c.name,
+                self.name,
+                allowed.join(", ")
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn string_arg(&self, idx: usize, what: &str) -> Result<&str, Error> {
+        match self.args.get(idx) {
+            Some(Arg {
+                value: ArgValue::Str(s),
+                ..
+            }) => Ok(s),
+            Some(arg) => Err(Error::new(arg.pos, format!("expected {} string", what))),
+            None => Err(self.error(format!("'{}' is missing {}", self.name, what))),
+        }
+    }
+
+    pub fn has_string_arg(&self) -> bool {
+        matches!(
+            self.args.first(),
+            Some(Arg {
+                value: ArgValue::Str(_),
+                ..
+            })
+        )
+    }
+
+    pub fn floats(&self) -> Result<Vec<Float>, Error> {
+        let mut out = Vec::new();
+        for arg in &self.args {
+            match &arg.value {
+                ArgValue::Num(n) => out.push(*n as Float),
+                ArgValue::List(l) => out.extend(l.iter().map(|n| *n as Float)),
+                ArgValue::Str(_) => {
+                    return Err(Error::new(
+                        arg.pos,
+                        format!("expected a number for '{}'", self.name),
+                    ));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn floats_n(&self, n: usize) -> Result<Vec<Float>, Error> {
+        let out = self.floats()?;
+        if out.len() != n {
+            return Err(self.error(format!(
+                "'{}' expects {} number{}, found {}",
+                self.name,
+                n,
+                if n == 1 { "" } else { "s" },
+                out.len()
+            )));
+        }
+        Ok(out)
+    }
+
+    pub fn float(&self) -> Result<Float, Error> {
+        Ok(self.floats_n(1)?[0])
+    }
+
+    pub fn int(&self) -> Result<i32, Error> {
+        let f = self.float()?;
+        if f.fract() != 0.0 {
+            return Err(self.error(format!("'{}' expects an integer", self.name)));
+        }
+        Ok(f as i32)
+    }
+
+    pub fn uints(&self) -> Result<Vec<u32>, Error> {
+        self.floats()?
+            .into_iter()
+            .map(|f| {
+                if f.fract() != 0.0 || f < 0.0 {
+                    Err(self.error(format!("'{}' expects non-negative integers", self.name)))
+                } else {
+                    Ok(f as u32)
+                }
+            })
+            .collect()
+    }
+
+    // A flat list of numbers split into groups of n, e.g. vertex positions
+    pub fn tuples(&self, n: usize) -> Result<Vec<Vec<Float>>, Error> {
+        let f = self.floats()?;
+        if f.len() % n != 0 {
+            return Err(self.error(format!(
+                "'{}' expects a multiple of {} numbers, found {}",
+                self.name,
+                n,
+                f.len()
+            )));
+        }
+        Ok(f.chunks(n).map(<[Float]>::to_vec).collect())
+    }
+
+    pub fn point3(&self) -> Result<Point3f, Error> {
+        let f = self.floats_n(3)?;
+        Ok(Point3f::new(f[0], f[1], f[2]))
+    }
+
+    pub fn vec3(&self) -> Result<Vec3f, Error> {
+        let f = self.floats_n(3)?;
+        Ok(Vec3f::new(f[0], f[1], f[2]))
+    }
+
+    // Either a single grey value or three RGB components
+    pub fn spectrum(&self) -> Result<Spectrum, Error> {
+        let f = self.floats()?;
+        match f.len() {
+            1 => Ok(Spectrum::all(f[0])),
+            3 => Ok(Spectrum::from_rgb(f[0], f[1], f[2])),
+            n => Err(self.error(format!(
+                "'{}' expects 1 or 3 numbers, found {}",
+                self.name, n
+            ))),
+        }
+    }
+
+    pub fn float_or(&self, name: &str, default: Float) -> Result<Float, Error> {
+        self.child(name).map_or(Ok(default), Node::float)
+    }
+
+    pub fn int_or(&self, name: &str, default: i32) -> Result<i32, Error> {
+        self.child(name).map_or(Ok(default), Node::int)
+    }
+
+    pub fn required(&self, name: &str) -> Result<&Node, Error> {
+        self.child(name)
+            .ok_or_else(|| self.error(format!("'{}' is missing '{}'", self.name, name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(src: &str) -> String {
+        parse(src).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn nested_nodes() {
+        let nodes = parse(
+            r#"
+            material "red" "matte" { diffuse 0.8 0 0 }
+            shape "mesh" {
+                indices [ 0 1 2 ]
+                transform { translate 1 2 3 }
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(nodes.len(), 2);
+
+        let material = &nodes[0];
+        assert_eq!(material.name, "material");
+        assert_eq!(material.pos, Pos { line: 2, col: 13 });
+        assert_eq!(material.string_arg(0, "a name").unwrap(), "red");
+        assert_eq!(material.string_arg(1, "a type").unwrap(), "matte");
+        assert_eq!(
+            material.required("diffuse").unwrap().floats().unwrap(),
+            vec![0.8, 0.0, 0.0]
+        );
+
+        let shape = &nodes[1];
+        assert_eq!(shape.children.len(), 2);
+        assert_eq!(
+            shape.child("indices").unwrap().uints().unwrap(),
+            vec![0, 1, 2]
+        );
+        let transform = shape.child("transform").unwrap();
+        assert_eq!(transform.children[0].name, "translate");
+        assert_eq!(transform.children[0].pos, Pos { line: 5, col: 29 });
+    }
+
+    #[test]
+    fn unclosed_brace_points_at_brace() {
+        assert_eq!(error("film {\n  resolution 4 4\n"), "1:6: unclosed '{'");
+    }
+
+    #[test]
+    fn unmatched_brace() {
+        assert_eq!(error("film { }\n}"), "2:1: unmatched '}'");
+    }
+
+    #[test]
+    fn unclosed_list() {
+        assert_eq!(error("shape [ 1 2"), "1:7: unclosed '['");
+        assert_eq!(error("shape [ 1 \"x\" ]"), "1:11: expected a number or ']'");
+    }
+
+    #[test]
+    fn statement_must_start_with_name() {
+        assert_eq!(error("film { }\n  2"), "2:3: expected a name");
+    }
+
+    #[test]
+    fn lexer_errors_propagate() {
+        assert_eq!(error("film {\n  $ }"), "2:3: unexpected character '$'");
+    }
+
+    #[test]
+    fn argument_checks() {
+        let nodes = parse("a 1 2\nb \"x\"\nc 1.5\nd [ 1 2 3 4 ]\ne { f 1 }").unwrap();
+        assert_eq!(
+            nodes[0].floats_n(3).unwrap_err().to_string(),
+            "1:1: 'a' expects 3 numbers, found 2"
+        );
+        assert_eq!(
+            nodes[1].float().unwrap_err().to_string(),
+            "2:3: expected a number for 'b'"
+        );
+        assert_eq!(
+            nodes[2].int().unwrap_err().to_string(),
+            "3:1: 'c' expects an integer"
+        );
+        assert_eq!(
+            nodes[3].tuples(3).unwrap_err().to_string(),
+            "4:1: 'd' expects a multiple of 3 numbers, found 4"
+        );
+        assert_eq!(
+            nodes[3].tuples(2).unwrap(),
+            vec![vec![1.0, 2.0], vec![3.0, 4.0]]
+        );
+        assert_eq!(
+            nodes[4].expect_children(&["g"]).unwrap_err().to_string(),
+            "5:5: unknown parameter 'f' for 'e' (expected one of: g)"
+        );
+        assert_eq!(nodes[4].float_or("g", 2.0).unwrap(), 2.0);
+        assert_eq!(
+            nodes[4].required("g").unwrap_err().to_string(),
+            "5:1: 'e' is missing 'g'"
+        );
+    }
+}
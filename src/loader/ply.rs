@@ -0,0 +1,559 @@
+use crate::film::spectrum::{srgb_to_linear, Spectrum};
+use crate::geometry::mesh::TriangleMesh;
+use crate::math::*;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// Stanford PLY reader. Faces with more than three vertices are split into fans and any
+// elements other than vertices and faces are skipped.
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Copy, Clone)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return Err(format!("unknown property type '{}'", name)),
+        })
+    }
+}
+
+enum Kind {
+    Scalar(Scalar),
+    List { count: Scalar, item: Scalar },
+}
+
+struct Property {
+    name: String,
+    kind: Kind,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+// Reads one scalar at a time from the body, in whichever encoding the file uses
+struct Body<R: BufRead> {
+    reader: R,
+    encoding: Encoding,
+}
+
+impl<R: BufRead> Body<R> {
+    fn read(&mut self, ty: Scalar) -> Result<f64, String> {
+        match self.encoding {
+            Encoding::Ascii => self.read_ascii(),
+            _ => self.read_binary(ty),
+        }
+    }
+
+    // Parses the next whitespace separated token without allocating
+    fn read_ascii(&mut self) -> Result<f64, String> {
+        let mut token = [0u8; 64];
+        let mut len = 0;
+
+        loop {
+            let buf = self.reader.fill_buf().map_err(|e| e.to_string())?;
+            if buf.is_empty() {
+                break;
+            }
+
+            let mut used = 0;
+            let mut done = false;
+            for &byte in buf {
+                if byte.is_ascii_whitespace() {
+                    used += 1;
+                    if len > 0 {
+                        done = true;
+                        break;
+                    }
+                } else if len < token.len() {
+                    token[len] = byte;
+                    len += 1;
+                    used += 1;
+                } else {
+                    return Err("number is too long".to_string());
+                }
+            }
+
+            self.reader.consume(used);
+            if done {
+                break;
+            }
+        }
+
+        if len == 0 {
+            return Err("unexpected end of file".to_string());
+        }
+
+        let text = std::str::from_utf8(&token[..len]).map_err(|_| "invalid number".to_string())?;
+        text.parse::<f64>()
+            .map_err(|_| format!("invalid number '{}'", text))
+    }
+
+    fn read_binary(&mut self, ty: Scalar) -> Result<f64, String> {
+        let size = match ty {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        };
+
+        let mut bytes = [0u8; 8];
+        self.reader
+            .read_exact(&mut bytes[..size])
+            .map_err(|_| "unexpected end of file".to_string())?;
+
+        // Byte swapping makes the data little endian so that one decoder handles both
+        if self.encoding == Encoding::BigEndian {
+            bytes[..size].reverse();
+        }
+
+        let mut b2 = [0u8; 2];
+        let mut b4 = [0u8; 4];
+        b2.copy_from_slice(&bytes[..2]);
+        b4.copy_from_slice(&bytes[..4]);
+
+        Ok(match ty {
+            Scalar::I8 => f64::from(bytes[0] as i8),
+            Scalar::U8 => f64::from(bytes[0]),
+            Scalar::I16 => f64::from(i16::from_le_bytes(b2)),
+            Scalar::U16 => f64::from(u16::from_le_bytes(b2)),
+            Scalar::I32 => f64::from(i32::from_le_bytes(b4)),
+            Scalar::U32 => f64::from(u32::from_le_bytes(b4)),
+            Scalar::F32 => f64::from(f32::from_le_bytes(b4)),
+            Scalar::F64 => f64::from_le_bytes(bytes),
+        })
+    }
+
+    fn skip(&mut self, property: &Property) -> Result<(), String> {
+        match property.kind {
+            Kind::Scalar(ty) => {
+                self.read(ty)?;
+            }
+            Kind::List { count, item } => {
+                for _ in 0..self.read(count)? as usize {
+                    self.read(item)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_header<R: BufRead>(reader: &mut R) -> Result<(Encoding, Vec<Element>), String> {
+    let mut line = String::new();
+    let mut next_line = |line: &mut String| -> Result<(), String> {
+        line.clear();
+        match reader.read_line(line) {
+            Ok(0) => Err("unexpected end of header".to_string()),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    };
+
+    next_line(&mut line)?;
+    if line.trim_end() != "ply" {
+        return Err("not a PLY file".to_string());
+    }
+
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+
+    loop {
+        next_line(&mut line)?;
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+        match tokens.as_slice() {
+            ["end_header"] => break,
+            ["format", format, _] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::LittleEndian,
+                    "binary_big_endian" => Encoding::BigEndian,
+                    _ => return Err(format!("unknown format '{}'", format)),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| format!("invalid element count '{}'", count))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let property = Property {
+                    name: name.to_string(),
+                    kind: Kind::List {
+                        count: Scalar::parse(count)?,
+                        item: Scalar::parse(item)?,
+                    },
+                };
+                elements
+                    .last_mut()
+                    .ok_or("property before any element")?
+                    .properties
+                    .push(property);
+            }
+            ["property", ty, name] => {
+                let property = Property {
+                    name: name.to_string(),
+                    kind: Kind::Scalar(Scalar::parse(ty)?),
+                };
+                elements
+                    .last_mut()
+                    .ok_or("property before any element")?
+                    .properties
+                    .push(property);
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => (),
+            _ => return Err(format!("invalid header line '{}'", line.trim_end())),
+        }
+    }
+
+    Ok((encoding.ok_or("missing format line")?, elements))
+}
+
+// Indices into the per-vertex value array
+const X: usize = 0;
+const NX: usize = 3;
+const U: usize = 6;
+const RED: usize = 8;
+const SLOTS: usize = 11;
+
+fn vertex_slot(name: &str) -> Option<usize> {
+    Some(match name {
+        "x" => X,
+        "y" => X + 1,
+        "z" => X + 2,
+        "nx" => NX,
+        "ny" => NX + 1,
+        "nz" => NX + 2,
+        "u" | "s" | "texture_u" | "texture_s" => U,
+        "v" | "t" | "texture_v" | "texture_t" => U + 1,
+        "red" | "r" => RED,
+        "green" | "g" => RED + 1,
+        "blue" | "b" => RED + 2,
+        _ => return None,
+    })
+}
+
+pub fn load(path: &Path) -> Result<TriangleMesh, String> {
+    let file =
+        File::open(path).map_err(|e| format!("couldn't read '{}': {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+    let error = |e: String| format!("{}: {}", path.display(), e);
+
+    let (encoding, elements) = parse_header(&mut reader).map_err(error)?;
+    let mut body = Body { reader, encoding };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::new();
+    let mut has = [false; SLOTS];
+    let mut polygon = Vec::new();
+
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let slots = element
+                    .properties
+                    .iter()
+                    .map(|p| match p.kind {
+                        Kind::Scalar(ty) => vertex_slot(&p.name).map(|s| (s, ty)),
+                        Kind::List { .. } => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                for &(slot, _) in slots.iter().flatten() {
+                    has[slot] = true;
+                }
+
+                if !(has[X] && has[X + 1] && has[X + 2]) {
+                    return Err(error("vertices have no positions".to_string()));
+                }
+
+                // Integer colors are 8-bit sRGB, floating point ones are linear
+                let srgb_colors = slots
+                    .iter()
+                    .flatten()
+                    .any(|&(slot, ty)| slot == RED && !matches!(ty, Scalar::F32 | Scalar::F64));
+
+                positions.reserve(element.count);
+                for _ in 0..element.count {
+                    let mut v = [0.0 as Float; SLOTS];
+                    for (property, slot) in element.properties.iter().zip(&slots) {
+                        match slot {
+                            Some((slot, ty)) => v[*slot] = body.read(*ty).map_err(error)? as Float,
+                            None => body.skip(property).map_err(error)?,
+                        }
+                    }
+
+                    positions.push(LocalPoint3f::new(v[X], v[X + 1], v[X + 2]));
+                    if has[NX] {
+                        normals.push(LocalNormal3f::new(v[NX], v[NX + 1], v[NX + 2]));
+                    }
+                    if has[U] {
+                        uvs.push(Point2f::new(v[U], v[U + 1]));
+                    }
+                    if has[RED] {
+                        colors.push(if srgb_colors {
+                            Spectrum::from_rgb(
+                                srgb_to_linear(v[RED] / 255.0),
+                                srgb_to_linear(v[RED + 1] / 255.0),
+                                srgb_to_linear(v[RED + 2] / 255.0),
+                            )
+                        } else {
+                            Spectrum::from_rgb(v[RED], v[RED + 1], v[RED + 2])
+                        });
+                    }
+                }
+            }
+            "face" => {
+                indices.reserve(element.count * 3);
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        match property.kind {
+                            Kind::List { count, item }
+                                if property.name == "vertex_indices"
+                                    || property.name == "vertex_index" =>
+                            {
+                                polygon.clear();
+                                for _ in 0..body.read(count).map_err(error)? as usize {
+                                    polygon.push(body.read(item).map_err(error)? as u32);
+                                }
+
+                                for i in 1..polygon.len().saturating_sub(1) {
+                                    indices.extend_from_slice(&[
+                                        polygon[0],
+                                        polygon[i],
+                                        polygon[i + 1],
+                                    ]);
+                                }
+                            }
+                            _ => body.skip(property).map_err(error)?,
+                        }
+                    }
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        body.skip(property).map_err(error)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let mesh = TriangleMesh::new(
+        positions,
+        indices,
+        if has[NX] { Some(normals) } else { None },
+        if has[U] { Some(uvs) } else { None },
+        None,
+    )
+    .map_err(error)?;
+
+    if has[RED] {
+        mesh.with_colors(colors).map_err(error)
+    } else {
+        Ok(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::LocalAABB;
+    use crate::loader::TempDir;
+
+    fn load_fixture(name: &str, header: &str, body: &[u8]) -> Result<TriangleMesh, String> {
+        let dir = TempDir::new(&format!("ply-{}", name));
+        let mut contents = header.as_bytes().to_vec();
+        contents.extend_from_slice(body);
+        load(&dir.write("mesh.ply", contents))
+    }
+
+    fn bounds(mesh: TriangleMesh) -> Vec<([Float; 3], [Float; 3])> {
+        mesh.into_triangles()
+            .iter()
+            .map(|t| {
+                let b = t.local_aabb();
+                ([b.min.x, b.min.y, b.min.z], [b.max.x, b.max.y, b.max.z])
+            })
+            .collect()
+    }
+
+    const UNIT_QUAD: [([Float; 3], [Float; 3]); 2] = [
+        ([0.0, 0.0, 0.0], [1.0, 1.0, 0.0]),
+        ([0.0, 0.0, 0.0], [1.0, 1.0, 0.0]),
+    ];
+
+    #[test]
+    fn ascii_with_attributes() {
+        let header = "ply
+format ascii 1.0
+comment a unit quad
+element vertex 4
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property float s
+property float t
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+        let body = "0 0 0 0 0 1 0 0 255 0 0
+1 0 0 0 0 1 1 0 0 255 0
+1 1 0 0 0 1 1 1 0 0 255
+0 1 0 0 0 1 0 1 255 255 255
+4 0 1 2 3
+";
+        let mesh = load_fixture("ascii", header, body.as_bytes()).unwrap();
+        assert_eq!(mesh.num_triangles(), 2);
+        assert_eq!(bounds(mesh), UNIT_QUAD.to_vec());
+    }
+
+    #[test]
+    fn binary_skips_unknown_properties_and_elements() {
+        let header = |format: &str| {
+            format!(
+                "ply
+format {} 1.0
+element vertex 4
+property float x
+property float y
+property float z
+property double confidence
+element face 1
+property uchar flags
+property list uchar uint vertex_index
+property list uchar float texcoord
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+",
+                format
+            )
+        };
+
+        let positions: [[f32; 3]; 4] = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+
+        for &(format, big_endian) in &[("binary_little_endian", false), ("binary_big_endian", true)]
+        {
+            let mut body = Vec::new();
+            let mut push = |bytes: &[u8]| {
+                let mut bytes = bytes.to_vec();
+                if big_endian {
+                    bytes.reverse();
+                }
+                body.extend_from_slice(&bytes);
+            };
+
+            for p in &positions {
+                for c in p {
+                    push(&c.to_le_bytes());
+                }
+                push(&0.5f64.to_le_bytes());
+            }
+            push(&[7]);
+            push(&[4]);
+            for i in 0..4u32 {
+                push(&i.to_le_bytes());
+            }
+            push(&[2]);
+            push(&0.25f32.to_le_bytes());
+            push(&0.75f32.to_le_bytes());
+            push(&0i32.to_le_bytes());
+            push(&1i32.to_le_bytes());
+
+            let mesh = load_fixture(format, &header(format), &body).unwrap();
+            assert_eq!(mesh.num_triangles(), 2, "{}", format);
+            assert_eq!(bounds(mesh), UNIT_QUAD.to_vec(), "{}", format);
+        }
+    }
+
+    #[test]
+    fn header_errors() {
+        let error = |name: &str, header: &str| load_fixture(name, header, b"").err().unwrap();
+
+        assert!(error("magic", "plx\n").ends_with("not a PLY file"));
+        assert!(error("format", "ply\nformat utf8 1.0\nend_header\n")
+            .ends_with("unknown format 'utf8'"));
+        assert!(error("end", "ply\nformat ascii 1.0\n").ends_with("unexpected end of header"));
+        assert!(error(
+            "orphan",
+            "ply\nformat ascii 1.0\nproperty float x\nend_header\n"
+        )
+        .ends_with("property before any element"));
+        assert!(error(
+            "positions",
+            "ply\nformat ascii 1.0\nelement vertex 0\nend_header\n"
+        )
+        .ends_with("vertices have no positions"));
+    }
+
+    #[test]
+    fn out_of_range_indices() {
+        let header = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+        let err = load_fixture("range", header, b"0 0 0\n1 0 0\n0 1 0\n3 0 1 5\n")
+            .err()
+            .unwrap();
+        assert!(
+            err.ends_with("index 5 is out of range for 3 vertices"),
+            "{}",
+            err
+        );
+    }
+}
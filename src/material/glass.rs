@@ -0,0 +1,39 @@
+use crate::bxdf::{bsdf::BSDF, fresnel, fresnel_specular::FresnelSpecular};
+use crate::film::spectrum::Spectrum;
+use crate::geometry::SurfaceInteraction;
+use crate::material::{perturb, Material, NormalMap};
+use crate::math::*;
+use crate::texture::Texture;
+use bumpalo::Bump;
+use std::sync::Arc;
+
+// Smooth dielectric such as glass or water, with eta its index of refraction relative to the
+// outside, which the shading normal points towards
+#[derive(new)]
+pub struct Glass {
+    kr: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    kt: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    eta: Arc<dyn Texture<Float> + Send + Sync>,
+    bump: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    normal_map: Option<NormalMap>,
+}
+
+impl Material for Glass {
+    fn bsdf<'a>(&self, hit: &SurfaceInteraction, alloc: &'a Bump) -> BSDF<'a> {
+        let perturbed = perturb(&self.bump, &self.normal_map, hit);
+        let hit = perturbed.as_ref().unwrap_or(hit);
+
+        let eta = self.eta.eval(hit);
+        let mut bsdf = BSDF::new(hit);
+        bsdf.eta = eta;
+
+        let r = self.kr.eval(hit);
+        let t = self.kt.eval(hit);
+        if !r.is_black() || !t.is_black() {
+            let fresnel = fresnel::Dielectric::new(1.0, eta);
+            bsdf.push(alloc, FresnelSpecular::new(r, t, fresnel));
+        }
+
+        bsdf
+    }
+}
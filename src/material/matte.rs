@@ -1,7 +1,7 @@
 use crate::bxdf::{bsdf::BSDF, lambertian::Lambertian, oren_nayar::OrenNayar};
 use crate::film::spectrum::Spectrum;
 use crate::geometry::SurfaceInteraction;
-use crate::material::Material;
+use crate::material::{perturb, Material, NormalMap};
 use crate::math::*;
 use crate::texture::Texture;
 use bumpalo::Bump;
@@ -12,12 +12,16 @@ use std::sync::Arc;
 pub struct Matte {
     diffuse: Arc<dyn Texture<Spectrum> + Send + Sync>,
     roughness: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    bump: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    normal_map: Option<NormalMap>,
 }
 
 impl Material for Matte {
     fn bsdf<'a>(&self, hit: &SurfaceInteraction, alloc: &'a Bump) -> BSDF<'a> {
-        let diffuse = self.diffuse.eval(&hit);
-        let roughness = self.roughness.as_ref().map(|r| r.eval(&hit)).unwrap_or(0.0);
+        let perturbed = perturb(&self.bump, &self.normal_map, hit);
+        let hit = perturbed.as_ref().unwrap_or(hit);
+        let diffuse = self.diffuse.eval(hit);
+        let roughness = self.roughness.as_ref().map(|r| r.eval(hit)).unwrap_or(0.0);
 
         let mut bsdf = BSDF::new(hit);
 
@@ -1,7 +1,8 @@
 use crate::bxdf::{bsdf::BSDF, fresnel, specular_reflection::SpecularReflection};
 use crate::film::spectrum::Spectrum;
 use crate::geometry::SurfaceInteraction;
-use crate::material::Material;
+use crate::material::{perturb, Material, NormalMap};
+use crate::math::*;
 use crate::texture::Texture;
 use bumpalo::Bump;
 use std::sync::Arc;
@@ -10,10 +11,14 @@ use std::sync::Arc;
 #[allow(dead_code)]
 pub struct Mirror {
     kr: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    bump: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    normal_map: Option<NormalMap>,
 }
 
 impl Material for Mirror {
     fn bsdf<'a>(&self, hit: &SurfaceInteraction, alloc: &'a Bump) -> BSDF<'a> {
+        let perturbed = perturb(&self.bump, &self.normal_map, hit);
+        let hit = perturbed.as_ref().unwrap_or(hit);
         let mut bsdf = BSDF::new(hit);
 
         let r = self.kr.eval(hit);
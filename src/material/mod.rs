@@ -1,11 +1,142 @@
 use crate::bxdf::bsdf::BSDF;
+use crate::film::spectrum::Spectrum;
 use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+use crate::texture::Texture;
 use bumpalo::Bump;
+use std::sync::Arc;
 
+pub mod glass;
 pub mod matte;
 pub mod mirror;
 pub mod plastic;
+pub mod rough_glass;
 
 pub trait Material {
     fn bsdf<'a>(&self, hit: &SurfaceInteraction, alloc: &'a Bump) -> BSDF<'a>;
 }
+
+// The shading frame a material builds its BSDF in, with the bump map applied first and the
+// normal map on top, or None if the material has neither
+pub fn perturb<'a>(
+    bump_map: &Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    normal_map: &Option<NormalMap>,
+    hit: &SurfaceInteraction<'a>,
+) -> Option<SurfaceInteraction<'a>> {
+    let bumped = bump_map.as_ref().map(|d| bump(d.as_ref(), hit));
+    match normal_map {
+        Some(normal_map) => Some(normal_map.apply(bumped.as_ref().unwrap_or(hit))),
+        None => bumped,
+    }
+}
+
+// Tilts the shading frame as if the surface were displaced along the shading normal by the
+// bump texture. The texture's slope comes from evaluating it a little way along u and v, about
+// a pixel away when there are ray differentials.
+pub fn bump<'a>(
+    displacement: &(dyn Texture<Float> + Send + Sync),
+    hit: &SurfaceInteraction<'a>,
+) -> SurfaceInteraction<'a> {
+    let ns = hit.shading.normal.to_vec();
+    let (dpdu, dpdv) = (hit.shading.dpdu, hit.shading.dpdv);
+    let (dndu, dndv) = (hit.shading.dndu.to_vec(), hit.shading.dndv.to_vec());
+
+    let shifted = |dp: Vec3f, dn: Vec3f, duv: Vec2f| {
+        let mut eval = hit.clone();
+        eval.int.point = hit.int.point + dp;
+        eval.uv = Point2f::new(hit.uv.x + duv.x, hit.uv.y + duv.y);
+        eval.shading.normal =
+            Normal3f::from((dpdu.cross(dpdv) + dn).normalized()).face_forward(hit.shading.normal);
+        displacement.eval(&eval)
+    };
+
+    let step = |d0: Float, d1: Float| {
+        let d = 0.5 * (d0.abs() + d1.abs());
+        if d == 0.0 {
+            0.0005
+        } else {
+            d
+        }
+    };
+    let du = step(hit.dudx, hit.dudy);
+    let dv = step(hit.dvdx, hit.dvdy);
+
+    let displace = displacement.eval(hit);
+    let u_displace = shifted(dpdu * du, dndu * du, Vec2f::new(du, 0.0));
+    let v_displace = shifted(dpdv * dv, dndv * dv, Vec2f::new(0.0, dv));
+
+    let dpdu = dpdu + ns * ((u_displace - displace) / du) + dndu * displace;
+    let dpdv = dpdv + ns * ((v_displace - displace) / dv) + dndv * displace;
+    let normal = dpdu.cross(dpdv).normalized();
+    if !normal.x.is_finite() || !normal.y.is_finite() || !normal.z.is_finite() {
+        return hit.clone();
+    }
+
+    let mut bumped = hit.clone();
+    bumped.shading.normal = Normal3f::from(normal).face_forward(hit.shading.normal);
+    bumped.shading.dpdu = dpdu;
+    bumped.shading.dpdv = dpdv;
+    bumped
+}
+
+// Which way green points in a normal map. OpenGL maps (also used by glTF) have green along +v,
+// DirectX maps have it along -v.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NormalMapConvention {
+    OpenGL,
+    DirectX,
+}
+
+// Tangent space normals stored as RGB, with red along dpdu, green along dpdv and blue along
+// the shading normal. Strength scales how far the normals tilt, one leaves them as they are.
+#[derive(new)]
+pub struct NormalMap {
+    texture: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    convention: NormalMapConvention,
+    strength: Float,
+}
+
+impl NormalMap {
+    pub fn apply<'a>(&self, hit: &SurfaceInteraction<'a>) -> SurfaceInteraction<'a> {
+        let [r, g, b] = self.texture.eval(hit).to_rgb();
+        let green = match self.convention {
+            NormalMapConvention::OpenGL => 2.0 * g - 1.0,
+            NormalMapConvention::DirectX => 1.0 - 2.0 * g,
+        };
+        let x = (2.0 * r - 1.0) * self.strength;
+        let y = green * self.strength;
+        let z = (2.0 * b - 1.0).max(0.0);
+
+        // Orthonormal tangent frame around the shading normal. The bitangent follows the
+        // surface's own dpdv so that mirrored uvs flip it as well.
+        let ns = hit.shading.normal.to_vec();
+        let tangent = hit.shading.dpdu - ns * ns.dot(hit.shading.dpdu);
+        if tangent.length_squared() == 0.0 {
+            return hit.clone();
+        }
+        let tangent = tangent.normalized();
+        let bitangent = ns.cross(tangent);
+        let bitangent = if bitangent.dot(hit.dpdv) < 0.0 {
+            -bitangent
+        } else {
+            bitangent
+        };
+
+        let normal = tangent * x + bitangent * y + ns * z;
+        if normal.length_squared() == 0.0 {
+            return hit.clone();
+        }
+        let normal = normal.normalized();
+
+        // The derivatives are projected onto the new tangent plane so that the BSDF's frame
+        // stays orthonormal around the new normal
+        let mut mapped = hit.clone();
+        mapped.shading.normal = Normal3f::from(normal);
+        mapped.shading.dpdu = hit.shading.dpdu - normal * normal.dot(hit.shading.dpdu);
+        mapped.shading.dpdv = hit.shading.dpdv - normal * normal.dot(hit.shading.dpdv);
+        if mapped.shading.dpdu.length_squared() == 0.0 {
+            mapped.shading.dpdu = normal.coordinate_system().0;
+        }
+        mapped
+    }
+}
@@ -5,7 +5,7 @@ use crate::bxdf::{
 };
 use crate::film::spectrum::Spectrum;
 use crate::geometry::SurfaceInteraction;
-use crate::material::Material;
+use crate::material::{perturb, Material, NormalMap};
 use crate::math::*;
 use crate::texture::Texture;
 use bumpalo::Bump;
@@ -17,10 +17,14 @@ pub struct Plastic {
     kd: Arc<dyn Texture<Spectrum> + Send + Sync>,
     ks: Arc<dyn Texture<Spectrum> + Send + Sync>,
     roughness: Arc<dyn Texture<Float> + Send + Sync>,
+    bump: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    normal_map: Option<NormalMap>,
 }
 
 impl Material for Plastic {
     fn bsdf<'a>(&self, hit: &SurfaceInteraction, alloc: &'a Bump) -> BSDF<'a> {
+        let perturbed = perturb(&self.bump, &self.normal_map, hit);
+        let hit = perturbed.as_ref().unwrap_or(hit);
         let mut bsdf = BSDF::new(hit);
 
         let kd = self.kd.eval(hit);
@@ -0,0 +1,95 @@
+use crate::bxdf::microfacet::{
+    beckmann::Beckmann, trowbridge_reitz::TrowbridgeReitz, MicrofacetDistribution,
+};
+use crate::bxdf::{
+    bsdf::BSDF, fresnel, microfacet, microfacet_reflection::MicrofacetReflection,
+    microfacet_transmission::MicrofacetTransmission,
+};
+use crate::film::spectrum::Spectrum;
+use crate::geometry::SurfaceInteraction;
+use crate::material::{perturb, Material, NormalMap};
+use crate::math::*;
+use crate::texture::Texture;
+use bumpalo::Bump;
+use std::sync::Arc;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Distribution {
+    TrowbridgeReitz,
+    Beckmann,
+}
+
+// Frosted dielectric: microfacet reflection and transmission sharing one distribution, with
+// roughness along dpdu and dpdv
+pub struct RoughGlass {
+    kr: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    kt: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    eta: Arc<dyn Texture<Float> + Send + Sync>,
+    roughness_u: Arc<dyn Texture<Float> + Send + Sync>,
+    roughness_v: Arc<dyn Texture<Float> + Send + Sync>,
+    distribution: Distribution,
+    bump: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    normal_map: Option<NormalMap>,
+}
+
+impl RoughGlass {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        kr: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        kt: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        eta: Arc<dyn Texture<Float> + Send + Sync>,
+        roughness_u: Arc<dyn Texture<Float> + Send + Sync>,
+        roughness_v: Arc<dyn Texture<Float> + Send + Sync>,
+        distribution: Distribution,
+        bump: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+        normal_map: Option<NormalMap>,
+    ) -> Self {
+        Self {
+            kr,
+            kt,
+            eta,
+            roughness_u,
+            roughness_v,
+            distribution,
+            bump,
+            normal_map,
+        }
+    }
+}
+
+impl Material for RoughGlass {
+    fn bsdf<'a>(&self, hit: &SurfaceInteraction, alloc: &'a Bump) -> BSDF<'a> {
+        let perturbed = perturb(&self.bump, &self.normal_map, hit);
+        let hit = perturbed.as_ref().unwrap_or(hit);
+
+        let eta = self.eta.eval(hit);
+        let mut bsdf = BSDF::new(hit);
+        bsdf.eta = eta;
+
+        let r = self.kr.eval(hit);
+        let t = self.kt.eval(hit);
+        if r.is_black() && t.is_black() {
+            return bsdf;
+        }
+
+        let alpha_u = microfacet::roughness_to_alpha(self.roughness_u.eval(hit));
+        let alpha_v = microfacet::roughness_to_alpha(self.roughness_v.eval(hit));
+        let distrib: &'a dyn MicrofacetDistribution = match self.distribution {
+            Distribution::TrowbridgeReitz => alloc.alloc(TrowbridgeReitz::new(alpha_u, alpha_v)),
+            Distribution::Beckmann => alloc.alloc(Beckmann::new(alpha_u, alpha_v)),
+        };
+        let fresnel = fresnel::Dielectric::new(1.0, eta);
+
+        if !r.is_black() {
+            bsdf.push(
+                alloc,
+                MicrofacetReflection::new(r, distrib, alloc.alloc(fresnel)),
+            );
+        }
+        if !t.is_black() {
+            bsdf.push(alloc, MicrofacetTransmission::new(t, distrib, fresnel));
+        }
+
+        bsdf
+    }
+}
@@ -0,0 +1,208 @@
+use super::{gamma, Bounds3f, Float, Point3f, Transform, TransformPair};
+use na::{Matrix3, Matrix4, Projective3, Rotation3, UnitQuaternion, Vector3, U1, U3};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+// A transform at one point in time, split into translation, rotation and scale so that each
+// part can be interpolated on its own
+#[derive(Debug, Copy, Clone)]
+struct Keyframe {
+    time: Float,
+    translation: Vector3<Float>,
+    rotation: UnitQuaternion<Float>,
+    scale: Matrix3<Float>,
+}
+
+impl Keyframe {
+    fn new(time: Float, transform: &Transform) -> Self {
+        let m = transform.matrix();
+        let translation = m.fixed_slice::<U3, U1>(0, 3).into_owned();
+        let linear = m.fixed_slice::<U3, U3>(0, 0).into_owned();
+
+        // Polar decomposition: averaging the matrix with its inverse transpose converges to
+        // the rotation, and whatever is left over is the scale
+        let mut r = linear;
+        for _ in 0..100 {
+            let r_inv_t = match r.try_inverse() {
+                Some(inv) => inv.transpose(),
+                None => break,
+            };
+            let next = (r + r_inv_t) * 0.5;
+            let change = (next - r).amax();
+            r = next;
+            if change < 1e-4 {
+                break;
+            }
+        }
+
+        // Quaternions can't hold a reflection, so it goes into the scale instead
+        if r.determinant() < 0.0 {
+            r = -r;
+        }
+
+        Self {
+            time,
+            translation,
+            rotation: UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(r)),
+            scale: r.transpose() * linear,
+        }
+    }
+
+    fn lerp(&self, other: &Self, t: Float) -> Transform {
+        compose(
+            self.translation.lerp(&other.translation, t),
+            self.rotation.slerp(&other.rotation, t),
+            self.scale * (1.0 - t) + other.scale * t,
+        )
+    }
+
+    fn transform(&self) -> Transform {
+        compose(self.translation, self.rotation, self.scale)
+    }
+}
+
+fn compose(
+    translation: Vector3<Float>,
+    rotation: UnitQuaternion<Float>,
+    scale: Matrix3<Float>,
+) -> Transform {
+    let mut m = Matrix4::identity();
+    m.fixed_slice_mut::<U3, U3>(0, 0)
+        .copy_from(&(rotation.to_rotation_matrix().matrix() * scale));
+    m.fixed_slice_mut::<U3, U1>(0, 3).copy_from(&translation);
+    Transform::new(Projective3::from_matrix_unchecked(m))
+}
+
+// Most transforms are static, so those stay inline rather than behind a pointer
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+enum Motion {
+    Static(TransformPair),
+    // At least two keyframes, sorted by time
+    Keyframed(Arc<[Keyframe]>),
+}
+
+// A transform that can change over the shutter interval. Before the first keyframe and after
+// the last one the transform holds still.
+#[derive(Debug, Clone)]
+pub struct AnimatedTransform {
+    motion: Motion,
+}
+
+impl AnimatedTransform {
+    pub fn new(keyframes: &[(Float, Transform)]) -> Self {
+        assert!(!keyframes.is_empty());
+        debug_assert!(keyframes.windows(2).all(|w| w[0].0 < w[1].0));
+
+        let first = keyframes[0].1;
+        if keyframes.iter().all(|(_, t)| t.matrix() == first.matrix()) {
+            return Self::from(first);
+        }
+
+        let mut keys = keyframes
+            .iter()
+            .map(|(time, t)| Keyframe::new(*time, t))
+            .collect::<Vec<_>>();
+
+        // q and -q are the same rotation, pick the one that takes the short way around
+        for i in 1..keys.len() {
+            if keys[i - 1].rotation.coords.dot(&keys[i].rotation.coords) < 0.0 {
+                keys[i].rotation = UnitQuaternion::new_unchecked(-keys[i].rotation.into_inner());
+            }
+        }
+
+        Self {
+            motion: Motion::Keyframed(keys.into()),
+        }
+    }
+
+    pub fn interpolate(&self, time: Float) -> Transform {
+        match &self.motion {
+            Motion::Static(t) => t.to_global,
+            Motion::Keyframed(keys) => {
+                let i = keys.partition_point(|k| k.time <= time);
+                if i == 0 {
+                    keys[0].transform()
+                } else if i == keys.len() {
+                    keys[i - 1].transform()
+                } else {
+                    let (a, b) = (&keys[i - 1], &keys[i]);
+                    a.lerp(b, (time - a.time) / (b.time - a.time))
+                }
+            }
+        }
+    }
+
+    // Borrowed when nothing moves, so static primitives don't copy their matrices per ray
+    pub fn at(&self, time: Float) -> Cow<'_, TransformPair> {
+        match &self.motion {
+            Motion::Static(t) => Cow::Borrowed(t),
+            Motion::Keyframed(_) => Cow::Owned(TransformPair::from(self.interpolate(time))),
+        }
+    }
+
+    // Bounds of everything the box passes through while it moves
+    pub fn motion_bounds(&self, bounds: Bounds3f) -> Bounds3f {
+        match &self.motion {
+            Motion::Static(t) => t.to_global.apply_bounds(bounds),
+            Motion::Keyframed(keys) => keys
+                .windows(2)
+                .map(|w| segment_bounds(&w[0], &w[1], bounds))
+                .fold(keys[0].transform().apply_bounds(bounds), Bounds3f::union),
+        }
+    }
+}
+
+fn segment_bounds(a: &Keyframe, b: &Keyframe, bounds: Bounds3f) -> Bounds3f {
+    // Translation and scale are interpolated linearly, so without rotation every point moves
+    // along a straight line and the bounds at both ends contain the whole path
+    if a.rotation == b.rotation {
+        return a
+            .transform()
+            .apply_bounds(bounds)
+            .union(b.transform().apply_bounds(bounds));
+    }
+
+    // Rotation doesn't change the distance from the translated origin, and the scaled
+    // distance of any point in the box is at most that of its farthest corner
+    let mut radius: Float = 0.0;
+    for i in 0..8 {
+        let corner = Vector3::new(
+            if i & 1 == 0 {
+                bounds.min.x
+            } else {
+                bounds.max.x
+            },
+            if i & 2 == 0 {
+                bounds.min.y
+            } else {
+                bounds.max.y
+            },
+            if i & 4 == 0 {
+                bounds.min.z
+            } else {
+                bounds.max.z
+            },
+        );
+        radius = radius
+            .max((a.scale * corner).norm())
+            .max((b.scale * corner).norm());
+    }
+    let r = radius * (1.0 + gamma(3));
+
+    let around = |t: &Vector3<Float>| {
+        Bounds3f::new(
+            Point3f::new(t.x - r, t.y - r, t.z - r),
+            Point3f::new(t.x + r, t.y + r, t.z + r),
+        )
+    };
+    around(&a.translation).union(around(&b.translation))
+}
+
+impl From<Transform> for AnimatedTransform {
+    fn from(t: Transform) -> Self {
+        Self {
+            motion: Motion::Static(TransformPair::from(t)),
+        }
+    }
+}
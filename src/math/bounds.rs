@@ -1,6 +1,4 @@
-use super::{Float, Point2, Point2f, Point2i, Point3f, Vec2};
-use bvh::aabb::AABB;
-use num::traits::ToPrimitive;
+use super::{gamma, Float, Point2, Point2f, Point2i, Point3f, Ray, Vec2, Vec3f};
 
 #[derive(new, Default, Copy, Clone, Debug)]
 pub struct Bounds3f {
@@ -113,20 +111,87 @@ impl IntoIterator for Bounds2i {
 }
 
 impl Bounds3f {
-    pub fn to_aabb(self) -> AABB {
-        AABB::with_bounds(
-            na::Point3::new(
-                self.min.x.to_f32().unwrap(),
-                self.min.y.to_f32().unwrap(),
-                self.min.z.to_f32().unwrap(),
+    pub fn union(self, other: Self) -> Self {
+        Self::new(
+            Point3f::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
             ),
-            na::Point3::new(
-                self.max.x.to_f32().unwrap(),
-                self.max.y.to_f32().unwrap(),
-                self.max.z.to_f32().unwrap(),
+            Point3f::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
             ),
         )
     }
+
+    pub fn union_point(self, p: Point3f) -> Self {
+        self.union(Self::new(p, p))
+    }
+
+    pub fn diagonal(&self) -> Vec3f {
+        self.max - self.min
+    }
+
+    pub fn centroid(&self) -> Point3f {
+        self.min + self.diagonal() * 0.5
+    }
+
+    pub fn surface_area(&self) -> Float {
+        let d = self.diagonal();
+        2.0 * (d.x * d.y + d.x * d.z + d.y * d.z)
+    }
+
+    pub fn maximum_extent(&self) -> usize {
+        let d = self.diagonal();
+        if d.x > d.y && d.x > d.z {
+            0
+        } else if d.y > d.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    // Position of p relative to the corners, 0 at min and 1 at max
+    pub fn offset(&self, p: Point3f) -> Vec3f {
+        let mut o = p - self.min;
+        for i in 0..3 {
+            if self.max[i] > self.min[i] {
+                o[i] /= self.max[i] - self.min[i];
+            }
+        }
+        o
+    }
+
+    // Slab test against [0, ray.t_max]. The reciprocal direction and its signs are passed in
+    // so that they can be computed once per ray rather than once per box.
+    pub fn intersect_p(&self, ray: &Ray, inv_dir: Vec3f, dir_is_neg: [bool; 3]) -> bool {
+        let corner = |neg: bool| if neg { self.max } else { self.min };
+        let mut t_min = 0.0;
+        let mut t_max = ray.t_max;
+
+        for i in 0..3 {
+            let t_near = (corner(dir_is_neg[i])[i] - ray.o[i]) * inv_dir[i];
+            // Conservative rounding so that boxes aren't missed at grazing angles
+            let t_far =
+                (corner(!dir_is_neg[i])[i] - ray.o[i]) * inv_dir[i] * (1.0 + 2.0 * gamma(3));
+
+            // Written so that NaNs from 0 * inf leave the interval unchanged
+            if t_near > t_min {
+                t_min = t_near;
+            }
+            if t_far < t_max {
+                t_max = t_far;
+            }
+            if t_min > t_max {
+                return false;
+            }
+        }
+
+        true
+    }
 }
 
 impl From<Bounds2f> for Bounds2i {
@@ -8,13 +8,23 @@ pub fn lerp<T: num::Float>(param: T, min: T, max: T) -> T {
     min * (T::one() - param) + max * param
 }
 
+// Hermite interpolation from 0 at a to 1 at b
+pub fn smooth_step(a: Float, b: Float, x: Float) -> Float {
+    if a == b {
+        return if x < a { 0.0 } else { 1.0 };
+    }
+    let t = clamp((x - a) / (b - a), 0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
 #[allow(dead_code)]
 pub fn power_heuristic(nf: i32, f_pdf: Float, ng: i32, g_pdf: Float) -> Float {
     let (f, g) = (nf as Float * f_pdf, ng as Float * g_pdf);
     (f * f) / (f * f + g * g)
 }
 
-#[allow(dead_code)]
+// Float is already f64 with double_float
+#[allow(dead_code, clippy::useless_conversion)]
 pub fn solve_quadratic(a: Float, b: Float, c: Float) -> Option<(Float, Float)> {
     let (a, b, c) = (f64::from(a), f64::from(b), f64::from(c));
 
@@ -42,6 +52,7 @@ pub fn solve_quadratic(a: Float, b: Float, c: Float) -> Option<(Float, Float)> {
     Some((Float::from_f64(t0).unwrap(), Float::from_f64(t1).unwrap()))
 }
 
+#[allow(clippy::useless_conversion)]
 pub fn solve_efloat_quadratic(a: EFloat, b: EFloat, c: EFloat) -> Option<(EFloat, EFloat)> {
     let discrim =
         f64::from(b.val()) * f64::from(b.val()) - 4.0 * f64::from(a.val()) * f64::from(c.val());
@@ -6,11 +6,9 @@ pub type Float = f64;
 
 #[macro_use]
 pub mod vec_base;
-pub use vec_base::*;
 
 #[macro_use]
 pub mod point_base;
-pub use point_base::*;
 
 pub mod vec2;
 pub use vec2::*;
@@ -51,6 +49,9 @@ pub use ray::*;
 pub mod transform;
 pub use transform::*;
 
+pub mod animated_transform;
+pub use animated_transform::*;
+
 pub mod sample;
 
 pub mod efloat;
@@ -74,36 +74,37 @@ impl LocalRay {
     }
 }
 
-impl LocalRay {
-    pub fn global_t(&self, local_t: Float, global: &Ray) -> Float {
-        let t_scale_factor = global.d.length() / self.d.length();
-        local_t / t_scale_factor
-    }
+// Where rays through the neighbouring pixels in x and y go
+#[derive(Debug, Copy, Clone)]
+pub struct RayDifferentialInfo {
+    pub rx_origin: Point3f,
+    pub ry_origin: Point3f,
+    pub rx_direction: Vec3f,
+    pub ry_direction: Vec3f,
 }
 
-// #[derive(Debug)]
-// pub struct RayDifferentialInfo {
-//     pub rx_origin: Point3f,
-//     pub ry_origin: Point3f,
-//     pub rx_direction: Vec3f,
-//     pub ry_direction: Vec3f,
-// }
+#[derive(Debug, Copy, Clone)]
+pub struct RayDifferential {
+    pub ray: Ray,
+    pub info: Option<RayDifferentialInfo>,
+}
 
-// #[derive(Debug)]
-// pub struct RayDifferential {
-//     pub ray: Ray,
-//     pub info: Option<RayDifferentialInfo>,
-// }
+impl RayDifferential {
+    // Shrinks the footprint when several samples share a pixel
+    pub fn scale_differentials(&mut self, s: Float) {
+        if let Some(info) = &mut self.info {
+            self.info = Some(RayDifferentialInfo {
+                rx_origin: self.ray.o + (info.rx_origin - self.ray.o) * s,
+                ry_origin: self.ray.o + (info.ry_origin - self.ray.o) * s,
+                rx_direction: self.ray.d + (info.rx_direction - self.ray.d) * s,
+                ry_direction: self.ray.d + (info.ry_direction - self.ray.d) * s,
+            });
+        }
+    }
+}
 
-// impl RayDifferential {
-//     pub fn scale_differentials(&mut self, s: Float) {
-//         if let Some(info) = &mut self.info {
-//             self.info = Some(RayDifferentialInfo {
-//                 rx_origin: self.ray.o + (info.rx_origin - self.ray.o) * s,
-//                 ry_origin: self.ray.o + (info.ry_origin - self.ray.o) * s,
-//                 rx_direction: self.ray.d + (info.rx_direction - self.ray.d) * s,
-//                 ry_direction: self.ray.d + (info.ry_direction - self.ray.d) * s,
-//             });
-//         }
-//     }
-// }
+impl From<Ray> for RayDifferential {
+    fn from(ray: Ray) -> Self {
+        Self { ray, info: None }
+    }
+}
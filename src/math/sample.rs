@@ -45,3 +45,23 @@ pub fn uniform_sphere(u: (Float, Float)) -> Vec3f {
 pub fn uniform_cone_pdf(cos_theta_max: Float) -> Float {
     1.0 / (2.0 * Float::PI() * (1.0 - cos_theta_max))
 }
+
+pub fn uniform_triangle(u: (Float, Float)) -> (Float, Float) {
+    let su0 = u.0.sqrt();
+    (1.0 - su0, u.1 * su0)
+}
+
+// Uniform point in a regular polygon with unit circumradius and a corner at angle rotation.
+// Each side and the center make a triangle of the same area, so u.0 picks one and is reused.
+pub fn regular_polygon(u: (Float, Float), sides: u32, rotation: Float) -> Vec2f {
+    let n = sides as Float;
+    let scaled = u.0 * n;
+    let side = scaled.floor().min(n - 1.0);
+    let (b0, b1) = uniform_triangle((scaled - side, u.1));
+
+    let corner = |i: Float| {
+        let angle = rotation + 2.0 * Float::PI() * i / n;
+        Vec2f::new(angle.cos(), angle.sin())
+    };
+    corner(side) * b0 + corner(side + 1.0) * b1
+}
@@ -34,6 +34,10 @@ impl Transform {
         }
     }
 
+    pub fn matrix(&self) -> &Matrix4<Float> {
+        self.m.matrix()
+    }
+
     // TODO: Multiply
     pub fn apply(&self, vec: Vec3f) -> Vec3f {
         let v = self.m * Vector3::new(vec.x, vec.y, vec.z);
@@ -137,12 +141,15 @@ impl Transform {
         (self.apply(v), Vec3f::new(x_abs_err, y_abs_err, z_abs_err))
     }
 
-    pub fn apply_ray_with_error(&self, ray: &Ray) -> (Ray, Vec3f, Vec3f) {
+    // The origin is moved forward by dt to get it past the rounding error, and t_max is
+    // shortened to match. A t found along the returned ray is dt short of the original one.
+    pub fn apply_ray_with_error(&self, ray: &Ray) -> (Ray, Vec3f, Vec3f, Float) {
         let (mut o, o_err) = self.apply_point_with_error(ray.o, Vec3f::default());
         let (d, d_err) = self.apply_vec_with_error(ray.d);
         let len_2 = d.length_squared();
+        let mut dt = 0.0;
         if len_2 > 0.0 {
-            let dt = d.abs().dot(o_err) / len_2;
+            dt = d.abs().dot(o_err) / len_2;
             o += d * dt;
         }
 
@@ -150,19 +157,19 @@ impl Transform {
             Ray {
                 o,
                 d,
-                t_max: ray.t_max,
+                t_max: ray.t_max - dt,
                 time: ray.time,
             },
             o_err,
             d_err,
+            dt,
         )
     }
 
-    // pub fn orthographic(z_near: Float, z_far: Float) -> Self {
-    //     Self {
-    //         m: Orthographic3::new(-1.0, 1.0, -1.0, 1.0, z_near, z_far).to_projective(),
-    //     }
-    // }
+    pub fn orthographic(z_near: Float, z_far: Float) -> Self {
+        Transform::scale(1.0, 1.0, 1.0 / (z_far - z_near))
+            * Transform::translate(Vec3f::new(0.0, 0.0, -z_near))
+    }
 
     pub fn perspective(fov_deg: Float, z_near: Float, z_far: Float) -> Self {
         let persp = Projective3::from_matrix_unchecked(Matrix4::new(
@@ -233,3 +240,36 @@ impl std::ops::Mul for Transform {
         }
     }
 }
+
+// Like Transform, the right hand side is applied first
+impl std::ops::Mul for TransformPair {
+    type Output = Self;
+
+    fn mul(self, other: TransformPair) -> Self {
+        Self {
+            to_global: self.to_global * other.to_global,
+            to_local: other.to_local * self.to_local,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_offset_shortens_t_max() {
+        let transform = Transform::translate(Vec3f::new(-1000.0, 0.0, 0.0));
+        let mut ray = Ray::new(Point3f::new(1000.0, 1.0, 2.0), Vec3f::new(1.0, 0.5, 0.0));
+        ray.t_max = 10.0;
+
+        let (local_ray, _, _, dt) = transform.apply_ray_with_error(&ray);
+        assert!(dt > 0.0);
+        assert_eq!(local_ray.t_max, ray.t_max - dt);
+
+        // Both rays still end at the same point
+        let end = transform.apply_point(ray.o + ray.d * ray.t_max);
+        let local_end = local_ray.o + local_ray.d * local_ray.t_max;
+        assert!((local_end - end).length() < 1e-4);
+    }
+}
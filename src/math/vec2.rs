@@ -9,6 +9,16 @@ pub struct Vec2<T> {
 pub type Vec2f = Vec2<Float>;
 pub type Vec2i = Vec2<i32>;
 
+impl Vec2f {
+    pub fn length_squared(self) -> Float {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn length(self) -> Float {
+        self.length_squared().sqrt()
+    }
+}
+
 impl<T> std::ops::Add for Vec2<T>
 where
     T: std::ops::Add<T, Output = T> + Copy,
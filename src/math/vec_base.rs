@@ -75,6 +75,21 @@ macro_rules! define_vec {
             pub fn reflect(wo: Self, n: Self) -> Self {
                 -wo + n * 2.0 * wo.dot(n)
             }
+
+            // wi points away from the surface and eta is the ratio of the indices of
+            // refraction, incident over transmitted. None on total internal reflection.
+            #[allow(dead_code)]
+            pub fn refract(wi: Self, n: Self, eta: Float) -> Option<Self> {
+                let cos_theta_i = n.dot(wi);
+                let sin2_theta_i = (1.0 - cos_theta_i * cos_theta_i).max(0.0);
+                let sin2_theta_t = eta * eta * sin2_theta_i;
+                if sin2_theta_t >= 1.0 {
+                    return None;
+                }
+
+                let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+                Some(-wi * eta + n * (eta * cos_theta_i - cos_theta_t))
+            }
         }
 
         impl std::cmp::PartialEq for $vecbase {
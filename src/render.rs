@@ -0,0 +1,114 @@
+use crate::film::camera::Camera;
+use crate::film::spectrum::Spectrum;
+use crate::film::Film;
+use crate::integrator::Integrator;
+use crate::math::*;
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use bumpalo::Bump;
+use rayon::prelude::*;
+
+// Renders every tile of the film in parallel on the current rayon thread pool. `progress` is
+// called once for each finished tile.
+pub fn render<F: Fn() + Sync>(
+    scene: &Scene,
+    camera: &(dyn Camera + Send + Sync),
+    film: &Film,
+    sampler: &(dyn Sampler + Send + Sync),
+    integrator: &(dyn Integrator + Send + Sync),
+    progress: F,
+) {
+    // Neighbouring samples are closer than a pixel apart when there are many per pixel
+    let differential_scale = (1.0 / (sampler.samples_per_pixel() as Float).sqrt()).max(0.125);
+
+    let thread_work = |tile_idx: i32| {
+        let mut film_tile = film.get_film_tile(tile_idx);
+
+        let mut sampler = sampler.clone_seed(tile_idx as u64);
+
+        let arena = Bump::new();
+
+        for pixel in film_tile.sample_bounds {
+            sampler.start_pixel(pixel);
+
+            while sampler.next_sample().is_some() {
+                let camera_sample = sampler.get_camera_sample(pixel);
+
+                if let Some((mut ray, weight)) = camera.generate_ray_differential(&camera_sample) {
+                    ray.scale_differentials(differential_scale);
+                    let mut sample = if weight > 0.0 {
+                        integrator.radiance(&ray, scene, sampler.as_mut(), &arena, 0) * weight
+                    } else {
+                        Spectrum::black()
+                    };
+                    if cfg!(debug_assertions) {
+                        if sample.has_nans() {
+                            eprintln!("Sample at pixel {}, {} has NaNs", pixel.x, pixel.y);
+                            sample = Spectrum::black();
+                        } else if sample.has_infs() {
+                            eprintln!("Sample at pixel ({}, {}) has infs", pixel.x, pixel.y);
+                            sample = Spectrum::black();
+                        } else if sample.has_negatives() {
+                            eprintln!("Sample at pixel ({}, {}) has negatives", pixel.x, pixel.y);
+                            sample = Spectrum::black();
+                        } else if sample.y() < 0.0 {
+                            eprintln!(
+                                "Sample at pixel ({}. {}) has negative luminance",
+                                pixel.x, pixel.y
+                            );
+                            sample = Spectrum::black();
+                        }
+                    }
+
+                    film_tile.add_sample(camera_sample.film, &sample);
+                }
+            }
+        }
+
+        film.merge_tile(film_tile);
+        progress();
+    };
+
+    (0..film.ntiles).into_par_iter().for_each(thread_work);
+}
+
+// Linear RGB pixels, row by row from the top left
+pub struct RenderedImage {
+    pub width: usize,
+    pub height: usize,
+    // Values per pixel, always 3 for red, green and blue
+    pub channels: usize,
+    pub pixels: Vec<Float>,
+}
+
+impl RenderedImage {
+    pub fn rgb(&self, x: usize, y: usize) -> [Float; 3] {
+        let i = (y * self.width + x) * self.channels;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+}
+
+// Renders with everything built from the settings and returns the image in memory
+pub fn render_to_buffer(scene: &Scene, settings: &RenderSettings) -> RenderedImage {
+    let film = settings.build_film();
+    let camera = settings.camera.build(&film);
+    let sampler = settings.build_sampler();
+    let integrator = settings.integrator.build();
+
+    render(
+        scene,
+        camera.as_ref(),
+        &film,
+        sampler.as_ref(),
+        integrator.as_ref(),
+        || (),
+    );
+
+    RenderedImage {
+        width: film.full_resolution.x as usize,
+        height: film.full_resolution.y as usize,
+        channels: 3,
+        pixels: film.to_rgb_buffer(),
+    }
+}
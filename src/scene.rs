@@ -1,13 +1,11 @@
-use crate::geometry::primitive::{BVHPrimitive, Primitive};
-use crate::geometry::SurfaceInteraction;
+use crate::geometry::bvh::BVH;
+use crate::geometry::primitive::Primitive;
+use crate::geometry::{Hit, SurfaceInteraction};
 use crate::light::emitter::Emitter;
 use crate::math::*;
-use bvh::bvh::BVH;
-use num::traits::ToPrimitive;
 
 pub struct Scene {
-    bvh: BVH,
-    geometry: Vec<BVHPrimitive>,
+    bvh: BVH<Primitive>,
     pub lights: Vec<Emitter>,
 }
 
@@ -17,50 +15,33 @@ impl Scene {
 
         let mut lights = Vec::new();
 
-        let mut bvh_geom = geometry
+        let bvh_geom = geometry
             .into_iter()
             .filter_map(|g| match g {
                 Primitive::Emitter(ref e) => {
                     let is_delta = e.is_delta();
                     lights.push(e.clone());
                     if !is_delta {
-                        Some(BVHPrimitive::new(g))
+                        Some(g)
                     } else {
                         None
                     }
                 }
-                Primitive::Receiver(_) => Some(BVHPrimitive::new(g)),
+                Primitive::Receiver(_) | Primitive::Instance(_) => Some(g),
             })
-            .collect::<Vec<BVHPrimitive>>();
-
-        let bvh = BVH::build(&mut bvh_geom);
+            .collect::<Vec<Primitive>>();
 
         Self {
-            bvh,
-            geometry: bvh_geom,
+            bvh: BVH::new(bvh_geom),
             lights,
         }
     }
 
-    pub fn intersect(&self, ray: &Ray) -> Option<SurfaceInteraction> {
-        let bvh_ray = bvh::ray::Ray::new(
-            na::Point3::new(
-                ray.o.x.to_f32().unwrap(),
-                ray.o.y.to_f32().unwrap(),
-                ray.o.z.to_f32().unwrap(),
-            ),
-            na::Vector3::new(
-                ray.d.x.to_f32().unwrap(),
-                ray.d.y.to_f32().unwrap(),
-                ray.d.z.to_f32().unwrap(),
-            ),
-        );
-
-        let hits = self.bvh.traverse(&bvh_ray, &self.geometry);
+    pub fn intersect(&self, ray: &Ray) -> Option<SurfaceInteraction<'_>> {
+        self.bvh.intersect(ray).map(|(si, _)| si)
+    }
 
-        hits.iter()
-            .filter_map(|hit| hit.intersect(ray))
-            .min_by_key(|(_, ray_t)| ordered_float::NotNan::new(*ray_t).unwrap())
-            .map(|(si, _)| si)
+    pub fn intersect_p(&self, ray: &Ray) -> bool {
+        self.bvh.intersect_p(ray)
     }
 }
@@ -0,0 +1,223 @@
+use crate::film::camera::{
+    default_screen_window, BrownConrady, Camera, CameraMotion, EquirectangularCamera, FieldOfView,
+    FisheyeCamera, FisheyeMapping, LensSystem, OdsCamera, OrthographicCamera, PerspectiveCamera,
+    RealisticCamera, ThinLens,
+};
+use crate::film::filter::{self, Filter};
+use crate::film::{Film, TILE_SIZE};
+use crate::integrator::{self, Integrator};
+use crate::math::*;
+use crate::sampler::{self, Sampler};
+use std::sync::Arc;
+
+#[derive(Debug, Copy, Clone)]
+pub enum FilterSettings {
+    Mitchell { radius: Float, b: Float, c: Float },
+    Triangle { radius: Float },
+}
+
+impl Default for FilterSettings {
+    fn default() -> Self {
+        FilterSettings::Mitchell {
+            radius: 2.0,
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        }
+    }
+}
+
+impl FilterSettings {
+    pub fn radius(&self) -> Float {
+        match *self {
+            FilterSettings::Mitchell { radius, .. } => radius,
+            FilterSettings::Triangle { radius } => radius,
+        }
+    }
+
+    pub fn with_radius(self, new_radius: Float) -> Self {
+        match self {
+            FilterSettings::Mitchell { b, c, .. } => FilterSettings::Mitchell {
+                radius: new_radius,
+                b,
+                c,
+            },
+            FilterSettings::Triangle { .. } => FilterSettings::Triangle { radius: new_radius },
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn Filter + Send + Sync> {
+        match *self {
+            FilterSettings::Mitchell { radius, b, c } => {
+                Box::new(filter::Mitchell::new(radius, b, c))
+            }
+            FilterSettings::Triangle { radius } => Box::new(filter::Triangle::new(radius)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum IntegratorSettings {
+    Path { min_depth: i32, max_depth: i32 },
+    Whitted { max_depth: i32 },
+    Normals,
+}
+
+impl Default for IntegratorSettings {
+    fn default() -> Self {
+        IntegratorSettings::Path {
+            min_depth: 4,
+            max_depth: 8,
+        }
+    }
+}
+
+impl IntegratorSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            IntegratorSettings::Path {
+                min_depth,
+                max_depth,
+            } => {
+                if min_depth < 0 || max_depth < 1 {
+                    Err("path depths must be positive".to_string())
+                } else if min_depth > max_depth {
+                    Err(format!(
+                        "minimum depth ({}) is greater than maximum depth ({})",
+                        min_depth, max_depth
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            IntegratorSettings::Whitted { max_depth } if max_depth < 1 => {
+                Err("maximum depth must be at least 1".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn Integrator + Send + Sync> {
+        match *self {
+            IntegratorSettings::Path {
+                min_depth,
+                max_depth,
+            } => Box::new(integrator::path::Path::new(min_depth, max_depth)),
+            IntegratorSettings::Whitted { max_depth } => {
+                Box::new(integrator::whitted::Whitted::new(max_depth))
+            }
+            IntegratorSettings::Normals => Box::new(integrator::normals::Normals::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CameraSettings {
+    Perspective {
+        motion: CameraMotion,
+        fov: FieldOfView,
+        distortion: Option<BrownConrady>,
+        overscan: Float,
+        lens: Option<ThinLens>,
+    },
+    Orthographic {
+        motion: CameraMotion,
+        // Defaults to the film's aspect ratio, spanning [-1, 1] along the shorter side
+        screen_window: Option<Bounds2f>,
+        lens: Option<ThinLens>,
+    },
+    Equirectangular {
+        motion: CameraMotion,
+    },
+    Fisheye {
+        motion: CameraMotion,
+        mapping: FisheyeMapping,
+        fov: Float,
+    },
+    Ods {
+        motion: CameraMotion,
+        ipd: Float,
+        convergence_distance: Option<Float>,
+        pole_merge_from: Float,
+        pole_merge_to: Float,
+    },
+    Realistic {
+        motion: CameraMotion,
+        lens: Arc<LensSystem>,
+    },
+}
+
+impl CameraSettings {
+    pub fn build(&self, film: &Film) -> Box<dyn Camera + Send + Sync> {
+        match self {
+            CameraSettings::Perspective {
+                motion,
+                fov,
+                distortion,
+                overscan,
+                lens,
+            } => Box::new(PerspectiveCamera::new(
+                motion.clone(),
+                *fov,
+                *distortion,
+                *overscan,
+                *lens,
+                film,
+            )),
+            CameraSettings::Orthographic {
+                motion,
+                screen_window,
+                lens,
+            } => Box::new(OrthographicCamera::new(
+                motion.clone(),
+                screen_window.unwrap_or_else(|| default_screen_window(film)),
+                *lens,
+                film,
+            )),
+            CameraSettings::Equirectangular { motion } => {
+                Box::new(EquirectangularCamera::new(motion.clone(), film))
+            }
+            CameraSettings::Fisheye {
+                motion,
+                mapping,
+                fov,
+            } => Box::new(FisheyeCamera::new(motion.clone(), *mapping, *fov, film)),
+            CameraSettings::Ods {
+                motion,
+                ipd,
+                convergence_distance,
+                pole_merge_from,
+                pole_merge_to,
+            } => Box::new(OdsCamera::new(
+                motion.clone(),
+                *ipd,
+                *convergence_distance,
+                *pole_merge_from,
+                *pole_merge_to,
+                film,
+            )),
+            CameraSettings::Realistic { motion, lens } => {
+                Box::new(RealisticCamera::new(motion.clone(), lens.clone(), film))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    pub width: i32,
+    pub height: i32,
+    pub filter: FilterSettings,
+    pub spp: u32,
+    pub integrator: IntegratorSettings,
+    pub camera: CameraSettings,
+}
+
+impl RenderSettings {
+    pub fn build_film(&self) -> Film {
+        Film::new(self.width, self.height, TILE_SIZE, self.filter.build())
+    }
+
+    pub fn build_sampler(&self) -> Box<dyn Sampler + Send + Sync> {
+        Box::new(sampler::random::RandomSampler::new(self.spp))
+    }
+}
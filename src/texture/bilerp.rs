@@ -0,0 +1,26 @@
+use super::mapping::TextureMapping2D;
+use super::{Tex, Texel, Texture};
+use crate::geometry::SurfaceInteraction;
+
+// Bilinear blend of four textures across the unit square in texture space, v00 at st (0, 0)
+// and v11 at (1, 1)
+#[derive(new)]
+pub struct BilerpTexture<T> {
+    v00: Tex<T>,
+    v01: Tex<T>,
+    v10: Tex<T>,
+    v11: Tex<T>,
+    mapping: TextureMapping2D,
+}
+
+impl<T: Texel> Texture<T> for BilerpTexture<T> {
+    fn eval(&self, si: &SurfaceInteraction) -> T {
+        self.mapping.eval(si, |c| {
+            let (s, t) = (c.st.x, c.st.y);
+            self.v00.eval(si) * ((1.0 - s) * (1.0 - t))
+                + self.v01.eval(si) * ((1.0 - s) * t)
+                + self.v10.eval(si) * (s * (1.0 - t))
+                + self.v11.eval(si) * (s * t)
+        })
+    }
+}
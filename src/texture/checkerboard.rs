@@ -0,0 +1,82 @@
+use super::mapping::{TextureMapping2D, TextureMapping3D};
+use super::{Tex, Texel, Texture};
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+
+// Alternates between two textures on a grid of unit squares in texture space
+#[derive(new)]
+pub struct Checkerboard2D<T> {
+    tex1: Tex<T>,
+    tex2: Tex<T>,
+    mapping: TextureMapping2D,
+}
+
+// How much of [0, x] is covered by odd squares
+fn odd_integral(x: Float) -> Float {
+    let half = x / 2.0;
+    half.floor() + 2.0 * (half - half.floor() - 0.5).max(0.0)
+}
+
+fn is_odd(x: Float) -> Float {
+    (x.floor() as i64).rem_euclid(2) as Float
+}
+
+// Average of the odd squares over [x - width, x + width]
+fn odd_fraction(x: Float, width: Float) -> Float {
+    if width == 0.0 {
+        is_odd(x)
+    } else {
+        (odd_integral(x + width) - odd_integral(x - width)) / (2.0 * width)
+    }
+}
+
+impl<T: Texel> Texture<T> for Checkerboard2D<T> {
+    fn eval(&self, si: &SurfaceInteraction) -> T {
+        let odd = self.mapping.eval(si, |c| {
+            let (s, t) = (c.st.x, c.st.y);
+            let ds = c.dstdx.x.abs().max(c.dstdy.x.abs());
+            let dt = c.dstdx.y.abs().max(c.dstdy.y.abs());
+            odd_coverage(s, t, ds, dt)
+        });
+
+        if odd == 0.0 {
+            self.tex1.eval(si)
+        } else if odd == 1.0 {
+            self.tex2.eval(si)
+        } else {
+            self.tex1.eval(si) * (1.0 - odd) + self.tex2.eval(si) * odd
+        }
+    }
+}
+
+// How much of the footprint around (s, t) is covered by odd squares
+fn odd_coverage(s: Float, t: Float, ds: Float, dt: Float) -> Float {
+    // Box filter the footprint in closed form, unless it's all in one square anyway
+    if (s - ds).floor() == (s + ds).floor() && (t - dt).floor() == (t + dt).floor() {
+        (is_odd(s) + is_odd(t)) % 2.0
+    } else if ds > 1.0 || dt > 1.0 {
+        0.5
+    } else {
+        let (s_odd, t_odd) = (odd_fraction(s, ds), odd_fraction(t, dt));
+        s_odd + t_odd - 2.0 * s_odd * t_odd
+    }
+}
+
+// Alternates between two textures on a grid of unit cubes in texture space
+#[derive(new)]
+pub struct Checkerboard3D<T> {
+    tex1: Tex<T>,
+    tex2: Tex<T>,
+    mapping: TextureMapping3D,
+}
+
+impl<T: Texel> Texture<T> for Checkerboard3D<T> {
+    fn eval(&self, si: &SurfaceInteraction) -> T {
+        let (p, _, _) = self.mapping.map(si);
+        if (p.x.floor() as i64 + p.y.floor() as i64 + p.z.floor() as i64).rem_euclid(2) == 0 {
+            self.tex1.eval(si)
+        } else {
+            self.tex2.eval(si)
+        }
+    }
+}
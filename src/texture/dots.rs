@@ -0,0 +1,41 @@
+use super::mapping::TextureMapping2D;
+use super::noise::noise;
+use super::{Tex, Texel, Texture};
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+
+// Polka dots on a grid of unit cells in texture space. Noise decides which cells get a dot and
+// nudges it away from the cell's center.
+#[derive(new)]
+pub struct DotsTexture<T> {
+    inside: Tex<T>,
+    outside: Tex<T>,
+    mapping: TextureMapping2D,
+}
+
+impl<T: Texel> Texture<T> for DotsTexture<T> {
+    fn eval(&self, si: &SurfaceInteraction) -> T {
+        let inside = self
+            .mapping
+            .eval(si, |c| if in_dot(c.st.x, c.st.y) { 1.0 } else { 0.0 });
+        if inside > 0.5 {
+            self.inside.eval(si)
+        } else {
+            self.outside.eval(si)
+        }
+    }
+}
+
+fn in_dot(s: Float, t: Float) -> bool {
+    let (s_cell, t_cell) = ((s + 0.5).floor(), (t + 0.5).floor());
+    if noise(Point3f::new(s_cell + 0.5, t_cell + 0.5, 0.5)) <= 0.0 {
+        return false;
+    }
+
+    let radius = 0.35;
+    let max_shift = 0.5 - radius;
+    let s_center = s_cell + max_shift * noise(Point3f::new(s_cell + 1.5, t_cell + 2.8, 0.5));
+    let t_center = t_cell + max_shift * noise(Point3f::new(s_cell + 4.5, t_cell + 9.8, 0.5));
+    let (ds, dt) = (s - s_center, t - t_center);
+    ds * ds + dt * dt < radius * radius
+}
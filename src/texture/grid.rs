@@ -0,0 +1,36 @@
+use super::mapping::TextureMapping2D;
+use super::{Tex, Texel, Texture};
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+
+// Lines along every integer s and t. The line width is a fraction of a cell.
+#[derive(new)]
+pub struct GridTexture<T> {
+    line: Tex<T>,
+    background: Tex<T>,
+    line_width: Float,
+    mapping: TextureMapping2D,
+}
+
+impl<T: Texel> Texture<T> for GridTexture<T> {
+    fn eval(&self, si: &SurfaceInteraction) -> T {
+        let half = self.line_width / 2.0;
+        let on_line = |x: Float| {
+            let f = x - x.floor();
+            f < half || f > 1.0 - half
+        };
+
+        let line = self.mapping.eval(si, |c| {
+            if on_line(c.st.x) || on_line(c.st.y) {
+                1.0
+            } else {
+                0.0
+            }
+        });
+        if line > 0.5 {
+            self.line.eval(si)
+        } else {
+            self.background.eval(si)
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use super::mapping::TextureMapping2D;
+use super::mipmap::{FilterMode, MIPMap, WrapMode};
+use super::{Texel, Texture};
+use crate::film::spectrum::{srgb_to_linear, Spectrum};
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+use std::path::Path;
+
+// An image looked up with the footprint of the ray differentials
+pub struct ImageTexture<T> {
+    mipmap: MIPMap<T>,
+    filter: FilterMode,
+    mapping: TextureMapping2D,
+}
+
+impl<T: Texel> ImageTexture<T> {
+    pub fn new(mipmap: MIPMap<T>, filter: FilterMode, mapping: TextureMapping2D) -> Self {
+        Self {
+            mipmap,
+            filter,
+            mapping,
+        }
+    }
+
+    // PNG, JPEG and the like through the image crate, or OpenEXR with the hdr feature. EXR
+    // files are always linear, `srgb` says whether 8-bit images need decoding.
+    pub fn open(
+        path: &Path,
+        srgb: bool,
+        wrap: WrapMode,
+        filter: FilterMode,
+        mapping: TextureMapping2D,
+    ) -> Result<Self, String> {
+        let is_exr = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exr"));
+        let (width, height, rgb) = if is_exr {
+            read_exr(path)?
+        } else {
+            read_8bit(path, srgb)?
+        };
+
+        let texels = rgb.into_iter().map(T::from_rgb).collect();
+        Ok(Self::new(
+            MIPMap::new(width, height, texels, wrap),
+            filter,
+            mapping,
+        ))
+    }
+}
+
+impl ImageTexture<Spectrum> {
+    // Tightly packed 8-bit RGB triples, top row first, repeating outside of [0, 1]
+    pub fn from_rgb8(width: usize, height: usize, rgb: &[u8], srgb: bool) -> Self {
+        assert_eq!(rgb.len(), width * height * 3);
+        let texels = rgb.chunks(3).map(|p| decode_8bit(p, srgb)).collect();
+        Self::new(
+            MIPMap::new(width, height, texels, WrapMode::Repeat),
+            FilterMode::default(),
+            TextureMapping2D::default(),
+        )
+    }
+}
+
+fn decode_8bit(p: &[u8], srgb: bool) -> Spectrum {
+    let decode = |c: u8| {
+        let c = Float::from(c) / 255.0;
+        if srgb {
+            srgb_to_linear(c)
+        } else {
+            c
+        }
+    };
+    Spectrum::from_rgb(decode(p[0]), decode(p[1]), decode(p[2]))
+}
+
+fn read_8bit(path: &Path, srgb: bool) -> Result<(usize, usize, Vec<Spectrum>), String> {
+    let image = ::image::open(path)
+        .map_err(|e| format!("couldn't load image '{}': {}", path.display(), e))?
+        .to_rgb();
+
+    let (width, height) = image.dimensions();
+    let rgb = image
+        .into_raw()
+        .chunks(3)
+        .map(|p| decode_8bit(p, srgb))
+        .collect();
+    Ok((width as usize, height as usize, rgb))
+}
+
+#[cfg(feature = "hdr")]
+fn read_exr(path: &Path) -> Result<(usize, usize, Vec<Spectrum>), String> {
+    use openexr::{FrameBufferMut, InputFile};
+
+    let error =
+        |e: &dyn std::fmt::Display| format!("couldn't load image '{}': {}", path.display(), e);
+    let mut file = std::fs::File::open(path).map_err(|e| error(&e))?;
+    let mut input = InputFile::new(&mut file).map_err(|e| error(&e))?;
+    let (width, height) = input.header().data_dimensions();
+
+    let mut pixels = vec![(0.0f32, 0.0f32, 0.0f32); (width * height) as usize];
+    {
+        let mut fb = FrameBufferMut::new(width, height);
+        fb.insert_channels(&[("R", 0.0), ("G", 0.0), ("B", 0.0)], &mut pixels);
+        input.read_pixels(&mut fb).map_err(|e| error(&e))?;
+    }
+
+    let rgb = pixels
+        .into_iter()
+        .map(|(r, g, b)| Spectrum::from_rgb(Float::from(r), Float::from(g), Float::from(b)))
+        .collect();
+    Ok((width as usize, height as usize, rgb))
+}
+
+#[cfg(not(feature = "hdr"))]
+fn read_exr(path: &Path) -> Result<(usize, usize, Vec<Spectrum>), String> {
+    Err(format!(
+        "couldn't load image '{}': EXR images need the hdr feature",
+        path.display()
+    ))
+}
+
+impl<T: Texel> Texture<T> for ImageTexture<T> {
+    fn eval(&self, si: &SurfaceInteraction) -> T {
+        // Images are stored top row first, but t points up
+        self.mapping.eval(si, |c| {
+            let st = Point2f::new(c.st.x, 1.0 - c.st.y);
+            let dst0 = Vec2f::new(c.dstdx.x, -c.dstdx.y);
+            let dst1 = Vec2f::new(c.dstdy.x, -c.dstdy.y);
+            self.mipmap.lookup(self.filter, st, dst0, dst1)
+        })
+    }
+}
@@ -0,0 +1,319 @@
+use super::Texel;
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+use num::traits::FloatConst;
+use std::borrow::Cow;
+
+// Texture coordinates and how far they move between neighbouring pixels in x and y
+#[derive(Debug, Copy, Clone)]
+pub struct TexCoord2D {
+    pub st: Point2f,
+    pub dstdx: Vec2f,
+    pub dstdy: Vec2f,
+}
+
+// Where texture space is in the scene. In object space the transform is relative to the shape
+// that was hit, so the texture moves with the shape and with every instance of it.
+#[derive(Debug, Clone)]
+pub struct TexturePlacement {
+    pub transform: TransformPair,
+    pub object_space: bool,
+}
+
+impl TexturePlacement {
+    pub fn texture_to_world(&self, si: &SurfaceInteraction) -> Cow<'_, TransformPair> {
+        if self.object_space {
+            Cow::Owned(si.object_to_world * self.transform)
+        } else {
+            Cow::Borrowed(&self.transform)
+        }
+    }
+}
+
+impl Default for TexturePlacement {
+    fn default() -> Self {
+        Self {
+            transform: TransformPair::from(Transform::scale(1.0, 1.0, 1.0)),
+            object_space: false,
+        }
+    }
+}
+
+// How a surface point becomes 2D texture coordinates. Projections other than UV happen in
+// texture space, which `placement` puts in the scene.
+#[derive(Debug, Clone)]
+pub enum TextureMapping2D {
+    // The surface's own uv, scaled, then rotated by an angle in radians, then offset
+    UV {
+        scale: Vec2f,
+        offset: Vec2f,
+        rotation: Float,
+    },
+    // Longitude and latitude around the origin, with t going from -y up to +y like the
+    // cylindrical mapping, so images aren't upside down
+    Spherical {
+        placement: TexturePlacement,
+    },
+    // Angle around the y axis and height along it
+    Cylindrical {
+        placement: TexturePlacement,
+    },
+    // x and y, projected along z
+    Planar {
+        placement: TexturePlacement,
+    },
+    // Planar projections along each axis, blended by how much the normal faces along them
+    Triplanar {
+        placement: TexturePlacement,
+        sharpness: Float,
+    },
+}
+
+impl Default for TextureMapping2D {
+    fn default() -> Self {
+        TextureMapping2D::UV {
+            scale: Vec2f::new(1.0, 1.0),
+            offset: Vec2f::new(0.0, 0.0),
+            rotation: 0.0,
+        }
+    }
+}
+
+impl TextureMapping2D {
+    // Looks up the texture at the mapped coordinates. Triplanar mapping needs up to three
+    // lookups, so the mapping drives them rather than handing back a single coordinate.
+    pub fn eval<T: Texel>(&self, si: &SurfaceInteraction, lookup: impl Fn(&TexCoord2D) -> T) -> T {
+        match self {
+            TextureMapping2D::UV {
+                scale,
+                offset,
+                rotation,
+            } => {
+                let (sin, cos) = rotation.sin_cos();
+                let map = |u: Float, v: Float| {
+                    let (u, v) = (u * scale.x, v * scale.y);
+                    Vec2f::new(cos * u - sin * v, sin * u + cos * v)
+                };
+                let st = map(si.uv.x, si.uv.y);
+                lookup(&TexCoord2D {
+                    st: Point2f::new(st.x + offset.x, st.y + offset.y),
+                    dstdx: map(si.dudx, si.dvdx),
+                    dstdy: map(si.dudy, si.dvdy),
+                })
+            }
+            TextureMapping2D::Spherical { placement } => {
+                let texture_to_world = placement.texture_to_world(si);
+                lookup(&differentiate(si, &texture_to_world, true, |p| {
+                    let d = Vec3f::from(p).normalized();
+                    let phi = d.z.atan2(d.x);
+                    Point2f::new(
+                        (phi + Float::PI()) * Float::FRAC_1_PI() * 0.5,
+                        1.0 - clamp(d.y, -1.0, 1.0).acos() * Float::FRAC_1_PI(),
+                    )
+                }))
+            }
+            TextureMapping2D::Cylindrical { placement } => {
+                let texture_to_world = placement.texture_to_world(si);
+                lookup(&differentiate(si, &texture_to_world, true, |p| {
+                    Point2f::new(
+                        (p.z.atan2(p.x) + Float::PI()) * Float::FRAC_1_PI() * 0.5,
+                        p.y,
+                    )
+                }))
+            }
+            TextureMapping2D::Planar { placement } => {
+                lookup(&planar(si, &placement.texture_to_world(si), 0, 1))
+            }
+            TextureMapping2D::Triplanar {
+                placement,
+                sharpness,
+            } => {
+                let texture_to_world = &*placement.texture_to_world(si);
+                let n = texture_to_world.to_global.apply_normal(si.shading.normal);
+                let (wx, wy, wz) = (
+                    n.x.abs().powf(*sharpness),
+                    n.y.abs().powf(*sharpness),
+                    n.z.abs().powf(*sharpness),
+                );
+                let total = wx + wy + wz;
+                if total == 0.0 {
+                    return lookup(&planar(si, texture_to_world, 0, 1));
+                }
+
+                // Each projection keeps the other two axes, skipping ones that barely show
+                let mut out = T::default();
+                for &(w, a, b) in &[(wx, 2, 1), (wy, 0, 2), (wz, 0, 1)] {
+                    let w = w / total;
+                    if w > 1e-3 {
+                        out = out + lookup(&planar(si, texture_to_world, a, b)) * w;
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+fn planar(
+    si: &SurfaceInteraction,
+    texture_to_world: &TransformPair,
+    a: usize,
+    b: usize,
+) -> TexCoord2D {
+    let p = texture_to_world.to_local.apply_point(si.int.point);
+    let dpdx = texture_to_world.to_local.apply(si.dpdx);
+    let dpdy = texture_to_world.to_local.apply(si.dpdy);
+    TexCoord2D {
+        st: Point2f::new(p[a], p[b]),
+        dstdx: Vec2f::new(dpdx[a], dpdx[b]),
+        dstdy: Vec2f::new(dpdy[a], dpdy[b]),
+    }
+}
+
+// Maps the point in texture space and finds the derivatives by stepping a little way along
+// dpdx and dpdy. `wraps_s` handles s jumping from one to zero across the seam.
+fn differentiate(
+    si: &SurfaceInteraction,
+    texture_to_world: &TransformPair,
+    wraps_s: bool,
+    map: impl Fn(Point3f) -> Point2f,
+) -> TexCoord2D {
+    let to_texture = |p: Point3f| texture_to_world.to_local.apply_point(p);
+    let st = map(to_texture(si.int.point));
+
+    let delta = 0.1;
+    let derivative = |dp: Vec3f| {
+        let st_delta = map(to_texture(si.int.point + dp * delta));
+        let mut d = Vec2f::new((st_delta.x - st.x) / delta, (st_delta.y - st.y) / delta);
+        if wraps_s {
+            if d.x > 0.5 / delta {
+                d.x -= 1.0 / delta;
+            } else if d.x < -0.5 / delta {
+                d.x += 1.0 / delta;
+            }
+        }
+        d
+    };
+
+    TexCoord2D {
+        st,
+        dstdx: derivative(si.dpdx),
+        dstdy: derivative(si.dpdy),
+    }
+}
+
+// Where 3D textures are evaluated: the surface point in texture space, and how far it moves
+// between neighbouring pixels
+#[derive(Debug, Clone, Default)]
+pub struct TextureMapping3D {
+    pub placement: TexturePlacement,
+}
+
+impl TextureMapping3D {
+    pub fn map(&self, si: &SurfaceInteraction) -> (Point3f, Vec3f, Vec3f) {
+        let texture_to_world = self.placement.texture_to_world(si);
+        let to_local = &texture_to_world.to_local;
+        (
+            to_local.apply_point(si.int.point),
+            to_local.apply(si.dpdx),
+            to_local.apply(si.dpdy),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::film::spectrum::Spectrum;
+    use crate::geometry::{bvh::BVH, instance::Instance, receiver::Receiver, sphere::Sphere, Hit};
+    use crate::material::matte::Matte;
+    use crate::texture::constant::ConstantTexture;
+    use std::sync::Arc;
+
+    fn sphere(to_world: Transform) -> Receiver {
+        let matte = Matte::new(
+            Arc::new(ConstantTexture::new(Spectrum::all(0.5))),
+            None,
+            None,
+            None,
+        );
+        Receiver::new(Arc::new(Sphere::new(1.0)), Arc::new(matte), to_world.into())
+    }
+
+    fn st(mapping: &TextureMapping2D, si: &SurfaceInteraction) -> (Float, Float) {
+        (mapping.eval(si, |c| c.st.x), mapping.eval(si, |c| c.st.y))
+    }
+
+    fn planar(object_space: bool) -> TextureMapping2D {
+        TextureMapping2D::Planar {
+            placement: TexturePlacement {
+                object_space,
+                ..TexturePlacement::default()
+            },
+        }
+    }
+
+    #[test]
+    fn spherical_t_goes_up() {
+        let sphere = sphere(Transform::scale(1.0, 1.0, 1.0));
+        let mapping = TextureMapping2D::Spherical {
+            placement: TexturePlacement::default(),
+        };
+        // Slightly off axis, since exact zeros in the direction trip up the sphere test
+        let down = Ray::new(
+            Point3f::new(0.01, 5.0, 0.02),
+            Vec3f::new(0.001, -1.0, 0.001),
+        );
+        let (si, _) = sphere.intersect(&down).unwrap();
+        assert!(st(&mapping, &si).1 > 0.95);
+
+        let up = Ray::new(
+            Point3f::new(0.01, -5.0, 0.02),
+            Vec3f::new(0.001, 1.0, 0.001),
+        );
+        let (si, _) = sphere.intersect(&up).unwrap();
+        assert!(st(&mapping, &si).1 < 0.05);
+    }
+
+    #[test]
+    fn object_space_follows_the_object() {
+        let ray = Ray::new(Point3f::new(10.1, 0.2, -5.0), Vec3f::new(0.001, 0.001, 1.0));
+        let moved = sphere(Transform::translate(Vec3f::new(10.0, 0.0, 0.0)));
+        let (si, _) = moved.intersect(&ray).unwrap();
+        let (s, t) = st(&planar(false), &si);
+        assert!((s - 10.1).abs() < 0.01 && (t - 0.2).abs() < 0.01);
+        let (s, t) = st(&planar(true), &si);
+        assert!((s - 0.1).abs() < 0.01 && (t - 0.2).abs() < 0.01);
+
+        // Instances move the object space too
+        let instance = Instance::new(
+            Arc::new(BVH::new(vec![moved])),
+            Transform::translate(Vec3f::new(0.0, 20.0, 0.0)).into(),
+        );
+        let ray = Ray {
+            o: Point3f::new(10.1, 20.2, -5.0),
+            ..ray
+        };
+        let (si, _) = instance.intersect(&ray).unwrap();
+        let (s, t) = st(&planar(true), &si);
+        assert!(
+            (s - 0.1).abs() < 0.01 && (t - 0.2).abs() < 0.01,
+            "{} {}",
+            s,
+            t
+        );
+
+        let mapping = TextureMapping3D {
+            placement: TexturePlacement {
+                object_space: true,
+                ..TexturePlacement::default()
+            },
+        };
+        let (p, _, _) = mapping.map(&si);
+        assert!(
+            (p.x - 0.1).abs() < 0.01 && (p.y - 0.2).abs() < 0.01,
+            "{:?}",
+            p
+        );
+    }
+}
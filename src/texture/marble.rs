@@ -0,0 +1,49 @@
+use super::mapping::TextureMapping3D;
+use super::noise::fbm;
+use super::Texture;
+use crate::film::spectrum::Spectrum;
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+
+// Grey veined marble from bands along y, bent by fbm noise
+#[derive(new, Clone)]
+pub struct MarbleTexture {
+    mapping: TextureMapping3D,
+    variation: Float,
+    omega: Float,
+    octaves: u32,
+}
+
+const COLORS: [[Float; 3]; 9] = [
+    [0.58, 0.58, 0.6],
+    [0.58, 0.58, 0.6],
+    [0.58, 0.58, 0.6],
+    [0.5, 0.5, 0.5],
+    [0.6, 0.59, 0.58],
+    [0.58, 0.58, 0.6],
+    [0.58, 0.58, 0.6],
+    [0.2, 0.2, 0.33],
+    [0.58, 0.58, 0.6],
+];
+
+impl Texture<Spectrum> for MarbleTexture {
+    fn eval(&self, si: &SurfaceInteraction) -> Spectrum {
+        let (p, dpdx, dpdy) = self.mapping.map(si);
+        let marble = p.y + self.variation * fbm(p, dpdx, dpdy, self.omega, self.octaves);
+        let t = 0.5 + 0.5 * marble.sin();
+
+        // Cubic Bezier through the four colors around t
+        let segments = COLORS.len() - 3;
+        let first = ((t * segments as Float).floor() as usize).min(segments - 1);
+        let t = t * segments as Float - first as Float;
+        let c = |i: usize| {
+            let c = COLORS[first + i];
+            Spectrum::from_rgb(c[0], c[1], c[2])
+        };
+
+        let blend = |a: Spectrum, b: Spectrum| a * (1.0 - t) + b * t;
+        let (s0, s1, s2) = (blend(c(0), c(1)), blend(c(1), c(2)), blend(c(2), c(3)));
+        let (s0, s1) = (blend(s0, s1), blend(s1, s2));
+        blend(s0, s1) * 1.5
+    }
+}
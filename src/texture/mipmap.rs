@@ -0,0 +1,365 @@
+use super::Texel;
+use crate::math::*;
+
+// What lookups outside of [0, 1] see
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FilterMode {
+    // A single bilinear lookup in the full resolution image, ignoring the footprint
+    Bilinear,
+    // Blends the two levels whose texels are closest to the widest side of the footprint
+    Trilinear,
+    // Elliptically weighted average over the footprint, stretched at most max_anisotropy times
+    Ewa { max_anisotropy: Float },
+}
+
+impl Default for FilterMode {
+    fn default() -> Self {
+        FilterMode::Ewa {
+            max_anisotropy: 8.0,
+        }
+    }
+}
+
+struct Level<T> {
+    width: usize,
+    height: usize,
+    texels: Vec<T>,
+}
+
+// An image and successively halved copies of it, down to a single texel. Coordinates are in
+// [0, 1] with t going down the image.
+pub struct MIPMap<T> {
+    levels: Vec<Level<T>>,
+    wrap: WrapMode,
+}
+
+impl<T: Texel> MIPMap<T> {
+    // Texels go row by row, top row first
+    pub fn new(width: usize, height: usize, texels: Vec<T>, wrap: WrapMode) -> Self {
+        assert!(width > 0 && height > 0);
+        assert_eq!(texels.len(), width * height);
+
+        let mut mipmap = Self {
+            levels: vec![Level {
+                width,
+                height,
+                texels,
+            }],
+            wrap,
+        };
+
+        // Each level box filters 2x2 texels of the one below, an odd texel out at the edge is
+        // filtered with its wrapped neighbour
+        loop {
+            let i = mipmap.levels.len() - 1;
+            let (width, height) = (mipmap.levels[i].width, mipmap.levels[i].height);
+            if width == 1 && height == 1 {
+                break;
+            }
+
+            let (w, h) = (width.div_ceil(2), height.div_ceil(2));
+            let (sx, sy) = (
+                if width > 1 { 2 } else { 1 },
+                if height > 1 { 2 } else { 1 },
+            );
+            let mut texels = Vec::with_capacity(w * h);
+            for y in 0..h as i64 {
+                for x in 0..w as i64 {
+                    let (x0, y0) = (x * sx, y * sy);
+                    let sum = mipmap.texel(i, x0, y0)
+                        + mipmap.texel(i, x0 + sx - 1, y0)
+                        + mipmap.texel(i, x0, y0 + sy - 1)
+                        + mipmap.texel(i, x0 + sx - 1, y0 + sy - 1);
+                    texels.push(sum * 0.25);
+                }
+            }
+            mipmap.levels.push(Level {
+                width: w,
+                height: h,
+                texels,
+            });
+        }
+
+        mipmap
+    }
+
+    pub fn width(&self) -> usize {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> usize {
+        self.levels[0].height
+    }
+
+    fn texel(&self, level: usize, x: i64, y: i64) -> T {
+        let level = &self.levels[level];
+        let wrap = |i: i64, n: usize| {
+            let n = n as i64;
+            match self.wrap {
+                WrapMode::Repeat => i.rem_euclid(n),
+                WrapMode::Clamp => i.max(0).min(n - 1),
+                WrapMode::Mirror => {
+                    let i = i.rem_euclid(2 * n);
+                    if i < n {
+                        i
+                    } else {
+                        2 * n - 1 - i
+                    }
+                }
+            }
+        };
+        level.texels[wrap(y, level.height) as usize * level.width + wrap(x, level.width) as usize]
+    }
+
+    fn bilerp(&self, level: usize, st: Point2f) -> T {
+        let l = &self.levels[level];
+        let x = st.x * l.width as Float - 0.5;
+        let y = st.y * l.height as Float - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (dx, dy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        self.texel(level, x0, y0) * ((1.0 - dx) * (1.0 - dy))
+            + self.texel(level, x0 + 1, y0) * (dx * (1.0 - dy))
+            + self.texel(level, x0, y0 + 1) * ((1.0 - dx) * dy)
+            + self.texel(level, x0 + 1, y0 + 1) * (dx * dy)
+    }
+
+    // dst0 and dst1 are how far st moves between neighbouring pixels in x and y
+    pub fn lookup(&self, filter: FilterMode, st: Point2f, dst0: Vec2f, dst1: Vec2f) -> T {
+        match filter {
+            FilterMode::Bilinear => self.bilerp(0, st),
+            FilterMode::Trilinear => {
+                let width = 2.0
+                    * dst0
+                        .x
+                        .abs()
+                        .max(dst0.y.abs())
+                        .max(dst1.x.abs())
+                        .max(dst1.y.abs());
+                self.trilinear(st, width)
+            }
+            FilterMode::Ewa { max_anisotropy } => self.ewa(st, dst0, dst1, max_anisotropy),
+        }
+    }
+
+    // Level 0 is the full image, so a width of one texel at the top of the pyramid means the
+    // last level
+    fn level_for(&self, width: Float) -> Float {
+        (self.levels.len() - 1) as Float + width.max(1e-8).log2()
+    }
+
+    fn trilinear(&self, st: Point2f, width: Float) -> T {
+        let level = self.level_for(width);
+        let last = self.levels.len() - 1;
+        if level <= 0.0 {
+            self.bilerp(0, st)
+        } else if level >= last as Float {
+            self.texel(last, 0, 0)
+        } else {
+            let i = level.floor() as usize;
+            let delta = level - i as Float;
+            self.bilerp(i, st) * (1.0 - delta) + self.bilerp(i + 1, st) * delta
+        }
+    }
+
+    fn ewa(&self, st: Point2f, mut dst0: Vec2f, mut dst1: Vec2f, max_anisotropy: Float) -> T {
+        if dst0.length_squared() < dst1.length_squared() {
+            std::mem::swap(&mut dst0, &mut dst1);
+        }
+        let major = dst0.length();
+        let mut minor = dst1.length();
+
+        // Very long and thin ellipses touch too many texels, so they're made wider and looked
+        // up in a blurrier level instead
+        if minor * max_anisotropy < major && minor > 0.0 {
+            let scale = major / (minor * max_anisotropy);
+            dst1 = dst1 * scale;
+            minor *= scale;
+        }
+        if minor == 0.0 {
+            return self.bilerp(0, st);
+        }
+
+        let level = self.level_for(minor).max(0.0);
+        let i = level.floor() as usize;
+        let delta = level - i as Float;
+        if delta == 0.0 {
+            self.ewa_level(i, st, dst0, dst1)
+        } else {
+            self.ewa_level(i, st, dst0, dst1) * (1.0 - delta)
+                + self.ewa_level(i + 1, st, dst0, dst1) * delta
+        }
+    }
+
+    fn ewa_level(&self, level: usize, st: Point2f, dst0: Vec2f, dst1: Vec2f) -> T {
+        if level >= self.levels.len() - 1 {
+            return self.texel(self.levels.len() - 1, 0, 0);
+        }
+
+        // The ellipse in texel coordinates as A s^2 + B s t + C t^2 < 1
+        let l = &self.levels[level];
+        let (w, h) = (l.width as Float, l.height as Float);
+        let s = st.x * w - 0.5;
+        let t = st.y * h - 0.5;
+        let (ds0, dt0) = (dst0.x * w, dst0.y * h);
+        let (ds1, dt1) = (dst1.x * w, dst1.y * h);
+
+        let a = dt0 * dt0 + dt1 * dt1 + 1.0;
+        let b = -2.0 * (ds0 * dt0 + ds1 * dt1);
+        let c = ds0 * ds0 + ds1 * ds1 + 1.0;
+        let inv_f = 1.0 / (a * c - b * b * 0.25);
+        let (a, b, c) = (a * inv_f, b * inv_f, c * inv_f);
+
+        let det = -b * b + 4.0 * a * c;
+        let inv_det = 1.0 / det;
+        let s_radius = 2.0 * inv_det * (det * c).sqrt();
+        let t_radius = 2.0 * inv_det * (a * det).sqrt();
+        let (s0, s1) = ((s - s_radius).ceil() as i64, (s + s_radius).floor() as i64);
+        let (t0, t1) = ((t - t_radius).ceil() as i64, (t + t_radius).floor() as i64);
+
+        // Gaussian falloff that reaches zero at the edge of the ellipse
+        let edge = (-2.0 as Float).exp();
+        let mut sum = T::default();
+        let mut weight_sum = 0.0;
+        for it in t0..=t1 {
+            let tt = it as Float - t;
+            for is in s0..=s1 {
+                let ss = is as Float - s;
+                let r2 = a * ss * ss + b * ss * tt + c * tt * tt;
+                if r2 < 1.0 {
+                    let weight = (-2.0 * r2).exp() - edge;
+                    sum = sum + self.texel(level, is, it) * weight;
+                    weight_sum += weight;
+                }
+            }
+        }
+
+        if weight_sum > 0.0 {
+            sum * (1.0 / weight_sum)
+        } else {
+            self.bilerp(level, st)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A horizontal ramp, 0 in the left column and width - 1 in the right
+    fn ramp(width: usize, height: usize, wrap: WrapMode) -> MIPMap<Float> {
+        let texels = (0..width * height).map(|i| (i % width) as Float).collect();
+        MIPMap::new(width, height, texels, wrap)
+    }
+
+    fn sizes(mipmap: &MIPMap<Float>) -> Vec<(usize, usize)> {
+        mipmap.levels.iter().map(|l| (l.width, l.height)).collect()
+    }
+
+    #[test]
+    fn level_sizes() {
+        assert_eq!(
+            sizes(&ramp(8, 4, WrapMode::Repeat)),
+            vec![(8, 4), (4, 2), (2, 1), (1, 1)]
+        );
+        assert_eq!(
+            sizes(&ramp(5, 3, WrapMode::Repeat)),
+            vec![(5, 3), (3, 2), (2, 1), (1, 1)]
+        );
+        assert_eq!(
+            sizes(&ramp(1, 6, WrapMode::Clamp)),
+            vec![(1, 6), (1, 3), (1, 2), (1, 1)]
+        );
+        assert_eq!(sizes(&ramp(1, 1, WrapMode::Clamp)), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn levels_average_the_one_below() {
+        let mipmap = ramp(4, 4, WrapMode::Repeat);
+        assert_eq!(mipmap.levels[1].texels, vec![0.5, 2.5, 0.5, 2.5]);
+        assert_eq!(mipmap.levels[2].texels, vec![1.5]);
+    }
+
+    #[test]
+    fn odd_edges_use_the_wrap_mode() {
+        // The last column of level 1 averages column 4 with the wrapped column 5
+        let last = |wrap| *ramp(5, 1, wrap).levels[1].texels.last().unwrap();
+        assert_eq!(last(WrapMode::Repeat), 2.0);
+        assert_eq!(last(WrapMode::Clamp), 4.0);
+        assert_eq!(last(WrapMode::Mirror), 4.0);
+    }
+
+    #[test]
+    fn wrap_modes() {
+        let texel = |wrap, x| ramp(4, 1, wrap).texel(0, x, 0);
+        let xs = [-5, -1, 0, 3, 4, 6, 9];
+        assert_eq!(
+            xs.iter()
+                .map(|&x| texel(WrapMode::Repeat, x))
+                .collect::<Vec<_>>(),
+            vec![3.0, 3.0, 0.0, 3.0, 0.0, 2.0, 1.0]
+        );
+        assert_eq!(
+            xs.iter()
+                .map(|&x| texel(WrapMode::Clamp, x))
+                .collect::<Vec<_>>(),
+            vec![0.0, 0.0, 0.0, 3.0, 3.0, 3.0, 3.0]
+        );
+        assert_eq!(
+            xs.iter()
+                .map(|&x| texel(WrapMode::Mirror, x))
+                .collect::<Vec<_>>(),
+            vec![3.0, 0.0, 0.0, 3.0, 3.0, 1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn filters_agree_on_constant_images() {
+        let mipmap = MIPMap::new(16, 8, vec![0.25 as Float; 128], WrapMode::Repeat);
+        let st = Point2f::new(0.3, 0.7);
+        for filter in [
+            FilterMode::Bilinear,
+            FilterMode::Trilinear,
+            FilterMode::default(),
+        ] {
+            for (dst0, dst1) in [
+                (Vec2f::new(0.0, 0.0), Vec2f::new(0.0, 0.0)),
+                (Vec2f::new(0.01, 0.0), Vec2f::new(0.0, 0.01)),
+                (Vec2f::new(0.5, 0.1), Vec2f::new(-0.01, 0.02)),
+                (Vec2f::new(4.0, 0.0), Vec2f::new(0.0, 4.0)),
+            ] {
+                let v = mipmap.lookup(filter, st, dst0, dst1);
+                assert!((v - 0.25).abs() < 1e-5, "{:?} gave {}", filter, v);
+            }
+        }
+    }
+
+    #[test]
+    fn wide_footprints_use_coarser_levels() {
+        let mipmap = ramp(8, 8, WrapMode::Clamp);
+        let st = Point2f::new(0.0625, 0.5);
+        let fine = mipmap.lookup(
+            FilterMode::Trilinear,
+            st,
+            Vec2f::new(0.0, 0.0),
+            Vec2f::new(0.0, 0.0),
+        );
+        assert_eq!(fine, 0.0);
+        // Covering the whole image gives the average of the ramp
+        let coarse = mipmap.lookup(
+            FilterMode::Trilinear,
+            st,
+            Vec2f::new(1.0, 0.0),
+            Vec2f::new(0.0, 1.0),
+        );
+        assert_eq!(coarse, 3.5);
+    }
+}
@@ -0,0 +1,28 @@
+use super::{Tex, Texel, Texture};
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+
+// Blends from tex1 at an amount of 0 to tex2 at 1
+#[derive(new)]
+pub struct MixTexture<T> {
+    tex1: Tex<T>,
+    tex2: Tex<T>,
+    amount: Tex<Float>,
+}
+
+impl<T: Texel> Texture<T> for MixTexture<T> {
+    fn eval(&self, si: &SurfaceInteraction) -> T {
+        let amount = self.amount.eval(si);
+        let t1 = if amount != 1.0 {
+            self.tex1.eval(si) * (1.0 - amount)
+        } else {
+            T::default()
+        };
+        let t2 = if amount != 0.0 {
+            self.tex2.eval(si) * amount
+        } else {
+            T::default()
+        };
+        t1 + t2
+    }
+}
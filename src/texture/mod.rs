@@ -1,7 +1,44 @@
+use crate::film::spectrum::Spectrum;
 use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+use std::ops::{Add, Mul};
+use std::sync::Arc;
 
+pub mod bilerp;
+pub mod checkerboard;
 pub mod constant;
+pub mod dots;
+pub mod grid;
+pub mod image;
+pub mod mapping;
+pub mod marble;
+pub mod mipmap;
+pub mod mix;
+pub mod noise;
+pub mod scale;
+pub mod vertex_color;
+pub mod wood;
 
 pub trait Texture<T> {
     fn eval(&self, si: &SurfaceInteraction) -> T;
 }
+
+// A texture shared between materials and the textures built on top of it
+pub type Tex<T> = Arc<dyn Texture<T> + Send + Sync>;
+
+// Values textures can filter and blend, either from images or other textures
+pub trait Texel: Copy + Default + Add<Output = Self> + Mul<Float, Output = Self> {
+    fn from_rgb(rgb: Spectrum) -> Self;
+}
+
+impl Texel for Float {
+    fn from_rgb(rgb: Spectrum) -> Self {
+        rgb.y()
+    }
+}
+
+impl Texel for Spectrum {
+    fn from_rgb(rgb: Spectrum) -> Self {
+        rgb
+    }
+}
@@ -0,0 +1,147 @@
+use super::mapping::TextureMapping3D;
+use super::Texture;
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+
+// Ken Perlin's reference permutation
+const PERM: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn perm(i: usize) -> usize {
+    PERM[i & 255] as usize
+}
+
+// Dot product of the offset into the cell with one of twelve gradient directions
+fn gradient(x: usize, y: usize, z: usize, dx: Float, dy: Float, dz: Float) -> Float {
+    let h = perm(perm(perm(x) + y) + z) & 15;
+    let u = if h < 8 || h == 12 || h == 13 { dx } else { dy };
+    let v = if h < 4 || h == 12 || h == 13 { dy } else { dz };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn fade(t: Float) -> Float {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+// Perlin noise in [-1, 1], zero at every integer point
+pub fn noise(p: Point3f) -> Float {
+    let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (dx, dy, dz) = (p.x - fx, p.y - fy, p.z - fz);
+    let (ix, iy, iz) = (
+        (fx as i64 & 255) as usize,
+        (fy as i64 & 255) as usize,
+        (fz as i64 & 255) as usize,
+    );
+
+    let w000 = gradient(ix, iy, iz, dx, dy, dz);
+    let w100 = gradient(ix + 1, iy, iz, dx - 1.0, dy, dz);
+    let w010 = gradient(ix, iy + 1, iz, dx, dy - 1.0, dz);
+    let w110 = gradient(ix + 1, iy + 1, iz, dx - 1.0, dy - 1.0, dz);
+    let w001 = gradient(ix, iy, iz + 1, dx, dy, dz - 1.0);
+    let w101 = gradient(ix + 1, iy, iz + 1, dx - 1.0, dy, dz - 1.0);
+    let w011 = gradient(ix, iy + 1, iz + 1, dx, dy - 1.0, dz - 1.0);
+    let w111 = gradient(ix + 1, iy + 1, iz + 1, dx - 1.0, dy - 1.0, dz - 1.0);
+
+    let (wx, wy, wz) = (fade(dx), fade(dy), fade(dz));
+    let x00 = lerp(wx, w000, w100);
+    let x10 = lerp(wx, w010, w110);
+    let x01 = lerp(wx, w001, w101);
+    let x11 = lerp(wx, w011, w111);
+    let y0 = lerp(wy, x00, x10);
+    let y1 = lerp(wy, x01, x11);
+    lerp(wz, y0, y1)
+}
+
+// Octaves finer than the footprint of dpdx and dpdy would only alias, so they're left out. The
+// last one that's kept fades in to avoid a seam where the count changes.
+fn octaves(dpdx: Vec3f, dpdy: Vec3f, max_octaves: u32) -> (u32, Float) {
+    let len2 = dpdx.length_squared().max(dpdy.length_squared());
+    let n = clamp(-1.0 - 0.5 * len2.log2(), 0.0, max_octaves as Float);
+    let whole = n.floor();
+    (whole as u32, n - whole)
+}
+
+// Fractional Brownian motion: octaves of noise, each at about twice the frequency and omega
+// times the amplitude of the last
+pub fn fbm(p: Point3f, dpdx: Vec3f, dpdy: Vec3f, omega: Float, max_octaves: u32) -> Float {
+    let (n, partial) = octaves(dpdx, dpdy, max_octaves);
+
+    let (mut sum, mut lambda, mut o) = (0.0, 1.0, 1.0);
+    for _ in 0..n {
+        sum += o * noise(scale_point(p, lambda));
+        lambda *= 1.99;
+        o *= omega;
+    }
+    sum + o * smooth_step(0.3, 0.7, partial) * noise(scale_point(p, lambda))
+}
+
+// Like fbm but with the absolute value of each octave, which gives creases where the noise
+// crosses zero
+pub fn turbulence(p: Point3f, dpdx: Vec3f, dpdy: Vec3f, omega: Float, max_octaves: u32) -> Float {
+    let (n, partial) = octaves(dpdx, dpdy, max_octaves);
+
+    let (mut sum, mut lambda, mut o) = (0.0, 1.0, 1.0);
+    for _ in 0..n {
+        sum += o * noise(scale_point(p, lambda)).abs();
+        lambda *= 1.99;
+        o *= omega;
+    }
+
+    // Octaves that were left out are replaced by their average value
+    sum += o * lerp(
+        smooth_step(0.3, 0.7, partial),
+        0.2,
+        noise(scale_point(p, lambda)).abs(),
+    );
+    for _ in n + 1..max_octaves {
+        o *= omega;
+        sum += o * 0.2;
+    }
+    sum
+}
+
+pub fn scale_point(p: Point3f, s: Float) -> Point3f {
+    Point3f::new(p.x * s, p.y * s, p.z * s)
+}
+
+#[derive(new, Clone)]
+pub struct FBmTexture {
+    mapping: TextureMapping3D,
+    omega: Float,
+    octaves: u32,
+}
+
+impl Texture<Float> for FBmTexture {
+    fn eval(&self, si: &SurfaceInteraction) -> Float {
+        let (p, dpdx, dpdy) = self.mapping.map(si);
+        fbm(p, dpdx, dpdy, self.omega, self.octaves)
+    }
+}
+
+// Turbulence, which looks like crumpled paper when used as a bump map
+#[derive(new, Clone)]
+pub struct WrinkledTexture {
+    mapping: TextureMapping3D,
+    omega: Float,
+    octaves: u32,
+}
+
+impl Texture<Float> for WrinkledTexture {
+    fn eval(&self, si: &SurfaceInteraction) -> Float {
+        let (p, dpdx, dpdy) = self.mapping.map(si);
+        turbulence(p, dpdx, dpdy, self.omega, self.octaves)
+    }
+}
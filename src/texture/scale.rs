@@ -0,0 +1,20 @@
+use super::{Tex, Texel, Texture};
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+
+// One texture multiplied by a float texture
+#[derive(new)]
+pub struct ScaleTexture<T> {
+    tex: Tex<T>,
+    scale: Tex<Float>,
+}
+
+impl<T: Texel> Texture<T> for ScaleTexture<T> {
+    fn eval(&self, si: &SurfaceInteraction) -> T {
+        let scale = self.scale.eval(si);
+        if scale == 0.0 {
+            return T::default();
+        }
+        self.tex.eval(si) * scale
+    }
+}
@@ -0,0 +1,15 @@
+use super::Texture;
+use crate::film::spectrum::Spectrum;
+use crate::geometry::SurfaceInteraction;
+
+// Interpolated mesh vertex colors, or a fallback for surfaces without them
+#[derive(new, Copy, Clone)]
+pub struct VertexColorTexture {
+    fallback: Spectrum,
+}
+
+impl Texture<Spectrum> for VertexColorTexture {
+    fn eval(&self, si: &SurfaceInteraction) -> Spectrum {
+        si.color.unwrap_or(self.fallback)
+    }
+}
@@ -0,0 +1,39 @@
+use super::mapping::TextureMapping3D;
+use super::noise::{fbm, noise, scale_point};
+use super::Texture;
+use crate::film::spectrum::Spectrum;
+use crate::geometry::SurfaceInteraction;
+use crate::math::*;
+
+// Growth rings around the y axis, one per unit in texture space. Each ring goes from light early
+// wood to a darker band of late wood, and noise warps the rings and adds fine grain.
+#[derive(new, Clone)]
+pub struct WoodTexture {
+    light: Spectrum,
+    dark: Spectrum,
+    mapping: TextureMapping3D,
+    distortion: Float,
+    octaves: u32,
+}
+
+impl Texture<Spectrum> for WoodTexture {
+    fn eval(&self, si: &SurfaceInteraction) -> Spectrum {
+        let (p, dpdx, dpdy) = self.mapping.map(si);
+        let warp = fbm(
+            scale_point(p, 0.5),
+            dpdx * 0.5,
+            dpdy * 0.5,
+            0.5,
+            self.octaves,
+        );
+
+        let r = (p.x * p.x + p.z * p.z).sqrt() + self.distortion * warp;
+        let ring = r - r.floor();
+        let late = smooth_step(0.6, 0.9, ring) * (1.0 - smooth_step(0.95, 1.0, ring));
+
+        // Grain is stretched along the trunk
+        let grain = noise(Point3f::new(p.x * 20.0, p.y * 0.5, p.z * 20.0)) * 0.1;
+        let t = clamp(late + grain, 0.0, 1.0);
+        self.light * (1.0 - t) + self.dark * t
+    }
+}